@@ -0,0 +1,203 @@
+//! Generates block, item and entity type tables from the vanilla data reports in
+//! `data/reports/<protocol version>`, made with `java -DbundlerMainClass=net.minecraft.data.Main
+//! -jar server.jar --reports`. The reports used are the ones for the protocol version the
+//! server speaks, so updating the version without new reports fails the build.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+use serde_json::Value;
+
+const STATUS_SOURCE: &str = "src/server/status.rs";
+
+fn main() {
+    println!("cargo:rerun-if-changed={STATUS_SOURCE}");
+    let protocol = protocol_version();
+    let reports = Path::new("data/reports").join(protocol.to_string());
+    println!("cargo:rerun-if-changed={}", reports.display());
+
+    let blocks = read_json(&reports.join("blocks.json"));
+    let registries = read_json(&reports.join("registries.json"));
+
+    let mut out = String::new();
+    writeln!(out, "/// Protocol version the tables were generated for.").unwrap();
+    writeln!(out, "pub const REPORTS_PROTOCOL_VERSION: i32 = {protocol};").unwrap();
+    out.push_str(&generate_blocks(&blocks));
+    write_generated("blocks.rs", out);
+
+    let mut out = generate_registry(&registries, "minecraft:item", "Item", "Items, by protocol ID.");
+    out.push_str(&generate_registry(&registries, "minecraft:entity_type", "EntityType", "Entity types, by protocol ID."));
+    write_generated("registries.rs", out);
+}
+
+fn write_generated(file: &str, code: String) {
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    std::fs::write(Path::new(&out_dir).join(file), code)
+        .unwrap_or_else(|e| panic!("Could not write generated {file}: {e}"));
+}
+
+fn protocol_version() -> i32 {
+    let source = std::fs::read_to_string(STATUS_SOURCE).expect("Could not read the status source");
+    source.lines()
+        .find_map(|line| line.trim().strip_prefix("pub const PROTOCOL_VERSION: i32 = "))
+        .and_then(|value| value.trim_end_matches(';').parse().ok())
+        .expect("PROTOCOL_VERSION is defined in the status source")
+}
+
+fn read_json(path: &Path) -> Value {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Could not read report {}: {e}", path.display()));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("Invalid report {}: {e}", path.display()))
+}
+
+/// `minecraft:oak_log` to `OakLog`.
+fn variant_name(name: &str) -> String {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let variant: String = name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        })
+        .collect();
+    if variant.starts_with(|c: char| c.is_ascii_digit()) { format!("_{variant}") } else { variant }
+}
+
+fn generate_blocks(blocks: &Value) -> String {
+    let blocks = blocks.as_object().expect("blocks report is an object");
+    let mut properties = BTreeSet::new();
+    // properties with the same name and values share their value list
+    let mut value_lists: Vec<Vec<String>> = Vec::new();
+    let mut infos = String::new();
+    let mut default_states = String::new();
+    let mut state_blocks = Vec::new();
+    let mut variants = String::new();
+    let mut names = String::new();
+
+    let first_state = |block: &Value| block["states"].as_array()
+        .and_then(|states| states.iter().filter_map(|state| state["id"].as_u64()).min())
+        .expect("blocks have states");
+    let mut blocks: Vec<_> = blocks.iter().collect();
+    blocks.sort_by_key(|(_, block)| first_state(block));
+
+    for (index, (name, block)) in blocks.iter().enumerate() {
+        let variant = variant_name(name);
+        let states = block["states"].as_array().expect("blocks have states");
+        let first = first_state(block);
+        let default = states.iter()
+            .find(|state| state["default"].as_bool() == Some(true))
+            .and_then(|state| state["id"].as_u64())
+            .unwrap_or(first);
+        for state in states {
+            let id = state["id"].as_u64().expect("states have an id");
+            assert_eq!(id as usize, state_blocks.len(), "states of {name} are not in order");
+            state_blocks.push(index);
+        }
+
+        let property_values: Vec<(&String, Vec<String>)> = block["properties"].as_object()
+            .map(|properties| properties.iter()
+                .map(|(property, values)| (property, values.as_array().expect("property values are a list").iter()
+                    .map(|value| value.as_str().expect("property values are strings").to_string())
+                    .collect()))
+                .collect())
+            .unwrap_or_default();
+        check_state_ids(name, states, first, &property_values);
+
+        let mut block_properties = String::new();
+        for (property, values) in property_values {
+            properties.insert(property.clone());
+            let list = match value_lists.iter().position(|list| *list == values) {
+                Some(list) => list,
+                None => {
+                    value_lists.push(values);
+                    value_lists.len() - 1
+                }
+            };
+            write!(block_properties, "(Property::{}, VALUES_{list}), ", variant_name(property)).unwrap();
+        }
+
+        writeln!(variants, "    {variant},").unwrap();
+        writeln!(names, "        {name:?} => Some(Block::{variant}),").unwrap();
+        writeln!(default_states, "            Block::{variant} => {default},").unwrap();
+        writeln!(infos, "    BlockInfo {{ block: Block::{variant}, name: {name:?}, first_state: {first}, \
+            default_state: {default}, properties: &[{block_properties}] }},").unwrap();
+    }
+
+    let mut out = String::new();
+    writeln!(out, "\n/// Blocks of the game, in registry order.").unwrap();
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum Block {{\n{variants}}}").unwrap();
+    writeln!(out, "\n/// Names of block state properties.").unwrap();
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum Property {{").unwrap();
+    for property in &properties {
+        writeln!(out, "    {},", variant_name(property)).unwrap();
+    }
+    writeln!(out, "}}\n\nimpl Property {{\n    pub fn name(self) -> &'static str {{\n        match self {{").unwrap();
+    for property in &properties {
+        writeln!(out, "            Property::{} => {property:?},", variant_name(property)).unwrap();
+    }
+    writeln!(out, "        }}\n    }}\n\n    pub fn from_name(name: &str) -> Option<Property> {{\n        match name {{").unwrap();
+    for property in &properties {
+        writeln!(out, "            {property:?} => Some(Property::{}),", variant_name(property)).unwrap();
+    }
+    writeln!(out, "            _ => None,\n        }}\n    }}\n}}").unwrap();
+
+    for (i, values) in value_lists.iter().enumerate() {
+        writeln!(out, "const VALUES_{i}: &[&str] = &{values:?};").unwrap();
+    }
+
+    writeln!(out, "\npub const STATE_COUNT: u32 = {};", state_blocks.len()).unwrap();
+    writeln!(out, "\nstatic BLOCKS: [BlockInfo; {}] = [\n{infos}];", blocks.len()).unwrap();
+    let state_blocks: Vec<String> = state_blocks.iter().map(usize::to_string).collect();
+    writeln!(out, "\n/// Index in `BLOCKS` of the block of every state.").unwrap();
+    writeln!(out, "static STATE_BLOCKS: [u16; {}] = [{}];", state_blocks.len(), state_blocks.join(",")).unwrap();
+    writeln!(out, "\nfn block_by_name(name: &str) -> Option<Block> {{\n    match name {{\n{names}        _ => None,\n    }}\n}}").unwrap();
+    writeln!(out, "\nimpl Block {{\n    /// The state a block is placed in without anything else to go by.").unwrap();
+    writeln!(out, "    pub const fn default_state(self) -> BlockState {{\n        match self {{\n{default_states}        }}\n    }}\n}}").unwrap();
+    out
+}
+
+/// The tables find states by counting through property values with the last property
+/// changing fastest, which is how vanilla numbers them.
+fn check_state_ids(name: &str, states: &[Value], first: u64, properties: &[(&String, Vec<String>)]) {
+    for state in states {
+        let mut id = 0;
+        for (property, values) in properties {
+            let value = state["properties"][property.as_str()].as_str().expect("states have every property");
+            let index = values.iter().position(|v| v == value).expect("state values are listed");
+            id = id * values.len() as u64 + index as u64;
+        }
+        assert_eq!(Some(first + id), state["id"].as_u64(), "states of {name} are not numbered by their properties");
+    }
+}
+
+fn generate_registry(registries: &Value, registry: &str, enum_name: &str, doc: &str) -> String {
+    let entries = registries[registry]["entries"].as_object()
+        .unwrap_or_else(|| panic!("registries report has {registry}"));
+    let mut entries: Vec<(&String, u64)> = entries.iter()
+        .map(|(name, entry)| (name, entry["protocol_id"].as_u64().expect("entries have a protocol ID")))
+        .collect();
+    entries.sort_by_key(|(_, id)| *id);
+
+    let mut out = String::new();
+    writeln!(out, "\n/// {doc}\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum {enum_name} {{").unwrap();
+    for (name, id) in &entries {
+        writeln!(out, "    {} = {id},", variant_name(name)).unwrap();
+    }
+    writeln!(out, "}}\n\nimpl {enum_name} {{\n    pub const ALL: &'static [{enum_name}] = &[").unwrap();
+    for (name, _) in &entries {
+        writeln!(out, "        {enum_name}::{},", variant_name(name)).unwrap();
+    }
+    writeln!(out, "    ];\n\n    pub fn name(self) -> &'static str {{\n        match self {{").unwrap();
+    for (name, _) in &entries {
+        writeln!(out, "            {enum_name}::{} => {name:?},", variant_name(name)).unwrap();
+    }
+    writeln!(out, "        }}\n    }}\n\n    /// Looks up an entry like `minecraft:stone`, the namespace can be left out.").unwrap();
+    writeln!(out, "    pub fn from_name(name: &str) -> Option<{enum_name}> {{").unwrap();
+    writeln!(out, "        match name.strip_prefix(\"minecraft:\").unwrap_or(name) {{").unwrap();
+    for (name, _) in &entries {
+        let short = name.strip_prefix("minecraft:").unwrap_or(name);
+        writeln!(out, "            {short:?} => Some({enum_name}::{}),", variant_name(name)).unwrap();
+    }
+    writeln!(out, "            _ => None,\n        }}\n    }}\n\n    pub fn protocol_id(self) -> i32 {{\n        self as i32\n    }}").unwrap();
+    writeln!(out, "\n    pub fn from_protocol_id(id: i32) -> Option<{enum_name}> {{\n        Self::ALL.get(usize::try_from(id).ok()?).copied()\n    }}\n}}").unwrap();
+    out
+}
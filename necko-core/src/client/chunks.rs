@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use necko_protocol::packets::clientbound::play::chunk_batch_finished::ChunkBatchFinishedClientbound;
+use necko_protocol::packets::clientbound::play::chunk_batch_start::ChunkBatchStartClientbound;
+use necko_protocol::packets::clientbound::play::forget_level_chunk::ForgetLevelChunkClientbound;
+use crate::client::Client;
+use crate::server::Server;
+use crate::world::World;
+
+/// How often chunks are sent, once per server tick.
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+/// Smallest view distance a client gets, whatever it asks for.
+pub const MIN_VIEW_DISTANCE: i32 = 2;
+const START_CHUNKS_PER_TICK: f32 = 9.0;
+const MIN_CHUNKS_PER_TICK: f32 = 0.01;
+const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+/// Batches in flight once the client acknowledged its first one.
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+
+/// Chunks a player has and how fast it gets new ones, paced like vanilla: every batch is
+/// acknowledged with the number of chunks per tick the client can keep up with.
+pub struct ChunkTracker {
+    /// The world chunks are sent from, `None` until the player is in one.
+    world: Option<Arc<World>>,
+    /// Render distance from the client settings, `None` until it sent them.
+    pub requested_view_distance: Option<i32>,
+    sent: HashSet<(i32, i32)>,
+    desired_chunks_per_tick: f32,
+    batch_quota: f32,
+    unacknowledged_batches: u32,
+    max_unacknowledged_batches: u32,
+}
+
+impl Default for ChunkTracker {
+    fn default() -> Self {
+        ChunkTracker {
+            world: None,
+            requested_view_distance: None,
+            sent: HashSet::new(),
+            desired_chunks_per_tick: START_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            max_unacknowledged_batches: 1,
+        }
+    }
+}
+
+impl ChunkTracker {
+    /// Starts sending chunks of a world, after the client got the Login or Respawn packet.
+    /// Returns the chunks sent so far, which the client dropped.
+    pub fn start(&mut self, world: Arc<World>) -> Option<SentChunks> {
+        let previous = self.world.replace(world)?;
+        Some(SentChunks { world: previous, chunks: std::mem::take(&mut self.sent) })
+    }
+
+    /// Stops sending chunks, returning the ones sent.
+    fn stop(&mut self) -> Option<SentChunks> {
+        let world = self.world.take()?;
+        Some(SentChunks { world, chunks: std::mem::take(&mut self.sent) })
+    }
+
+    /// The view distance used for the player: what it asked for, capped by the server.
+    pub fn view_distance(&self, server_view_distance: i32) -> i32 {
+        self.requested_view_distance
+            .unwrap_or(server_view_distance)
+            .clamp(MIN_VIEW_DISTANCE, server_view_distance.max(MIN_VIEW_DISTANCE))
+    }
+
+    pub fn has_chunk(&self, world: &World, x: i32, z: i32) -> bool {
+        self.world.as_deref().is_some_and(|sent_from| std::ptr::eq(sent_from, world)) && self.sent.contains(&(x, z))
+    }
+
+    pub fn batch_received(&mut self, desired_chunks_per_tick: f32) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.desired_chunks_per_tick = if desired_chunks_per_tick.is_nan() {
+            MIN_CHUNKS_PER_TICK
+        } else {
+            desired_chunks_per_tick.clamp(MIN_CHUNKS_PER_TICK, MAX_CHUNKS_PER_TICK)
+        };
+        if self.unacknowledged_batches == 0 {
+            self.batch_quota = 1.0
+        }
+        self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES
+    }
+
+    /// How many chunks the next batch may hold, 0 if the client is behind.
+    fn next_batch_size(&mut self) -> usize {
+        if self.unacknowledged_batches >= self.max_unacknowledged_batches {
+            return 0
+        }
+        let desired = self.desired_chunks_per_tick;
+        self.batch_quota = (self.batch_quota + desired).min(desired.max(1.0));
+        if self.batch_quota < 1.0 {
+            return 0
+        }
+        self.batch_quota as usize
+    }
+
+    fn batch_sent(&mut self, size: usize) {
+        self.unacknowledged_batches += 1;
+        self.batch_quota -= size as f32
+    }
+}
+
+/// Chunks a player had of a world, to release once it no longer does.
+pub struct SentChunks {
+    world: Arc<World>,
+    chunks: HashSet<(i32, i32)>,
+}
+
+impl SentChunks {
+    pub async fn release(self) {
+        for (x, z) in self.chunks {
+            self.world.release_chunk(x, z).await
+        }
+    }
+}
+
+/// Whether a chunk is close enough to `center` to be sent, with the same rounded square
+/// as vanilla, which also counts the chunks right outside the view distance.
+pub fn is_within_distance((center_x, center_z): (i32, i32), view_distance: i32, (x, z): (i32, i32)) -> bool {
+    let dx = ((x - center_x).abs() - 1).max(0) as i64;
+    let dz = ((z - center_z).abs() - 1).max(0) as i64;
+    let far = (dx.max(dz) - 1).max(0);
+    let near = dx.min(dz);
+    let view_distance = view_distance as i64;
+    near * near + far * far < view_distance * view_distance
+}
+
+fn distance_squared((center_x, center_z): (i32, i32), (x, z): (i32, i32)) -> i64 {
+    let (dx, dz) = ((x - center_x) as i64, (z - center_z) as i64);
+    dx * dx + dz * dz
+}
+
+/// Streams chunks around a playing client until it disconnects: closest ones first, in
+/// batches the client paces, forgetting the ones that get out of range.
+pub async fn run(client: Arc<Client>, server: Arc<Server>) {
+    let _release = ReleaseChunks(client.clone());
+    let mut interval = tokio::time::interval(SEND_INTERVAL);
+    while !client.closed.load(Ordering::Relaxed) {
+        interval.tick().await;
+        if client.is_playing() {
+            client.update_chunks(&server).await
+        }
+    }
+}
+
+/// Releases the chunks of a client once [`run`] ends, even if it panicked.
+struct ReleaseChunks(Arc<Client>);
+
+impl Drop for ReleaseChunks {
+    fn drop(&mut self) {
+        let client = self.0.clone();
+        tokio::spawn(async move {
+            let sent = client.chunks.lock().await.stop();
+            if let Some(sent) = sent {
+                sent.release().await
+            }
+        });
+    }
+}
+
+impl Client {
+    async fn update_chunks(&self, server: &Arc<Server>) {
+        let mut tracker = self.chunks.lock().await;
+        let Some(world) = tracker.world.clone() else { return };
+        let center = self.player.lock().await.chunk();
+        let view_distance = tracker.view_distance(server.config.view_distance);
+
+        let forgotten: Vec<_> = tracker.sent.iter()
+            .filter(|&&chunk| !is_within_distance(center, view_distance, chunk))
+            .copied()
+            .collect();
+        for (x, z) in forgotten {
+            tracker.sent.remove(&(x, z));
+            self.send_packet(&ForgetLevelChunkClientbound::new(x, z)).await;
+            world.release_chunk(x, z).await
+        }
+
+        let batch_size = tracker.next_batch_size();
+        if batch_size == 0 {
+            return
+        }
+        let (center_x, center_z) = center;
+        let radius = view_distance + 1;
+        let mut missing: Vec<_> = (center_x - radius..=center_x + radius)
+            .flat_map(|x| (center_z - radius..=center_z + radius).map(move |z| (x, z)))
+            .filter(|&chunk| !tracker.sent.contains(&chunk) && is_within_distance(center, view_distance, chunk))
+            .collect();
+        if missing.is_empty() {
+            return
+        }
+        missing.sort_by_key(|&chunk| distance_squared(center, chunk));
+        missing.truncate(batch_size);
+        drop(tracker);
+
+        let mut loading = JoinSet::new();
+        for (x, z) in missing {
+            let world = world.clone();
+            loading.spawn(async move { ((x, z), world.acquire_chunk(x, z).await) });
+        }
+        let mut chunks = loading.join_all().await;
+        chunks.sort_by_key(|&(chunk, _)| distance_squared(center, chunk));
+
+        // the player may have changed worlds while they loaded, the tracker stays locked
+        // while sending so it can't happen between the check and the chunks arriving
+        let mut tracker = self.chunks.lock().await;
+        if !tracker.world.as_ref().is_some_and(|sent_from| Arc::ptr_eq(sent_from, &world)) {
+            drop(tracker);
+            let chunks = chunks.into_iter().map(|(chunk, _)| chunk).collect();
+            return SentChunks { world, chunks }.release().await
+        }
+        tracker.sent.extend(chunks.iter().map(|(chunk, _)| *chunk));
+        tracker.batch_sent(chunks.len());
+        self.send_packet(&ChunkBatchStartClientbound).await;
+        for (_, chunk) in &chunks {
+            let packet = chunk.read().unwrap().to_packet();
+            self.send_packet(&packet).await
+        }
+        self.send_packet(&ChunkBatchFinishedClientbound::new(chunks.len() as i32)).await;
+        drop(tracker);
+        // light that loading them spread to the chunks around them goes out with the next tick
+    }
+}
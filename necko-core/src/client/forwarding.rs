@@ -0,0 +1,218 @@
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, SocketAddr};
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+use necko_protocol::buffer::PacketByteBuffer;
+use necko_protocol::types::{GameProfile, ProfileProperty};
+
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// `MODERN_DEFAULT`, the only version we ask for: address, uuid, name and properties.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardingMode {
+    None,
+    Legacy,
+    Velocity,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ForwardingConfig {
+    pub mode: ForwardingMode,
+    pub velocity_secret: String,
+}
+
+impl Default for ForwardingConfig {
+    fn default() -> Self {
+        ForwardingConfig {
+            mode: ForwardingMode::None,
+            velocity_secret: String::new(),
+        }
+    }
+}
+
+impl ForwardingConfig {
+    /// Anyone who can reach the server could sign forwarding data with an empty secret.
+    pub fn check(&self) -> Result<(), String> {
+        if self.mode == ForwardingMode::Velocity && self.velocity_secret.is_empty() {
+            return Err("Velocity forwarding needs a velocity_secret".into())
+        }
+        Ok(())
+    }
+}
+
+/// Player data smuggled by a proxy, applied once the client reaches login.
+#[derive(Debug, Clone)]
+pub struct ForwardedPlayer {
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    pub name: Option<String>,
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl ForwardedPlayer {
+    pub fn into_profile(self, name: String) -> GameProfile {
+        GameProfile {
+            uuid: self.uuid,
+            name: self.name.unwrap_or(name),
+            properties: self.properties,
+        }
+    }
+
+    pub fn socket_address(&self, port: u16) -> SocketAddr {
+        SocketAddr::new(self.address, port)
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+/// Parses BungeeCord legacy forwarding from the handshake address, which looks like
+/// `host\0ip\0uuid[\0properties json]`. Returns `None` if the address wasn't forwarded.
+pub fn parse_legacy(server_address: &str) -> Result<Option<ForwardedPlayer>, Error> {
+    let parts: Vec<&str> = server_address.split('\0').collect();
+    if parts.len() < 3 {
+        return Ok(None)
+    }
+
+    let address = parts[1].parse::<IpAddr>()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let uuid = Uuid::parse_str(parts[2])
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let properties = match parts.get(3) {
+        Some(json) => serde_json::from_str::<Vec<LegacyProperty>>(json)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            .into_iter()
+            .map(|p| ProfileProperty { name: p.name, value: p.value, signature: p.signature })
+            .collect(),
+        None => vec![]
+    };
+
+    Ok(Some(ForwardedPlayer { address, uuid, name: None, properties }))
+}
+
+/// Verifies and parses the answer to a `velocity:player_info` query. The payload is an
+/// HMAC-SHA256 signature (keyed with the shared forwarding secret) followed by the signed data.
+pub fn parse_velocity(secret: &str, data: Bytes) -> Result<ForwardedPlayer, Error> {
+    if data.len() < 32 {
+        return Err(Error::new(ErrorKind::InvalidData, "Velocity forwarding data is too short"))
+    }
+    let (signature, signed) = data.split_at(32);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    mac.update(signed);
+    mac.verify_slice(signature)
+        .map_err(|_| Error::new(ErrorKind::PermissionDenied, "Invalid Velocity forwarding signature"))?;
+
+    let mut buffer = PacketByteBuffer::new(signed.into());
+    let version = buffer.read_var_int()?.0;
+    if version < VELOCITY_FORWARDING_VERSION as i32 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unsupported Velocity forwarding version"))
+    }
+
+    let address = buffer.read_string_limited(255)?.parse::<IpAddr>()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let uuid = buffer.read_uuid()?;
+    let name = buffer.read_string_limited(16)?;
+    let properties = (0..buffer.read_var_int()?.0)
+        .map(|_| ProfileProperty::read(&mut buffer))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(ForwardedPlayer { address, uuid, name: Some(name), properties })
+}
+
+/// The UUID vanilla assigns to players on offline-mode servers.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let mut bytes = md5::compute(format!("OfflinePlayer:{name}")).0;
+    bytes[6] = (bytes[6] & 0x0F) | 0x30;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    Uuid::from_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use necko_protocol::types::VarInt;
+    use super::*;
+
+    fn velocity_data(secret: &str, uuid: Uuid) -> Bytes {
+        let mut buffer = PacketByteBuffer::new(BytesMut::new());
+        buffer.write_var_int(&VarInt(VELOCITY_FORWARDING_VERSION as i32));
+        buffer.write_string("203.0.113.7");
+        buffer.write_uuid(&uuid);
+        buffer.write_string("Notch");
+        buffer.write_var_int(&VarInt(1));
+        ProfileProperty { name: "textures".into(), value: "abc".into(), signature: Some("sig".into()) }
+            .write(&mut buffer);
+        let signed = buffer.buffer().to_vec();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(&signed);
+        [mac.finalize().into_bytes().to_vec(), signed].concat().into()
+    }
+
+    #[test]
+    fn velocity_round_trip() {
+        let uuid = Uuid::new_v4();
+        let forwarded = parse_velocity("secret", velocity_data("secret", uuid)).unwrap();
+        assert_eq!(forwarded.address, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(forwarded.uuid, uuid);
+        assert_eq!(forwarded.name.as_deref(), Some("Notch"));
+        assert_eq!(forwarded.properties.len(), 1);
+        assert_eq!(forwarded.properties[0].signature.as_deref(), Some("sig"));
+    }
+
+    #[test]
+    fn velocity_rejects_bad_signatures() {
+        let data = velocity_data("other", Uuid::new_v4());
+        assert_eq!(parse_velocity("secret", data).unwrap_err().kind(), ErrorKind::PermissionDenied);
+        let mut data = velocity_data("secret", Uuid::new_v4()).to_vec();
+        *data.last_mut().unwrap() ^= 1;
+        assert!(parse_velocity("secret", data.into()).is_err());
+        assert!(parse_velocity("secret", Bytes::from_static(&[0; 16])).is_err());
+    }
+
+    #[test]
+    fn velocity_needs_a_secret() {
+        let config = ForwardingConfig { mode: ForwardingMode::Velocity, velocity_secret: String::new() };
+        assert!(config.check().is_err());
+        assert!(ForwardingConfig::default().check().is_ok());
+    }
+
+    #[test]
+    fn legacy_address() {
+        let uuid = "069a79f444e94726a5befca90e38aaf5";
+        let forwarded = parse_legacy(&format!("mc.example.com\0198.51.100.4\0{uuid}")).unwrap().unwrap();
+        assert_eq!(forwarded.address, "198.51.100.4".parse::<IpAddr>().unwrap());
+        assert_eq!(forwarded.uuid, Uuid::parse_str(uuid).unwrap());
+        assert!(forwarded.properties.is_empty());
+
+        let properties = r#"[{"name":"textures","value":"abc","signature":"sig"}]"#;
+        let forwarded = parse_legacy(&format!("host\0::1\0{uuid}\0{properties}")).unwrap().unwrap();
+        assert_eq!(forwarded.properties[0].value, "abc");
+    }
+
+    #[test]
+    fn legacy_rejects_bad_fields() {
+        let uuid = "069a79f444e94726a5befca90e38aaf5";
+        assert!(parse_legacy("mc.example.com").unwrap().is_none());
+        assert!(parse_legacy(&format!("host\0not-an-ip\0{uuid}")).is_err());
+        assert!(parse_legacy("host\0::1\0not-a-uuid").is_err());
+        assert!(parse_legacy(&format!("host\0::1\0{uuid}\0{{")).is_err());
+    }
+
+    #[test]
+    fn offline_uuids_match_vanilla() {
+        assert_eq!(offline_uuid("Notch").to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+    }
+}
@@ -1,23 +1,54 @@
 use std::cmp::Ordering;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 use necko_protocol::packets::serverbound::intention;
+use necko_protocol::packets::clientbound::login::custom_query::CustomQueryClientbound;
+use necko_protocol::packets::clientbound::login::login_finished::LoginFinishedClientbound;
 use necko_protocol::packets::clientbound::status::pong_response::PongResponseClientbound;
 use necko_protocol::packets::clientbound::status::status_response::StatusResponseClientbound;
 use necko_protocol::packets::serverbound::intention::NextState;
+use necko_protocol::packets::serverbound::configuration::keep_alive::KeepAliveServerbound as ConfigurationKeepAliveServerbound;
+use necko_protocol::packets::serverbound::login::custom_query_answer::CustomQueryAnswerServerbound;
 use necko_protocol::packets::serverbound::login::hello::HelloServerbound;
+use necko_protocol::packets::serverbound::login::login_acknowledged::LoginAcknowledgedServerbound;
+use necko_protocol::packets::clientbound::configuration::finish_configuration::FinishConfigurationClientbound;
+use necko_protocol::packets::clientbound::configuration::select_known_packs::SelectKnownPacksClientbound;
+use necko_protocol::packets::clientbound::play::command_suggestions::CommandSuggestionsClientbound;
+use necko_protocol::packets::clientbound::play::set_chunk_cache_center::SetChunkCacheCenterClientbound;
+use necko_protocol::packets::serverbound::configuration::client_information::ClientInformationServerbound as ConfigurationClientInformationServerbound;
+use necko_protocol::packets::serverbound::configuration::finish_configuration::FinishConfigurationServerbound;
+use necko_protocol::packets::serverbound::configuration::select_known_packs::SelectKnownPacksServerbound;
+use necko_protocol::packets::serverbound::play::accept_teleportation::AcceptTeleportationServerbound;
+use necko_protocol::packets::serverbound::play::chunk_batch_received::ChunkBatchReceivedServerbound;
+use necko_protocol::packets::serverbound::play::client_command::{ClientCommandServerbound, PERFORM_RESPAWN};
+use necko_protocol::packets::serverbound::play::client_information::ClientInformationServerbound;
+use necko_protocol::packets::serverbound::play::move_player::{
+    MovePlayer, MovePlayerPosRotServerbound, MovePlayerPosServerbound, MovePlayerRotServerbound, MovePlayerStatusOnlyServerbound
+};
+use necko_protocol::packets::serverbound::play::player_loaded::PlayerLoadedServerbound;
+use necko_protocol::packets::serverbound::play::chat_command::ChatCommandServerbound;
+use necko_protocol::packets::serverbound::play::chat_command_signed::ChatCommandSignedServerbound;
+use necko_protocol::packets::serverbound::play::command_suggestion::CommandSuggestionServerbound;
+use necko_protocol::packets::serverbound::play::keep_alive::KeepAliveServerbound;
 use necko_protocol::packets::serverbound::status::ping_request::PingRequestServerbound;
 use necko_protocol::packets::serverbound::status::status_request::StatusRequestServerbound;
 use necko_protocol::packets::{Packet, ServerboundPacket, UnsignedPacket};
-use crate::client::Client;
+use necko_protocol::types::{ClientInformation, GameProfile};
+use crate::client::{Client, LoginStage};
+use crate::command::CommandSender;
+use crate::client::forwarding::{self, ForwardingMode, VELOCITY_CHANNEL, VELOCITY_FORWARDING_VERSION};
 use crate::server::Server;
-use crate::server::status::PROTOCOL_VERSION;
+use crate::registry;
+use crate::server::status::{MINECRAFT_VERSION, PROTOCOL_VERSION};
+
+const VELOCITY_MESSAGE_ID: i32 = 0;
 
 impl Client {
-    pub async fn handle_packet(&self, packet: UnsignedPacket, server: &Server) -> Result<(), Error> {
-        println!("handling {:?}", packet);
+    pub async fn handle_packet(self: &Arc<Self>, packet: UnsignedPacket, server: &Server) -> Result<(), Error> {
+        tracing::trace!(id = packet.id.0, "Handling packet");
         match self.state.load() {
             NextState::None => {
-                self.handle_intention_packet(packet).await
+                self.handle_intention_packet(packet, server).await
             }
             NextState::Status => {
                 self.handle_status_packet(packet, server).await
@@ -25,44 +56,66 @@ impl Client {
             NextState::Login => {
                 self.handle_login_packet(packet, server).await
             }
-            NextState::Transfer => {unimplemented!("got transfer packet ({})", packet.id.0)}
+            NextState::Transfer => Err(Error::new(ErrorKind::InvalidData,
+                format!("Got packet {} after a transfer intention", packet.id.0))),
+            NextState::Configuration => {
+                self.handle_configuration_packet(packet, server).await
+            }
+            NextState::Play => {
+                self.handle_play_packet(packet, server).await
+            }
         }
     }
     
     // HANDSHAKE
     
-    async fn handle_intention_packet(&self, mut packet: UnsignedPacket) -> Result<(), Error> {
+    async fn handle_intention_packet(&self, mut packet: UnsignedPacket, server: &Server) -> Result<(), Error> {
         let buffer = &mut packet.data;
         match packet.id.0 {
             intention::IntentionServerbound::PACKET_ID => {
-                self.handle_intention(intention::IntentionServerbound::read(buffer)?).await
-            }
-            id => {
-                unimplemented!("failed to handle intention packet ({id})")
+                self.handle_intention(intention::IntentionServerbound::read(buffer)?, server).await?
             }
+            id => return Err(unknown_packet("intention", id))
         }
-        println!("from intention: {:?}, {:?}", self.state.load(), self.decoder.lock().await);
         Ok(())
     }
     
-    async fn handle_intention(&self, packet: intention::IntentionServerbound) {
-        log::debug!("Handling Intention packet");
-        self.state.store(packet.next_state);
+    async fn handle_intention(&self, packet: intention::IntentionServerbound, server: &Server) -> Result<(), Error> {
+        tracing::debug!("Handling Intention packet");
+        self.set_state(packet.next_state);
 
-        log::debug!("Got state: {:?}", self.state.load());
+        tracing::debug!("Got state: {:?}", self.state.load());
         let protocol = packet.protocol_version.0;
-        log::debug!("Protocol version: {}", protocol);
+        tracing::debug!("Protocol version: {}", protocol);
         if self.state.load() != NextState::Status {
             match protocol.cmp(&PROTOCOL_VERSION) {
                 Ordering::Less => {
-                    todo!("client outdated")
+                    self.disconnect(&format!("Outdated client! Please use {MINECRAFT_VERSION}")).await;
+                    return Ok(())
                 }
-                Ordering::Equal => log::debug!("OK."),
+                Ordering::Equal => tracing::debug!("OK."),
                 Ordering::Greater => {
-                    todo!("server outdated")
+                    self.disconnect(&format!("Outdated server! I'm still on {MINECRAFT_VERSION}")).await;
+                    return Ok(())
                 }
             }
         }
+
+        if server.config.forwarding.mode == ForwardingMode::Legacy {
+            *self.forwarded.lock().await = forwarding::parse_legacy(&packet.server_address)?;
+        }
+
+        // Velocity only forwards the address once login starts, the throttle waits for it.
+        if self.state.load() == NextState::Login && server.config.forwarding.mode != ForwardingMode::Velocity {
+            let address = match self.forwarded.lock().await.as_ref() {
+                Some(forwarded) => forwarded.address,
+                None => self.address.read().await.ip(),
+            };
+            if !server.throttle.allow_login(address) {
+                self.disconnect("Connection throttled! Please wait before reconnecting.").await
+            }
+        }
+        Ok(())
     }
     
     // STATUS
@@ -73,22 +126,20 @@ impl Client {
             StatusRequestServerbound::PACKET_ID => self.handle_status_request(server).await,
             PingRequestServerbound::PACKET_ID => self
                 .handle_ping_request(PingRequestServerbound::read(buffer)?).await,
-            id => {
-                unimplemented!("failed to handle status packet ({id})")
-            }
+            id => return Err(unknown_packet("status", id))
         }
         Ok(())
     }
     
     async fn handle_ping_request(&self, packet: PingRequestServerbound) {
-        log::debug!("Handling Ping Request packet");
+        tracing::debug!("Handling Ping Request packet");
         self.send_packet(&PongResponseClientbound::new(
             packet.timestamp)).await;
         self.close().await
     }
 
     async fn handle_status_request(&self, server: &Server) {
-        log::debug!("Handling Status Request packet");
+        tracing::debug!("Handling Status Request packet");
         self.send_packet(&StatusResponseClientbound::new(
             server.cached_status.read().await.json.as_str())).await
     }
@@ -100,16 +151,267 @@ impl Client {
         match packet.id.0 {
             HelloServerbound::PACKET_ID => self
                 .handle_hello(HelloServerbound::read(buffer)?, server).await,
-            id => {
-                unimplemented!("failed to handle status packet ({id})")
-            }
+            CustomQueryAnswerServerbound::PACKET_ID => self
+                .handle_custom_query_answer(CustomQueryAnswerServerbound::read(buffer)?, server).await,
+            LoginAcknowledgedServerbound::PACKET_ID => self.handle_login_acknowledged().await,
+            id => return Err(unknown_packet("login", id))
         }
         Ok(())
     }
     
-    async fn handle_hello(&self, _packet: HelloServerbound, _server: &Server) {
-        log::debug!("Handling Hello (login start) packet");
-        
-        unimplemented!("actually")
+    async fn handle_hello(&self, packet: HelloServerbound, server: &Server) {
+        tracing::debug!("Handling Hello (login start) packet");
+        if *self.login.lock().await != LoginStage::Start {
+            return self.disconnect("Unexpected login start.").await
+        }
+
+        let offline = GameProfile {
+            uuid: forwarding::offline_uuid(&packet.name),
+            name: packet.name,
+            properties: vec![],
+        };
+
+        match server.config.forwarding.mode {
+            ForwardingMode::None => self.finish_login(offline, server).await,
+            ForwardingMode::Legacy => {
+                let Some(forwarded) = self.forwarded.lock().await.take() else {
+                    return self.disconnect("If you wish to use IP forwarding, \
+                        please enable it in your BungeeCord config as well!").await
+                };
+                let port = self.address.read().await.port();
+                let address = forwarded.socket_address(port);
+                self.span.record("address", tracing::field::display(address));
+                *self.address.write().await = address;
+                self.finish_login(forwarded.into_profile(offline.name), server).await
+            }
+            ForwardingMode::Velocity => {
+                *self.login.lock().await = LoginStage::Forwarding(offline.name);
+                self.send_packet(&CustomQueryClientbound::new(
+                    VELOCITY_MESSAGE_ID, VELOCITY_CHANNEL, &[VELOCITY_FORWARDING_VERSION])).await
+            }
+        }
+    }
+
+    async fn handle_custom_query_answer(&self, packet: CustomQueryAnswerServerbound, server: &Server) {
+        tracing::debug!("Handling Custom Query Answer (login plugin response) packet");
+
+        let forwarding = &server.config.forwarding;
+        let name = match std::mem::take(&mut *self.login.lock().await) {
+            LoginStage::Forwarding(name) if packet.message_id.0 == VELOCITY_MESSAGE_ID => name,
+            _ => return self.disconnect("Unexpected login plugin response.").await
+        };
+
+        let Some(data) = packet.data else {
+            return self.disconnect("This server requires you to connect with Velocity.").await
+        };
+        let forwarded = match forwarding::parse_velocity(&forwarding.velocity_secret, data) {
+            Ok(forwarded) => forwarded,
+            Err(e) => {
+                tracing::warn!("Failed to verify Velocity forwarding from {}: {}", self.address.read().await, e);
+                return self.disconnect("Unable to verify player details.").await
+            }
+        };
+
+        if !server.throttle.allow_login(forwarded.address) {
+            return self.disconnect("Connection throttled! Please wait before reconnecting.").await
+        }
+        let port = self.address.read().await.port();
+        let address = forwarded.socket_address(port);
+        self.span.record("address", tracing::field::display(address));
+        *self.address.write().await = address;
+        self.finish_login(forwarded.into_profile(name), server).await
+    }
+
+    async fn finish_login(&self, profile: GameProfile, server: &Server) {
+        let address = *self.address.read().await;
+        if let Some(reason) = server.access.login_denial(&profile.uuid, address.ip()) {
+            return self.disconnect(&reason).await
+        }
+
+        self.span.record("username", profile.name.as_str());
+        tracing::info!("{} ({}) logged in from {}", profile.name, profile.uuid, address);
+        server.user_cache.add(&profile);
+        self.permission_level.store(server.access.op_level(&profile.uuid), std::sync::atomic::Ordering::Relaxed);
+        *self.permissions.write().unwrap() = server.permissions.permissions(&profile.uuid, &profile.name);
+        self.send_packet(&LoginFinishedClientbound::new(&profile)).await;
+        *self.profile.write().await = Some(profile);
+        *self.login.lock().await = LoginStage::Finished;
+    }
+
+    async fn handle_login_acknowledged(&self) {
+        tracing::debug!("Handling Login Acknowledged packet");
+        if *self.login.lock().await != LoginStage::Finished {
+            return self.disconnect("Unexpected login acknowledgement.").await
+        }
+        self.set_state(NextState::Configuration);
+        self.send_packet(&SelectKnownPacksClientbound::new(vec![registry::core_pack()])).await
+    }
+
+    // CONFIGURATION
+
+    async fn handle_configuration_packet(self: &Arc<Self>, mut packet: UnsignedPacket, server: &Server) -> Result<(), Error> {
+        let buffer = &mut packet.data;
+        match packet.id.0 {
+            ConfigurationKeepAliveServerbound::PACKET_ID => self
+                .handle_keep_alive(ConfigurationKeepAliveServerbound::read(buffer)?.id).await,
+            SelectKnownPacksServerbound::PACKET_ID => self
+                .handle_select_known_packs(SelectKnownPacksServerbound::read(buffer)?).await,
+            ConfigurationClientInformationServerbound::PACKET_ID => self
+                .handle_client_information(ConfigurationClientInformationServerbound::read(buffer)?.0).await,
+            FinishConfigurationServerbound::PACKET_ID => self
+                .handle_finish_configuration(server).await,
+            id => tracing::debug!("Unhandled configuration packet ({id})")
+        }
+        Ok(())
+    }
+
+    async fn handle_select_known_packs(&self, packet: SelectKnownPacksServerbound) {
+        tracing::debug!("Handling Select Known Packs packet");
+        // registries are sent by name only, so the client has to have the vanilla data
+        if !packet.packs.contains(&registry::core_pack()) {
+            return self.disconnect(&format!("Incompatible client, please use Minecraft {MINECRAFT_VERSION}")).await
+        }
+        for packet in registry::registry_packets() {
+            self.send_packet(&packet).await
+        }
+        self.send_packet(&FinishConfigurationClientbound).await
+    }
+
+    async fn handle_client_information(&self, information: ClientInformation) {
+        tracing::debug!("Handling Client Information packet");
+        self.chunks.lock().await.requested_view_distance = Some(information.view_distance as i32)
+    }
+
+    async fn handle_finish_configuration(self: &Arc<Self>, server: &Server) {
+        tracing::debug!("Handling Finish Configuration packet");
+        self.set_state(NextState::Play);
+        self.join(server).await
+    }
+
+    // PLAY
+
+    async fn handle_play_packet(self: &Arc<Self>, mut packet: UnsignedPacket, server: &Server) -> Result<(), Error> {
+        let buffer = &mut packet.data;
+        match packet.id.0 {
+            KeepAliveServerbound::PACKET_ID => self
+                .handle_keep_alive(KeepAliveServerbound::read(buffer)?.id).await,
+            ChatCommandServerbound::PACKET_ID => self
+                .handle_chat_command(ChatCommandServerbound::read(buffer)?.command, server).await,
+            ChatCommandSignedServerbound::PACKET_ID => self
+                .handle_chat_command(ChatCommandSignedServerbound::read(buffer)?.command, server).await,
+            CommandSuggestionServerbound::PACKET_ID => self
+                .handle_command_suggestion(CommandSuggestionServerbound::read(buffer)?, server).await,
+            ClientCommandServerbound::PACKET_ID => self
+                .handle_client_command(ClientCommandServerbound::read(buffer)?, server).await,
+            AcceptTeleportationServerbound::PACKET_ID => self
+                .handle_accept_teleportation(AcceptTeleportationServerbound::read(buffer)?).await,
+            MovePlayerPosServerbound::PACKET_ID => self
+                .handle_move_player(MovePlayerPosServerbound::read(buffer)?.0).await,
+            MovePlayerPosRotServerbound::PACKET_ID => self
+                .handle_move_player(MovePlayerPosRotServerbound::read(buffer)?.0).await,
+            MovePlayerRotServerbound::PACKET_ID => self
+                .handle_move_player(MovePlayerRotServerbound::read(buffer)?.0).await,
+            MovePlayerStatusOnlyServerbound::PACKET_ID => self
+                .handle_move_player(MovePlayerStatusOnlyServerbound::read(buffer)?.0).await,
+            ClientInformationServerbound::PACKET_ID => self
+                .handle_client_information(ClientInformationServerbound::read(buffer)?.0).await,
+            ChunkBatchReceivedServerbound::PACKET_ID => self
+                .handle_chunk_batch_received(ChunkBatchReceivedServerbound::read(buffer)?).await,
+            PlayerLoadedServerbound::PACKET_ID => tracing::debug!("Player loaded the world"),
+            id => tracing::debug!("Unhandled play packet ({id})")
+        }
+        Ok(())
+    }
+
+    async fn handle_client_command(&self, packet: ClientCommandServerbound, server: &Server) {
+        tracing::debug!("Handling Client Command packet");
+        match packet.action.0 {
+            PERFORM_RESPAWN => self.respawn(server).await,
+            action => tracing::debug!("Unhandled client command ({action})")
+        }
+    }
+
+    async fn handle_accept_teleportation(&self, packet: AcceptTeleportationServerbound) {
+        tracing::debug!("Handling Accept Teleportation packet");
+        let mut player = self.player.lock().await;
+        if player.pending_teleport == Some(packet.teleport_id) {
+            player.pending_teleport = None
+        }
+    }
+
+    async fn handle_chunk_batch_received(&self, packet: ChunkBatchReceivedServerbound) {
+        tracing::trace!(chunks_per_tick = packet.desired_chunks_per_tick, "Handling Chunk Batch Received packet");
+        self.chunks.lock().await.batch_received(packet.desired_chunks_per_tick)
+    }
+
+    async fn handle_move_player(&self, packet: MovePlayer) {
+        let mut player = self.player.lock().await;
+        // movement sent before the client confirmed a teleport is from before it
+        if player.pending_teleport.is_some() {
+            return
+        }
+        let chunk = player.chunk();
+        if let Some(position) = packet.position {
+            player.position = position
+        }
+        if let Some((yaw, pitch)) = packet.rotation {
+            player.yaw = yaw;
+            player.pitch = pitch
+        }
+        let (x, z) = player.chunk();
+        drop(player);
+
+        if (x, z) != chunk {
+            self.send_packet(&SetChunkCacheCenterClientbound::new(x, z)).await
+        }
+    }
+
+    async fn handle_chat_command(self: &Arc<Self>, command: String, server: &Server) {
+        tracing::debug!("Handling Chat Command packet");
+        tracing::info!("{} issued server command: /{}", self.name().await, command);
+        server.commands.execute(server, &CommandSender::Player(self.clone()), &command).await
+    }
+
+    async fn handle_command_suggestion(self: &Arc<Self>, packet: CommandSuggestionServerbound, server: &Server) {
+        tracing::debug!("Handling Command Suggestion packet");
+        let command = packet.command.strip_prefix('/').unwrap_or(&packet.command);
+        let sender = CommandSender::Player(self.clone());
+        let (start, matches) = server.commands.suggest(server, &sender, command).await;
+
+        // offsets are sent in UTF-16 units relative to the text box, which includes the slash
+        let offset = packet.command.len() - command.len();
+        let matches = matches.into_iter().map(|text| (text, None)).collect();
+        self.send_packet(&CommandSuggestionsClientbound::new(packet.id,
+            utf16_len(&packet.command[..start + offset]), utf16_len(&command[start..]), matches)).await
+    }
+}
+
+fn unknown_packet(state: &str, id: i32) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Unknown {state} packet ({id})"))
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::connected_client;
+    use super::*;
+
+    #[tokio::test]
+    async fn login_acknowledged_before_login_success_disconnects() {
+        let (client, _peer) = connected_client().await;
+        client.set_state(NextState::Login);
+        client.handle_login_acknowledged().await;
+        assert!(client.closed.load(std::sync::atomic::Ordering::Relaxed));
+        assert_eq!(client.state.load(), NextState::Login);
+
+        let (client, _peer) = connected_client().await;
+        client.set_state(NextState::Login);
+        *client.login.lock().await = LoginStage::Finished;
+        client.handle_login_acknowledged().await;
+        assert!(!client.closed.load(std::sync::atomic::Ordering::Relaxed));
+        assert_eq!(client.state.load(), NextState::Configuration);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use tokio::sync::MutexGuard;
+use necko_protocol::packets::clientbound::play::game_event::{GameEvent, GameEventClientbound};
+use necko_protocol::packets::clientbound::play::login::LoginClientbound;
+use necko_protocol::packets::clientbound::play::respawn::{RespawnClientbound, KEEP_ATTRIBUTES, KEEP_ENTITY_DATA};
+use necko_protocol::packets::clientbound::play::set_chunk_cache_center::SetChunkCacheCenterClientbound;
+use necko_protocol::packets::clientbound::play::set_default_spawn_position::SetDefaultSpawnPositionClientbound;
+use necko_protocol::packets::clientbound::play::set_health::SetHealthClientbound;
+use necko_protocol::packets::clientbound::play::set_time::SetTimeClientbound;
+use necko_protocol::text::TextComponent;
+use necko_protocol::types::VarInt;
+use crate::client::Client;
+use crate::client::chunks::ChunkTracker;
+use crate::client::player::{chunk_at, PlayerState};
+use crate::client::player_data::PlayerData;
+use crate::server::Server;
+use crate::world::{Weather, World};
+
+impl Client {
+    /// Puts the player into the world once configuration finished: the Login packet, the spawn
+    /// point and a position the client confirms before its movement is accepted. Players who
+    /// played before come back where they left, with what they had.
+    pub async fn join(self: &Arc<Self>, server: &Server) {
+        let uuid = self.profile.read().await.as_ref().map(|profile| profile.uuid).unwrap_or_default();
+        let saved = match PlayerData::load(&server.player_data_directory(), &uuid) {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::error!("Could not load player data of {}: {}", self.name().await, e);
+                return self.disconnect("Could not load your player data.").await
+            }
+        };
+        let entity_id = server.next_entity_id();
+
+        let tracker = self.chunks.lock().await;
+        let mut player = self.player.lock().await;
+        player.entity_id = entity_id;
+        if let Some(saved) = &saved {
+            saved.apply(&mut player);
+        }
+        let saved_world = saved.as_ref().and_then(|saved| {
+            let world = server.world(&saved.dimension);
+            if world.is_none() {
+                tracing::warn!("Unknown saved dimension {}, sending the player to spawn", saved.dimension);
+            }
+            Some((world?.clone(), saved.position, saved.yaw, saved.pitch))
+        });
+        let (world, position, yaw, pitch) = saved_world.unwrap_or_else(|| {
+            let world = server.default_world().clone();
+            let spawn = world.spawn();
+            (world, spawn.center(), spawn.angle, 0.0)
+        });
+        let game_mode = player.game_mode;
+        drop(player);
+
+        self.send_packet(&LoginClientbound {
+            entity_id,
+            hardcore: false,
+            dimensions: server.worlds.iter().map(|world| world.dimension.clone()).collect(),
+            max_players: VarInt(server.config.max_players),
+            view_distance: VarInt(server.config.view_distance),
+            simulation_distance: VarInt(server.config.simulation_distance),
+            reduced_debug_info: false,
+            show_death_screen: true,
+            limited_crafting: false,
+            spawn: world.spawn_info(game_mode),
+            enforces_secure_chat: server.config.enforces_secure_chat,
+        }).await;
+        self.send_commands(server).await;
+        self.enter_world(tracker, world, position, yaw, pitch).await;
+        self.send_inventory().await;
+        if saved.is_some() {
+            let health = self.player.lock().await.health;
+            self.set_health(health).await
+        }
+
+        let name = self.name().await;
+        let (x, y, z) = position;
+        tracing::info!("{}[{}] logged in with entity id {} at ({}, {}, {})",
+            name, self.address.read().await, entity_id, x, y, z);
+        server.broadcast_message(TextComponent::text(format!("{name} joined the game")).color("yellow")).await
+    }
+
+    /// Moves the player to a position in another world with a Respawn packet, which makes the
+    /// client drop everything it had of the world it leaves.
+    pub async fn change_world(&self, world: Arc<World>, position: (f64, f64, f64), yaw: f32, pitch: f32) {
+        self.send_respawn(world, KEEP_ATTRIBUTES | KEEP_ENTITY_DATA, position, yaw, pitch).await
+    }
+
+    /// Brings a dead player back from the death screen at the spawn of the world players join,
+    /// with full health.
+    pub async fn respawn(&self, server: &Server) {
+        let mut player = self.player.lock().await;
+        if player.health > 0.0 {
+            return
+        }
+        let PlayerState { health, food, saturation, .. } = PlayerState::default();
+        (player.health, player.food, player.saturation) = (health, food, saturation);
+        drop(player);
+
+        let world = server.default_world().clone();
+        let spawn = world.spawn();
+        self.send_respawn(world, 0, spawn.center(), spawn.angle, 0.0).await;
+        self.send_packet(&SetHealthClientbound::new(health, food, saturation)).await
+    }
+
+    async fn send_respawn(&self, world: Arc<World>, data_to_keep: u8, position: (f64, f64, f64), yaw: f32, pitch: f32) {
+        let tracker = self.chunks.lock().await;
+        let game_mode = self.player.lock().await.game_mode;
+        self.send_packet(&RespawnClientbound {
+            spawn: world.spawn_info(game_mode),
+            data_to_keep,
+        }).await;
+        self.enter_world(tracker, world, position, yaw, pitch).await
+    }
+
+    /// Teleports the player, into another world if it isn't in `world`.
+    pub async fn teleport_to(&self, server: &Server, world: Arc<World>, position: (f64, f64, f64), yaw: f32, pitch: f32) {
+        if Arc::ptr_eq(&self.world(server).await, &world) {
+            self.teleport(position, yaw, pitch).await
+        } else {
+            self.change_world(world, position, yaw, pitch).await
+        }
+    }
+
+    /// Sends the state of a world the client was just put in and starts sending its chunks.
+    /// The tracker is locked from before the Login or Respawn packet, so no chunk of the world
+    /// the player left is sent after it.
+    async fn enter_world(&self, mut tracker: MutexGuard<'_, ChunkTracker>, world: Arc<World>,
+                         position: (f64, f64, f64), yaw: f32, pitch: f32) {
+        let mut player = self.player.lock().await;
+        player.dimension = world.dimension.clone();
+        player.position = position;
+        drop(player);
+
+        let time = world.time();
+        self.send_packet(&SetTimeClientbound::new(time.game_time, time.day_time, true)).await;
+        self.send_weather(Weather::default(), world.weather()).await;
+        let spawn = world.spawn();
+        self.send_packet(&SetDefaultSpawnPositionClientbound::new(spawn.position, spawn.angle)).await;
+        self.send_packet(&GameEventClientbound::new(GameEvent::WaitForLevelChunks, 0.0)).await;
+        let (x, z) = chunk_at(position);
+        self.send_packet(&SetChunkCacheCenterClientbound::new(x, z)).await;
+        self.teleport(position, yaw, pitch).await;
+
+        let previous = tracker.start(world);
+        drop(tracker);
+        if let Some(previous) = previous {
+            previous.release().await
+        }
+    }
+}
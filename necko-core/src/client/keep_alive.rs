@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use necko_protocol::packets::clientbound::configuration::keep_alive::KeepAliveClientbound as ConfigurationKeepAliveClientbound;
+use necko_protocol::packets::clientbound::play::keep_alive::KeepAliveClientbound;
+use necko_protocol::packets::serverbound::intention::NextState;
+use crate::client::Client;
+use crate::client::timeout::TimeoutConfig;
+
+/// How often the scheduler wakes up to check the keep-alive state.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct KeepAlive {
+    pending: Option<(i64, Instant)>,
+    last_sent: Option<Instant>,
+}
+
+/// Sends Keep Alive packets to a configuring or playing client until it disconnects,
+/// kicking it once an answer takes longer than the configured timeout.
+pub async fn run(client: Arc<Client>, config: TimeoutConfig) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    while !client.closed.load(Ordering::Relaxed) {
+        interval.tick().await;
+
+        let state = client.state.load();
+        if !matches!(state, NextState::Configuration | NextState::Play) {
+            continue
+        }
+
+        let id = {
+            let mut keep_alive = client.keep_alive.lock().await;
+            if let Some((_, sent)) = keep_alive.pending {
+                if sent.elapsed() >= config.keep_alive_timeout() {
+                    drop(keep_alive);
+                    client.disconnect("Timed out").await;
+                    return
+                }
+                continue
+            }
+            if keep_alive.last_sent.is_some_and(|last| last.elapsed() < config.keep_alive_interval()) {
+                continue
+            }
+
+            let id = SystemTime::now().duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as i64);
+            let now = Instant::now();
+            keep_alive.pending = Some((id, now));
+            keep_alive.last_sent = Some(now);
+            id
+        };
+
+        match state {
+            NextState::Configuration => client.send_packet(&ConfigurationKeepAliveClientbound::new(id)).await,
+            _ => client.send_packet(&KeepAliveClientbound::new(id)).await,
+        }
+    }
+}
+
+impl Client {
+    /// Validates an echoed keep-alive ID and updates the smoothed latency.
+    pub async fn handle_keep_alive(&self, id: i64) {
+        let mut keep_alive = self.keep_alive.lock().await;
+        match keep_alive.pending {
+            Some((pending, sent)) if pending == id => {
+                keep_alive.pending = None;
+                let elapsed = sent.elapsed().as_millis() as i32;
+                let latency = self.latency.load(Ordering::Relaxed);
+                self.latency.store((latency * 3 + elapsed) / 4, Ordering::Relaxed);
+            }
+            _ => {
+                drop(keep_alive);
+                self.disconnect("Timed out").await
+            }
+        }
+    }
+}
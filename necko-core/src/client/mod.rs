@@ -1,29 +1,91 @@
 use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
-use tokio::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicUsize, Ordering};
+use std::time::Instant;
+use bytes::BytesMut;
+use tokio::sync::{mpsc, Mutex, Notify, RwLock};
 use tokio::net::TcpStream;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncReadExt;
+use tokio::time::Instant as TokioInstant;
+use tracing::field::Empty;
+use tracing::Span;
 use crossbeam::atomic::AtomicCell;
 use necko_protocol::decoder::Decoder;
 use necko_protocol::encoder::Encoder;
-use necko_protocol::packets::{ClientboundPacket, UnsignedPacket};
+use necko_protocol::metrics::METRICS;
+use necko_protocol::packets::{ClientboundPacket, Packet, UnsignedPacket};
+use necko_protocol::nbt::Tag;
+use necko_protocol::packets::clientbound::configuration::disconnect::DisconnectClientbound as ConfigurationDisconnectClientbound;
+use necko_protocol::packets::clientbound::login::login_disconnect::LoginDisconnectClientbound;
+use necko_protocol::packets::clientbound::play::disconnect::DisconnectClientbound;
 use necko_protocol::packets::serverbound::intention::NextState;
+use necko_protocol::packets::serverbound::play::keep_alive::KeepAliveServerbound;
+use necko_protocol::types::GameProfile;
+use crate::client::chunks::ChunkTracker;
+use crate::client::forwarding::ForwardedPlayer;
+use crate::client::keep_alive::KeepAlive;
+use crate::client::player::PlayerState;
+use crate::client::writer::MAX_QUEUED_BYTES;
+use crate::command::CommandSender;
+use crate::permission::PermissionSet;
 use crate::server::Server;
 
 pub mod handlers;
+pub mod proxy;
+pub mod forwarding;
+pub mod keep_alive;
+pub mod timeout;
+pub mod player;
+pub mod player_data;
+pub mod join;
+pub mod chunks;
+pub mod writer;
+
+/// How far a login got, so its packets are only accepted in the order vanilla sends them.
+#[derive(Debug, Default, PartialEq)]
+pub enum LoginStage {
+    #[default]
+    Start,
+    /// Waiting for Velocity to forward the player who started logging in with this name.
+    Forwarding(String),
+    /// Login Success was sent.
+    Finished,
+}
 
 pub struct Client {
     pub address: RwLock<SocketAddr>,
     pub packets_queue: Arc<Mutex<VecDeque<UnsignedPacket>>>,
     pub state: AtomicCell<NextState>,
     pub closed: AtomicBool,
+    closing: Notify,
     pub decoder: Arc<Mutex<Decoder>>,
     pub reader: Arc<Mutex<OwnedReadHalf>>,
     pub encoder: Arc<Mutex<Encoder>>,
-    pub writer: Arc<Mutex<OwnedWriteHalf>>,
+    /// Encoded packets, written by [`writer::run`] so sending never waits for the socket.
+    outgoing: mpsc::UnboundedSender<BytesMut>,
+    outgoing_queue: Mutex<mpsc::UnboundedReceiver<BytesMut>>,
+    /// Bytes queued and not written yet.
+    queued_bytes: AtomicUsize,
+    writer: Mutex<OwnedWriteHalf>,
+    flushing: Notify,
+    pub profile: RwLock<Option<GameProfile>>,
+    pub forwarded: Mutex<Option<ForwardedPlayer>>,
+    pub login: Mutex<LoginStage>,
+    pub keep_alive: Mutex<KeepAlive>,
+    /// Smoothed round-trip time of keep-alives in milliseconds.
+    pub latency: AtomicI32,
+    /// Op level, see [`crate::command::MAX_PERMISSION_LEVEL`].
+    pub permission_level: AtomicU8,
+    /// Resolved when the player logs in, see [`Client::has_permission`].
+    pub permissions: std::sync::RwLock<PermissionSet>,
+    pub player: Mutex<PlayerState>,
+    pub teleport_id: AtomicI32,
+    pub chunks: Mutex<ChunkTracker>,
+    pub connected_at: Instant,
+    /// Span of everything logged for this connection, with its address, username and state.
+    pub span: Span,
     
     pub done: AtomicBool,
 }
@@ -31,90 +93,251 @@ pub struct Client {
 impl Client {
     pub fn new(tcp_stream: TcpStream, socket_addr: SocketAddr) -> Self {
         let (reader, writer) = tcp_stream.into_split();
+        let (outgoing, outgoing_queue) = mpsc::unbounded_channel();
         Client {
             address: RwLock::new(socket_addr),
             packets_queue: Arc::new(Mutex::new(VecDeque::new())),
             state: AtomicCell::new(NextState::None),
             closed: AtomicBool::new(false),
+            closing: Notify::new(),
             decoder: Arc::new(Mutex::new(Decoder::new())),
             encoder: Arc::new(Mutex::new(Encoder::new())),
             reader: Arc::new(Mutex::new(reader)),
-            writer: Arc::new(Mutex::new(writer)),
+            outgoing,
+            outgoing_queue: Mutex::new(outgoing_queue),
+            queued_bytes: AtomicUsize::new(0),
+            writer: Mutex::new(writer),
+            flushing: Notify::new(),
+            profile: RwLock::new(None),
+            forwarded: Mutex::new(None),
+            login: Mutex::new(LoginStage::Start),
+            keep_alive: Mutex::new(KeepAlive::default()),
+            latency: AtomicI32::new(0),
+            permission_level: AtomicU8::new(0),
+            permissions: std::sync::RwLock::new(PermissionSet::default()),
+            player: Mutex::new(PlayerState::default()),
+            teleport_id: AtomicI32::new(0),
+            chunks: Mutex::new(ChunkTracker::default()),
+            connected_at: Instant::now(),
+            span: tracing::info_span!("connection", address = %socket_addr, username = Empty, state = ?NextState::None),
             
             done: AtomicBool::new(false),
         }
     }
 
+    pub fn set_state(&self, state: NextState) {
+        self.state.store(state);
+        self.span.record("state", tracing::field::debug(state));
+    }
+
     pub async fn add_packet(&self, packet: UnsignedPacket) {
         let mut packets_queue = self.packets_queue.lock().await;
         packets_queue.push_back(packet);
     }
 
-    pub async fn process_packets(&self, server: &Server) {
+    /// The player's name, or its address before it logged in.
+    pub async fn name(&self) -> String {
+        match self.profile.read().await.as_ref() {
+            Some(profile) => profile.name.clone(),
+            None => self.address.read().await.to_string(),
+        }
+    }
+
+    /// Whether a permission node is set for the player, `None` if no rule matches it.
+    pub fn permission(&self, node: &str) -> Option<bool> {
+        self.permissions.read().unwrap().check(node)
+    }
+
+    /// Whether the player has a permission node. Nodes that aren't set are granted to operators.
+    pub fn has_permission(&self, node: &str) -> bool {
+        self.permission(node)
+            .unwrap_or_else(|| self.permission_level.load(Ordering::Relaxed) > 0)
+    }
+
+    /// Sends the command tree, filtered by what the player is allowed to use.
+    pub async fn send_commands(self: &Arc<Self>, server: &Server) {
+        let sender = CommandSender::Player(self.clone());
+        self.send_packet(&server.commands.build_packet(&sender)).await
+    }
+
+    /// Handles the packets received so far. While playing only keep-alives are, so the latency
+    /// doesn't include waiting for a tick, the rest wait for [`Client::handle_queued_packets`].
+    pub async fn process_packets(self: &Arc<Self>, server: &Server) {
         let mut packet_queue = self.packets_queue.lock().await;
+        if self.is_playing() {
+            let (keep_alives, actions): (VecDeque<_>, VecDeque<_>) = packet_queue.drain(..)
+                .partition(|packet| packet.id.0 == KeepAliveServerbound::PACKET_ID);
+            *packet_queue = actions;
+            drop(packet_queue);
+            for packet in keep_alives {
+                self.handle_or_close(packet, server).await
+            }
+            return
+        }
         while let Some(packet) = packet_queue.pop_front() {
-            if let Err(_error) = self.handle_packet(packet, server).await {
-                todo!("error while reading packet")
+            self.handle_or_close(packet, server).await
+        }
+    }
+
+    /// Handles the play packets queued since the last tick, in the order they came in.
+    pub async fn handle_queued_packets(self: &Arc<Self>, server: &Server) {
+        let packets = std::mem::take(&mut *self.packets_queue.lock().await);
+        for packet in packets {
+            if self.closed.load(Ordering::Relaxed) {
+                return
             }
+            self.handle_or_close(packet, server).await
         }
     }
 
-    pub async fn poll(&self) -> bool {
+    async fn handle_or_close(self: &Arc<Self>, packet: UnsignedPacket, server: &Server) {
+        if let Err(e) = self.handle_packet(packet, server).await {
+            tracing::debug!(error = %e, "Failed to handle packet");
+            self.close().await
+        }
+    }
+
+    pub async fn poll(&self, server: &Server) -> bool {
         loop {
             let mut decoder = self.decoder.lock().await;
 
             match decoder.decode() {
                 Ok(Some(packet)) => {
-                    println!("from polling (Some(Packet)): {:?}, {:?}, {:?}", self.state.load(), decoder, packet);
+                    METRICS.packet_in(self.state.load(), packet.id.0);
+                    tracing::trace!(id = packet.id.0, size = packet.data.len(), "Received packet");
                     self.add_packet(packet).await;
                     return true
                 } Ok(None) => (),
                 Err(e) => {
-                    println!("failed to decode packet for: {}", e);
+                    tracing::debug!(error = %e, "Failed to decode packet");
                     self.close().await;
                     return false
                 }
             }
 
-            // println!("{:?}", decoder);
-
             decoder.reserve(4096);
             let mut buffer = decoder.take_capacity();
 
-            match self.reader.lock().await.read_buf(&mut buffer).await {
+            let mut reader = self.reader.lock().await;
+            let deadline = server.config.timeouts.deadline_for(self.state.load())
+                .map(|deadline| self.connected_at + deadline);
+            let result = tokio::select! {
+                result = reader.read_buf(&mut buffer) => result,
+                _ = self.closing.notified() => return false,
+                _ = sleep_until(deadline) => {
+                    tracing::debug!("Timed out reading from {} in state {:?}",
+                        self.address.read().await, self.state.load());
+                    self.close().await;
+                    return false
+                }
+            };
+            drop(reader);
+
+            match result {
                 Ok(0) => {
-                    println!("buffer: {:?}, decoder {:?}", buffer, decoder);
+                    tracing::debug!("Connection closed by the client");
                     self.close().await;
                     return false
                 }
                 Err(e) => {
-                    println!("error while reading packet: {}", e);
+                    tracing::debug!(error = %e, "Failed to read from the connection");
                     self.close().await;
                     return false
                 }
                 _ => ()
             }
 
+            tracing::trace!(size = buffer.len(), "Read bytes");
             decoder.append_bytes(buffer);
-            println!("from polling (end): {:?}, {:?}", self.state.load(), decoder);
         }
     }
 
+    /// Queues a packet for the writer task. A packet that can't be encoded or a client that
+    /// lets more than [`MAX_QUEUED_BYTES`] pile up closes the connection.
     pub async fn send_packet<P: ClientboundPacket>(&self, packet: &P) {
+        if self.closed.load(Ordering::Relaxed) {
+            return
+        }
+        // Queued under the encoder lock so packets are written in the order they were encoded.
         let mut encoder = self.encoder.lock().await;
         if let Err(e) = encoder.append(packet) {
-            unimplemented!("failed to encode packet: {}", e)
+            tracing::error!(id = P::PACKET_ID, error = %e, "Failed to encode packet");
+            drop(encoder);
+            return self.close().await
         }
+        METRICS.packet_out(self.state.load(), P::PACKET_ID);
 
-        let mut writer = self.writer.lock().await;
-        if let Err(e) = writer.write_all(&encoder.take()).await {
-            unimplemented!("failed to write packet: {}", e)
+        let bytes = encoder.take();
+        tracing::trace!(id = P::PACKET_ID, size = bytes.len(), "Sent packet");
+        let queued = self.queued_bytes.fetch_add(bytes.len(), Ordering::Relaxed) + bytes.len();
+        if queued > MAX_QUEUED_BYTES {
+            drop(encoder);
+            tracing::debug!("{} bytes are waiting to be written, the client can't keep up", queued);
+            return self.close().await
+        }
+        if self.outgoing.send(bytes).is_err() {
+            drop(encoder);
+            tracing::debug!("The connection is no longer written to");
+            self.close().await
         }
     }
 
+    pub async fn disconnect(&self, reason: &str) {
+        tracing::info!("Disconnecting {}: {}", self.address.read().await, reason);
+        match self.state.load() {
+            NextState::Login => {
+                let json_reason = serde_json::json!({ "text": reason }).to_string();
+                self.send_packet(&LoginDisconnectClientbound::new(&json_reason)).await
+            }
+            NextState::Configuration => self
+                .send_packet(&ConfigurationDisconnectClientbound::new(&Tag::from(reason))).await,
+            NextState::Play => self
+                .send_packet(&DisconnectClientbound::new(&Tag::from(reason))).await,
+            _ => ()
+        }
+        self.close().await
+    }
+
     pub async fn close(&self) {
-        self.closed.store(true, std::sync::atomic::Ordering::Relaxed);
-        println!("closing connection from {}", self.address.read().await.to_string())
+        self.closed.store(true, Ordering::Relaxed);
+        self.closing.notify_one();
+        self.flushing.notify_one();
+        tracing::debug!("Closing connection")
     }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(TokioInstant::from_std(deadline)).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tokio::net::TcpListener;
+    use necko_protocol::buffer::PacketByteBuffer;
+    use super::*;
+
+    /// A client on a local connection, with the other end of it.
+    pub async fn connected_client() -> (Client, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let peer = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, address) = listener.accept().await.unwrap();
+        (Client::new(stream, address), peer)
+    }
+
+    /// Packets queued for the client so far, as their ID and data.
+    pub fn sent_packets(client: &Client) -> Vec<(i32, PacketByteBuffer)> {
+        let mut queue = client.outgoing_queue.try_lock().unwrap();
+        let mut packets = Vec::new();
+        while let Ok(bytes) = queue.try_recv() {
+            let mut buffer = PacketByteBuffer::new(bytes);
+            buffer.read_var_int().unwrap();
+            let id = buffer.read_var_int().unwrap().0;
+            packets.push((id, buffer));
+        }
+        packets
+    }
+}
 
-}
\ No newline at end of file
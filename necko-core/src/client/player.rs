@@ -0,0 +1,282 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use serde::{Deserialize, Serialize};
+use necko_protocol::packets::clientbound::play::game_event::{GameEvent, GameEventClientbound};
+use necko_protocol::packets::clientbound::play::player_position::PlayerPositionClientbound;
+use necko_protocol::packets::clientbound::play::set_chunk_cache_center::SetChunkCacheCenterClientbound;
+use necko_protocol::packets::clientbound::play::set_health::SetHealthClientbound;
+use necko_protocol::packets::serverbound::intention::NextState;
+use necko_protocol::packets::clientbound::play::container_set_content::ContainerSetContentClientbound;
+use necko_protocol::packets::clientbound::play::container_set_slot::{ContainerSetSlotClientbound, PLAYER_INVENTORY};
+use necko_protocol::types::{GameMode, SlotData};
+use crate::client::Client;
+use crate::client::player_data::PlayerData;
+use crate::registry::Item;
+use crate::server::Server;
+use crate::world::{Weather, World};
+
+pub const MAX_HEALTH: f32 = 20.0;
+pub const MAX_FOOD: i32 = 20;
+/// Main inventory and hotbar.
+pub const INVENTORY_SIZE: usize = 36;
+/// Slots of the player's inventory window: crafting, armor, the inventory and the offhand.
+const WINDOW_SIZE: usize = 46;
+pub const MAX_STACK_SIZE: i32 = 64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: i32,
+}
+
+impl ItemStack {
+    /// The stack as sent to clients, `None` for items they don't know.
+    pub fn slot_data(&self) -> Option<SlotData> {
+        let item = Item::from_name(&self.item)?;
+        Some(SlotData { item_id: item.protocol_id(), count: self.count })
+    }
+}
+
+/// Slots 0 to 8 are the hotbar, like vanilla's inventory indices.
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    /// Bumped whenever the client is sent a change, it echoes the last one when clicking.
+    pub state_id: i32,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory { slots: vec![None; INVENTORY_SIZE], state_id: 0 }
+    }
+}
+
+/// The slot of the inventory window an inventory slot is shown in, the hotbar being last.
+pub fn window_slot(slot: usize) -> i16 {
+    if slot < 9 { slot as i16 + 36 } else { slot as i16 }
+}
+
+impl Inventory {
+    /// Adds items to existing stacks first, then to empty slots. Returns how many didn't fit.
+    pub fn add(&mut self, item: &str, mut count: i32) -> i32 {
+        for stack in self.slots.iter_mut().flatten().filter(|stack| stack.item == item) {
+            let moved = count.min(MAX_STACK_SIZE - stack.count).max(0);
+            stack.count += moved;
+            count -= moved;
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                break
+            }
+            let moved = count.min(MAX_STACK_SIZE);
+            *slot = Some(ItemStack { item: item.to_string(), count: moved });
+            count -= moved;
+        }
+        count
+    }
+}
+
+/// In-game state of a player.
+#[derive(Debug, Clone)]
+pub struct PlayerState {
+    pub entity_id: i32,
+    /// Dimension of the world the player is in, see [`Client::world`].
+    pub dimension: String,
+    pub position: (f64, f64, f64),
+    pub yaw: f32,
+    pub pitch: f32,
+    pub game_mode: GameMode,
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    pub inventory: Inventory,
+    /// Teleport the client hasn't confirmed yet. Movement is ignored until it does.
+    pub pending_teleport: Option<i32>,
+}
+
+impl PlayerState {
+    /// The chunk the player is in.
+    pub fn chunk(&self) -> (i32, i32) {
+        chunk_at(self.position)
+    }
+}
+
+pub fn chunk_at((x, _, z): (f64, f64, f64)) -> (i32, i32) {
+    ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        PlayerState {
+            entity_id: 0,
+            dimension: String::new(),
+            position: (0.0, 0.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            game_mode: GameMode::default(),
+            health: MAX_HEALTH,
+            food: MAX_FOOD,
+            saturation: 5.0,
+            inventory: Inventory::default(),
+            pending_teleport: None,
+        }
+    }
+}
+
+impl Client {
+    pub fn is_playing(&self) -> bool {
+        self.state.load() == NextState::Play
+    }
+
+    /// The world the player is in, the one players join before it is in any.
+    pub async fn world(&self, server: &Server) -> Arc<World> {
+        let dimension = self.player.lock().await.dimension.clone();
+        server.world(&dimension).unwrap_or_else(|| server.default_world()).clone()
+    }
+
+    /// Tells the client the weather changed from `previous`.
+    pub async fn send_weather(&self, previous: Weather, weather: Weather) {
+        if weather.raining != previous.raining {
+            let event = if weather.raining { GameEvent::StartRaining } else { GameEvent::StopRaining };
+            self.send_packet(&GameEventClientbound::new(event, 0.0)).await
+        }
+        let rain_level = if weather.raining { 1.0 } else { 0.0 };
+        let thunder_level = if weather.thundering { 1.0 } else { 0.0 };
+        self.send_packet(&GameEventClientbound::new(GameEvent::RainLevelChange, rain_level)).await;
+        self.send_packet(&GameEventClientbound::new(GameEvent::ThunderLevelChange, thunder_level)).await
+    }
+
+    pub async fn teleport(&self, position: (f64, f64, f64), yaw: f32, pitch: f32) {
+        let teleport_id = self.teleport_id.fetch_add(1, Ordering::Relaxed);
+        let mut player = self.player.lock().await;
+        let chunk = player.chunk();
+        player.position = position;
+        player.yaw = yaw;
+        player.pitch = pitch;
+        player.pending_teleport = Some(teleport_id);
+        drop(player);
+
+        if chunk_at(position) != chunk {
+            let (x, z) = chunk_at(position);
+            self.send_packet(&SetChunkCacheCenterClientbound::new(x, z)).await
+        }
+        self.send_packet(&PlayerPositionClientbound::new(teleport_id, position, yaw, pitch, 0)).await
+    }
+
+    pub async fn set_game_mode(&self, game_mode: GameMode) {
+        self.player.lock().await.game_mode = game_mode;
+        self.send_packet(&GameEventClientbound::new(GameEvent::ChangeGameMode, game_mode as i32 as f32)).await
+    }
+
+    pub async fn set_health(&self, health: f32) {
+        let mut player = self.player.lock().await;
+        player.health = health.clamp(0.0, MAX_HEALTH);
+        let packet = SetHealthClientbound::new(player.health, player.food, player.saturation);
+        drop(player);
+        self.send_packet(&packet).await
+    }
+
+    /// Gives the player the item and sends the slots it went to, returning how many didn't
+    /// fit into the inventory.
+    pub async fn give(&self, item: &str, count: i32) -> i32 {
+        let mut player = self.player.lock().await;
+        let inventory = &mut player.inventory;
+        let before = inventory.slots.clone();
+        let left = inventory.add(item, count);
+        let changed: Vec<_> = (0..INVENTORY_SIZE)
+            .filter(|slot| before[*slot] != inventory.slots[*slot])
+            .map(|slot| (slot, inventory.slots[slot].as_ref().and_then(ItemStack::slot_data)))
+            .collect();
+        if !changed.is_empty() {
+            inventory.state_id += 1;
+        }
+        let state_id = inventory.state_id;
+        drop(player);
+
+        for (slot, item) in changed {
+            self.send_packet(&ContainerSetSlotClientbound::new(PLAYER_INVENTORY, state_id, window_slot(slot), item)).await
+        }
+        left
+    }
+
+    /// Sends every slot of the inventory, when the player joins.
+    pub async fn send_inventory(&self) {
+        let mut player = self.player.lock().await;
+        let inventory = &mut player.inventory;
+        inventory.state_id += 1;
+        let mut slots = vec![None; WINDOW_SIZE];
+        for (slot, stack) in inventory.slots.iter().enumerate() {
+            slots[window_slot(slot) as usize] = stack.as_ref().and_then(ItemStack::slot_data);
+        }
+        let packet = ContainerSetContentClientbound::new(PLAYER_INVENTORY, inventory.state_id, slots);
+        drop(player);
+        self.send_packet(&packet).await
+    }
+
+    /// Saves the player into the playerdata of the world players join. Nothing is saved before
+    /// the player is in a world, so leaving while joining never replaces what was saved.
+    pub async fn save_data(&self, server: &Server) {
+        let Some(uuid) = self.profile.read().await.as_ref().map(|profile| profile.uuid) else {
+            return
+        };
+        let data = {
+            let player = self.player.lock().await;
+            if player.dimension.is_empty() {
+                return
+            }
+            PlayerData::from_state(&player)
+        };
+        if let Err(e) = data.save(&server.player_data_directory(), &uuid) {
+            tracing::error!("Could not save player data of {}: {}", self.name().await, e)
+        }
+    }
+
+    /// Applies the op level from the ops list and the permissions of the backend, then
+    /// resends the command tree.
+    pub async fn update_permission_level(self: &Arc<Self>, server: &Server) {
+        let Some((uuid, name)) = self.profile.read().await.as_ref().map(|profile| (profile.uuid, profile.name.clone())) else {
+            return
+        };
+        self.permission_level.store(server.access.op_level(&uuid), Ordering::Relaxed);
+        *self.permissions.write().unwrap() = server.permissions.permissions(&uuid, &name);
+        if self.is_playing() {
+            self.send_commands(server).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use necko_protocol::packets::Packet;
+    use crate::client::tests::{connected_client, sent_packets};
+    use super::*;
+
+    #[tokio::test]
+    async fn give_sends_changed_slots() {
+        let (client, _peer) = connected_client().await;
+        client.player.lock().await.inventory.slots[0] = Some(ItemStack { item: "minecraft:stone".into(), count: 60 });
+
+        assert_eq!(client.give("minecraft:stone", 10).await, 0);
+        let stone = Item::Stone.protocol_id();
+        let packets: Vec<_> = sent_packets(&client).into_iter()
+            .map(|(id, mut data)| {
+                assert_eq!(id, ContainerSetSlotClientbound::PACKET_ID);
+                assert_eq!(data.read_var_int().unwrap().0, PLAYER_INVENTORY);
+                assert_eq!(data.read_var_int().unwrap().0, 1, "state id");
+                let slot = data.read_u16().unwrap() as i16;
+                let count = data.read_var_int().unwrap().0;
+                (slot, count, data.read_var_int().unwrap().0)
+            })
+            .collect();
+        assert_eq!(packets, [(36, 64, stone), (37, 6, stone)], "the first two hotbar slots");
+    }
+
+    #[test]
+    fn window_slots() {
+        assert_eq!(window_slot(0), 36);
+        assert_eq!(window_slot(8), 44);
+        assert_eq!(window_slot(9), 9);
+        assert_eq!(window_slot(35), 35);
+    }
+}
+
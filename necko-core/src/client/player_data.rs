@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use necko_protocol::types::GameMode;
+use crate::client::player::{Inventory, ItemStack, PlayerState, INVENTORY_SIZE};
+
+/// Directory of the world players join where players are saved, like vanilla's.
+pub const PLAYER_DATA_DIRECTORY: &str = "playerdata";
+
+/// What is kept of a player between sessions, saved as `playerdata/<uuid>.json`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerData {
+    pub dimension: String,
+    pub position: (f64, f64, f64),
+    pub yaw: f32,
+    pub pitch: f32,
+    /// `survival`, `creative`, `adventure` or `spectator`.
+    pub game_mode: String,
+    pub health: f32,
+    pub food: i32,
+    pub saturation: f32,
+    /// Slot and stack of every item in the inventory.
+    pub inventory: Vec<(usize, ItemStack)>,
+}
+
+impl PlayerData {
+    pub fn from_state(player: &PlayerState) -> Self {
+        PlayerData {
+            dimension: player.dimension.clone(),
+            position: player.position,
+            yaw: player.yaw,
+            pitch: player.pitch,
+            game_mode: player.game_mode.name().into(),
+            health: player.health,
+            food: player.food,
+            saturation: player.saturation,
+            inventory: player.inventory.slots.iter().enumerate()
+                .filter_map(|(slot, stack)| Some((slot, stack.clone()?)))
+                .collect(),
+        }
+    }
+
+    /// Restores the player, leaving what wasn't saved like its entity ID alone.
+    pub fn apply(&self, player: &mut PlayerState) {
+        player.dimension = self.dimension.clone();
+        player.position = self.position;
+        player.yaw = self.yaw;
+        player.pitch = self.pitch;
+        player.game_mode = GameMode::from_name(&self.game_mode).unwrap_or_default();
+        player.health = self.health;
+        player.food = self.food;
+        player.saturation = self.saturation;
+        player.inventory = Inventory::default();
+        for (slot, stack) in self.inventory.iter().filter(|(slot, _)| *slot < INVENTORY_SIZE) {
+            player.inventory.slots[*slot] = Some(stack.clone());
+        }
+    }
+
+    fn path(directory: &Path, uuid: &Uuid) -> PathBuf {
+        directory.join(format!("{uuid}.json"))
+    }
+
+    /// Reads a player's data, `None` if it never played here.
+    pub fn load(directory: &Path, uuid: &Uuid) -> Result<Option<Self>, Error> {
+        let path = Self::path(directory, uuid);
+        if !path.exists() {
+            return Ok(None)
+        }
+        let json = fs::read_to_string(&path)?;
+        serde_json::from_str(&json).map(Some)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Failed to load {}: {}", path.display(), e)))
+    }
+
+    /// Writes the data through a temporary file, so a crash never leaves half of it.
+    pub fn save(&self, directory: &Path, uuid: &Uuid) -> Result<(), Error> {
+        fs::create_dir_all(directory)?;
+        let json = serde_json::to_string_pretty(self).map_err(Error::other)?;
+        let path = Self::path(directory, uuid);
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, json)?;
+        fs::rename(temporary, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let directory = std::env::temp_dir().join(format!("necko-playerdata-{}", std::process::id()));
+        let uuid = Uuid::from_u128(42);
+        assert_eq!(PlayerData::load(&directory, &uuid).unwrap(), None);
+
+        let mut player = PlayerState {
+            dimension: "minecraft:the_nether".into(),
+            position: (1.5, 70.0, -3.25),
+            yaw: 90.0,
+            game_mode: GameMode::Creative,
+            health: 7.0,
+            ..PlayerState::default()
+        };
+        player.inventory.add("minecraft:stone", 100);
+        let data = PlayerData::from_state(&player);
+        assert_eq!(data.inventory.len(), 2);
+        data.save(&directory, &uuid).unwrap();
+        assert_eq!(PlayerData::load(&directory, &uuid).unwrap(), Some(data.clone()));
+
+        let mut restored = PlayerState { entity_id: 5, ..PlayerState::default() };
+        data.apply(&mut restored);
+        assert_eq!(restored.entity_id, 5);
+        assert_eq!(restored.dimension, player.dimension);
+        assert_eq!(restored.position, player.position);
+        assert_eq!(restored.game_mode, GameMode::Creative);
+        assert_eq!(restored.health, 7.0);
+        assert_eq!(restored.inventory.slots, player.inventory.slots);
+
+        fs::write(directory.join(format!("{uuid}.json")), "{").unwrap();
+        assert!(PlayerData::load(&directory, &uuid).is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
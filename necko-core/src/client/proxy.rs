@@ -0,0 +1,236 @@
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use serde::{Deserialize, Serialize};
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LENGTH: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LENGTH: usize = 16;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ProxyProtocolConfig {
+    pub enabled: bool,
+    /// Addresses (`10.0.0.1`) or CIDR ranges (`10.0.0.0/8`) allowed to send a PROXY header.
+    pub trusted_sources: Vec<String>,
+}
+
+impl Default for ProxyProtocolConfig {
+    fn default() -> Self {
+        ProxyProtocolConfig {
+            enabled: false,
+            trusted_sources: vec!["127.0.0.1".into(), "::1".into()],
+        }
+    }
+}
+
+impl ProxyProtocolConfig {
+    pub fn is_trusted(&self, address: IpAddr) -> bool {
+        self.trusted_sources.iter().any(|source| cidr_contains(source, address))
+    }
+}
+
+fn cidr_contains(cidr: &str, address: IpAddr) -> bool {
+    let (network, prefix) = match cidr.split_once('/') {
+        Some((network, prefix)) => (network, prefix.parse::<u32>().ok()),
+        None => (cidr, None),
+    };
+    let Ok(network) = network.parse::<IpAddr>() else {
+        return false
+    };
+
+    match (network, address.to_canonical()) {
+        (IpAddr::V4(network), IpAddr::V4(address)) => {
+            let prefix = prefix.unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(address) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(address)) => {
+            let prefix = prefix.unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(address) & mask
+        }
+        _ => false
+    }
+}
+
+/// Reads a PROXY protocol v1 or v2 header off the stream and returns the address of the
+/// original client, or `None` if the proxy sent a `LOCAL`/`UNKNOWN` header (e.g. health checks).
+/// Bytes read past the header are the start of the first packet and are returned with it.
+pub async fn read_proxy_header<R: AsyncRead + Unpin>(stream: &mut R) -> Result<(Option<SocketAddr>, BytesMut), Error> {
+    match stream.read_u8().await? {
+        b'P' => read_v1(stream).await,
+        b'\r' => Ok((read_v2(stream).await?, BytesMut::new())),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Missing PROXY protocol header"))
+    }
+}
+
+/// Reads the v1 line in as few reads as the proxy sent it in, never more than the longest
+/// line allowed.
+async fn read_v1<R: AsyncRead + Unpin>(stream: &mut R) -> Result<(Option<SocketAddr>, BytesMut), Error> {
+    let mut buffer = [0u8; V1_MAX_LENGTH];
+    buffer[0] = b'P';
+    let mut length = 1;
+    let end = loop {
+        if let Some(end) = buffer[..length].windows(2).position(|window| window == b"\r\n") {
+            break end
+        }
+        if length == V1_MAX_LENGTH {
+            return Err(Error::new(ErrorKind::InvalidData, "PROXY v1 header is too long"))
+        }
+        match stream.read(&mut buffer[length..]).await? {
+            0 => return Err(Error::new(ErrorKind::UnexpectedEof, "Connection closed in the PROXY header")),
+            read => length += read,
+        }
+    };
+    Ok((parse_v1(&buffer[..end])?, BytesMut::from(&buffer[end + 2..length])))
+}
+
+/// Parses a v1 line without its `\r\n`.
+fn parse_v1(line: &[u8]) -> Result<Option<SocketAddr>, Error> {
+    if !line.starts_with(V1_PREFIX) {
+        return Err(Error::new(ErrorKind::InvalidData, "Missing PROXY protocol header"))
+    }
+    let line = std::str::from_utf8(line)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    match parts.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", source, _destination, source_port, _destination_port] => {
+            let ip = source.parse::<IpAddr>()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            let port = source_port.parse::<u16>()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(Error::new(ErrorKind::InvalidData, "Malformed PROXY v1 header"))
+    }
+}
+
+async fn read_v2<R: AsyncRead + Unpin>(stream: &mut R) -> Result<Option<SocketAddr>, Error> {
+    let mut header = [0u8; V2_HEADER_LENGTH];
+    header[0] = b'\r';
+    stream.read_exact(&mut header[1..]).await?;
+    let mut payload = vec![0u8; u16::from_be_bytes([header[14], header[15]]) as usize];
+    stream.read_exact(&mut payload).await?;
+    parse_v2(&header, &payload)
+}
+
+fn parse_v2(header: &[u8; V2_HEADER_LENGTH], payload: &[u8]) -> Result<Option<SocketAddr>, Error> {
+    if !header.starts_with(V2_SIGNATURE) {
+        return Err(Error::new(ErrorKind::InvalidData, "Missing PROXY protocol header"))
+    }
+
+    let version = header[12] >> 4;
+    let command = header[12] & 0x0F;
+    if version != 2 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unsupported PROXY protocol version"))
+    }
+
+    // LOCAL connections are made by the proxy itself and carry no client address
+    if command == 0x0 {
+        return Ok(None)
+    }
+    if command != 0x1 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unknown PROXY v2 command"))
+    }
+
+    match header[13] >> 4 {
+        0x1 if payload.len() >= 12 => {
+            let ip = Ipv4Addr::new(payload[0], payload[1], payload[2], payload[3]);
+            let port = u16::from_be_bytes([payload[8], payload[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        0x2 if payload.len() >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&payload[..16]);
+            let port = u16::from_be_bytes([payload[32], payload[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port)))
+        }
+        0x0 => Ok(None),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Unsupported PROXY v2 address family"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_header(command: u8, family: u8, payload: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family << 4 | 0x1);
+        header.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        header.extend_from_slice(payload);
+        header
+    }
+
+    #[tokio::test]
+    async fn v1_keeps_bytes_after_the_header() {
+        let mut input: &[u8] = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10\x00";
+        let (source, rest) = read_proxy_header(&mut input).await.unwrap();
+        assert_eq!(source, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(&rest[..], b"\x10\x00");
+    }
+
+    #[tokio::test]
+    async fn v1_tcp6_and_unknown() {
+        let mut input: &[u8] = b"PROXY TCP6 ::1 ::2 4000 25565\r\n";
+        assert_eq!(read_proxy_header(&mut input).await.unwrap().0, Some("[::1]:4000".parse().unwrap()));
+        let mut input: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_proxy_header(&mut input).await.unwrap().0, None);
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_long_and_malformed_lines() {
+        let long = [b"PROXY TCP4 ".as_slice(), &[b'1'; 120]].concat();
+        assert!(read_proxy_header(&mut long.as_slice()).await.is_err());
+        let mut input: &[u8] = b"PROXY TCP4 not-an-ip 1.1.1.1 1 2\r\n";
+        assert!(read_proxy_header(&mut input).await.is_err());
+        let mut input: &[u8] = b"PROXY TCP4 1.1.1.1";
+        assert!(read_proxy_header(&mut input).await.is_err());
+        let mut input: &[u8] = b"\x10\x00";
+        assert!(read_proxy_header(&mut input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v2_ipv4_and_ipv6() {
+        let payload = [[10, 0, 0, 1], [10, 0, 0, 2]].concat();
+        let payload = [payload, 1234u16.to_be_bytes().to_vec(), 25565u16.to_be_bytes().to_vec()].concat();
+        let input = v2_header(0x1, 0x1, &payload);
+        let (source, rest) = read_proxy_header(&mut input.as_slice()).await.unwrap();
+        assert_eq!(source, Some("10.0.0.1:1234".parse().unwrap()));
+        assert!(rest.is_empty());
+
+        let mut payload = vec![0u8; 36];
+        payload[15] = 1;
+        payload[32..34].copy_from_slice(&4321u16.to_be_bytes());
+        let input = v2_header(0x1, 0x2, &payload);
+        assert_eq!(read_proxy_header(&mut input.as_slice()).await.unwrap().0, Some("[::1]:4321".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn v2_local_and_invalid() {
+        let input = v2_header(0x0, 0x1, &[0; 12]);
+        assert_eq!(read_proxy_header(&mut input.as_slice()).await.unwrap().0, None);
+        let input = v2_header(0x1, 0x1, &[0; 4]);
+        assert!(read_proxy_header(&mut input.as_slice()).await.is_err());
+        let mut input = v2_header(0x1, 0x1, &[0; 12]);
+        input[12] = 0x11;
+        assert!(read_proxy_header(&mut input.as_slice()).await.is_err());
+    }
+
+    #[test]
+    fn trusted_sources() {
+        let config = ProxyProtocolConfig {
+            enabled: true,
+            trusted_sources: vec!["10.0.0.0/8".into(), "::1".into()],
+        };
+        assert!(config.is_trusted("10.1.2.3".parse().unwrap()));
+        assert!(!config.is_trusted("11.0.0.1".parse().unwrap()));
+        assert!(config.is_trusted("::1".parse().unwrap()));
+        assert!(config.is_trusted("::ffff:10.0.0.1".parse().unwrap()));
+    }
+}
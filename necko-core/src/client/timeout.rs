@@ -0,0 +1,52 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use necko_protocol::packets::serverbound::intention::NextState;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Time a new connection has to send its PROXY header and Intention packet.
+    pub handshake_ms: u64,
+    pub status_ms: u64,
+    pub login_ms: u64,
+    pub keep_alive_interval_ms: u64,
+    /// Time a client has to answer a Keep Alive before being disconnected.
+    pub keep_alive_timeout_ms: u64,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        TimeoutConfig {
+            handshake_ms: 5_000,
+            status_ms: 10_000,
+            login_ms: 30_000,
+            keep_alive_interval_ms: 15_000,
+            keep_alive_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// How long after connecting a client may still be in `state`. Configuration and play
+    /// have no deadline, their liveness is checked with keep-alives instead.
+    pub fn deadline_for(&self, state: NextState) -> Option<Duration> {
+        match state {
+            NextState::None => Some(self.handshake_ms),
+            NextState::Status => Some(self.status_ms),
+            NextState::Login | NextState::Transfer => Some(self.login_ms),
+            NextState::Configuration | NextState::Play => None,
+        }.map(Duration::from_millis)
+    }
+
+    pub fn handshake(&self) -> Duration {
+        Duration::from_millis(self.handshake_ms)
+    }
+
+    pub fn keep_alive_interval(&self) -> Duration {
+        Duration::from_millis(self.keep_alive_interval_ms)
+    }
+
+    pub fn keep_alive_timeout(&self) -> Duration {
+        Duration::from_millis(self.keep_alive_timeout_ms)
+    }
+}
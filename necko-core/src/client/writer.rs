@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use crate::client::Client;
+
+/// Bytes of encoded packets that may wait for the socket. Bursts like a chunk batch fit many
+/// times over, a client that lets this much pile up can't keep up and is disconnected.
+pub const MAX_QUEUED_BYTES: usize = 64 * 1024 * 1024;
+/// How long a single write may block before the connection is considered dead.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Writes the packets queued for a client until it closes, then flushes what was queued
+/// before closing, like the disconnect reason, and shuts the connection down.
+pub async fn run(client: Arc<Client>) {
+    let mut writer = client.writer.lock().await;
+    let mut outgoing = client.outgoing_queue.lock().await;
+    loop {
+        let bytes = tokio::select! {
+            biased;
+            bytes = outgoing.recv() => bytes,
+            _ = client.flushing.notified() => None,
+        };
+        let Some(bytes) = bytes else { break };
+        if !write(&client, &mut writer, &bytes).await {
+            return
+        }
+    }
+
+    while let Ok(bytes) = outgoing.try_recv() {
+        if !write(&client, &mut writer, &bytes).await {
+            return
+        }
+    }
+    if let Err(e) = writer.shutdown().await {
+        tracing::debug!(error = %e, "Failed to flush the connection");
+    }
+}
+
+async fn write(client: &Client, writer: &mut tokio::net::tcp::OwnedWriteHalf, bytes: &[u8]) -> bool {
+    match tokio::time::timeout(WRITE_TIMEOUT, writer.write_all(bytes)).await {
+        Ok(Ok(())) => {
+            client.queued_bytes.fetch_sub(bytes.len(), Ordering::Relaxed);
+            true
+        }
+        Ok(Err(e)) => {
+            tracing::debug!(error = %e, "Failed to write to the connection");
+            client.close().await;
+            false
+        }
+        Err(_) => {
+            tracing::debug!("Timed out writing to the connection");
+            client.close().await;
+            false
+        }
+    }
+}
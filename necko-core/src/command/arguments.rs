@@ -0,0 +1,520 @@
+use necko_protocol::buffer::PacketByteBuffer;
+use necko_protocol::packets::clientbound::play::commands::ArgumentParser;
+use necko_protocol::text::TextComponent;
+use necko_protocol::types::{GameMode, VarInt};
+use uuid::Uuid;
+use crate::command::reader::{CommandSyntaxError, StringReader};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringKind {
+    /// A single word made of unquoted characters.
+    Word,
+    /// A word or a quoted phrase.
+    Quotable,
+    /// The rest of the input.
+    Greedy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentType {
+    Bool,
+    Float { min: Option<f32>, max: Option<f32> },
+    Double { min: Option<f64>, max: Option<f64> },
+    Integer { min: Option<i32>, max: Option<i32> },
+    Long { min: Option<i64>, max: Option<i64> },
+    String(StringKind),
+    Entity { single: bool, players_only: bool },
+    GameProfile,
+    BlockPos,
+    Vec3,
+    ItemStack,
+    Component,
+    Message,
+    ResourceLocation,
+    Dimension,
+    GameMode,
+    Time { min: i32 },
+    Uuid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntitySelector {
+    Player(String),
+    Uuid(Uuid),
+    /// `@s`
+    Sender,
+    /// `@a`
+    AllPlayers,
+    /// `@p`
+    NearestPlayer,
+    /// `@r`
+    RandomPlayer,
+    /// `@e`
+    AllEntities,
+}
+
+impl EntitySelector {
+    pub fn is_single(&self) -> bool {
+        !matches!(self, EntitySelector::AllPlayers | EntitySelector::AllEntities)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldCoordinate {
+    pub relative: bool,
+    pub value: f64,
+}
+
+impl WorldCoordinate {
+    pub fn resolve(&self, origin: f64) -> f64 {
+        if self.relative { origin + self.value } else { self.value }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    /// `x y z`, each either absolute or relative to the sender (`~`).
+    World([WorldCoordinate; 3]),
+    /// `^left ^up ^forwards`, relative to where the sender is looking.
+    Local([f64; 3]),
+}
+
+impl Coordinates {
+    /// Resolves to absolute coordinates for a sender at `origin` looking at `(yaw, pitch)`.
+    pub fn resolve(&self, origin: (f64, f64, f64), rotation: (f32, f32)) -> (f64, f64, f64) {
+        match self {
+            Coordinates::World([x, y, z]) => (x.resolve(origin.0), y.resolve(origin.1), z.resolve(origin.2)),
+            Coordinates::Local([left, up, forwards]) => {
+                let (yaw, pitch) = ((rotation.0 + 90.0).to_radians() as f64, (-rotation.1).to_radians() as f64);
+                let (yaw_up, pitch_up) = (yaw, (-rotation.1 + 90.0).to_radians() as f64);
+                let forward = (yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+                let up_vec = (yaw_up.cos() * pitch_up.cos(), pitch_up.sin(), yaw_up.sin() * pitch_up.cos());
+                let left_vec = (
+                    forward.1 * up_vec.2 - forward.2 * up_vec.1,
+                    forward.2 * up_vec.0 - forward.0 * up_vec.2,
+                    forward.0 * up_vec.1 - forward.1 * up_vec.0,
+                );
+                (
+                    origin.0 + forward.0 * forwards + up_vec.0 * up + left_vec.0 * -left,
+                    origin.1 + forward.1 * forwards + up_vec.1 * up + left_vec.1 * -left,
+                    origin.2 + forward.2 * forwards + up_vec.2 * up + left_vec.2 * -left,
+                )
+            }
+        }
+    }
+
+    pub fn resolve_block(&self, origin: (f64, f64, f64), rotation: (f32, f32)) -> (i32, i32, i32) {
+        let (x, y, z) = self.resolve(origin, rotation);
+        (x.floor() as i32, y.floor() as i32, z.floor() as i32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Float(f32),
+    Double(f64),
+    Integer(i32),
+    Long(i64),
+    String(String),
+    Entity(EntitySelector),
+    Coordinates(Coordinates),
+    ResourceLocation(String),
+    Component(TextComponent),
+    GameMode(GameMode),
+    Time(i32),
+    Uuid(Uuid),
+}
+
+macro_rules! check_range {
+    ($reader:expr, $start:expr, $value:expr, $min:expr, $max:expr, $kind:literal) => {
+        if let Some(min) = $min {
+            if $value < min {
+                $reader.cursor = $start;
+                return Err(CommandSyntaxError::at(format!(concat!($kind, " must not be less than {}, found {}"), min, $value), $reader))
+            }
+        }
+        if let Some(max) = $max {
+            if $value > max {
+                $reader.cursor = $start;
+                return Err(CommandSyntaxError::at(format!(concat!($kind, " must not be more than {}, found {}"), max, $value), $reader))
+            }
+        }
+    };
+}
+
+impl ArgumentType {
+    pub fn parse(&self, reader: &mut StringReader) -> Result<ArgumentValue, CommandSyntaxError> {
+        let start = reader.cursor;
+        Ok(match self {
+            ArgumentType::Bool => ArgumentValue::Bool(reader.read_bool()?),
+            ArgumentType::Float { min, max } => {
+                let value: f32 = reader.read_number("float")?;
+                check_range!(reader, start, value, *min, *max, "Float");
+                ArgumentValue::Float(value)
+            }
+            ArgumentType::Double { min, max } => {
+                let value: f64 = reader.read_number("double")?;
+                check_range!(reader, start, value, *min, *max, "Double");
+                ArgumentValue::Double(value)
+            }
+            ArgumentType::Integer { min, max } => {
+                let value: i32 = reader.read_number("integer")?;
+                check_range!(reader, start, value, *min, *max, "Integer");
+                ArgumentValue::Integer(value)
+            }
+            ArgumentType::Long { min, max } => {
+                let value: i64 = reader.read_number("long")?;
+                check_range!(reader, start, value, *min, *max, "Long");
+                ArgumentValue::Long(value)
+            }
+            ArgumentType::String(kind) => ArgumentValue::String(match kind {
+                StringKind::Word => reader.read_unquoted_string().to_string(),
+                StringKind::Quotable => reader.read_string()?,
+                StringKind::Greedy => {
+                    let rest = reader.remaining().to_string();
+                    reader.cursor = reader.input().len();
+                    rest
+                }
+            }),
+            ArgumentType::Entity { single, players_only } => {
+                let selector = parse_selector(reader)?;
+                if *single && !selector.is_single() {
+                    reader.cursor = start;
+                    return Err(CommandSyntaxError::at("Only one entity is allowed, but the provided selector allows more than one", reader))
+                }
+                if *players_only && selector == EntitySelector::AllEntities {
+                    reader.cursor = start;
+                    return Err(CommandSyntaxError::at("Only players may be affected by this command, but the provided selector includes entities", reader))
+                }
+                ArgumentValue::Entity(selector)
+            }
+            ArgumentType::GameProfile => ArgumentValue::Entity(parse_selector(reader)?),
+            ArgumentType::BlockPos => ArgumentValue::Coordinates(parse_coordinates(reader, true)?),
+            ArgumentType::Vec3 => ArgumentValue::Coordinates(parse_coordinates(reader, false)?),
+            ArgumentType::ItemStack | ArgumentType::ResourceLocation | ArgumentType::Dimension => {
+                ArgumentValue::ResourceLocation(parse_resource_location(reader)?)
+            }
+            ArgumentType::Component => ArgumentValue::Component(parse_component(reader)?),
+            ArgumentType::Message => {
+                let rest = reader.remaining().to_string();
+                reader.cursor = reader.input().len();
+                ArgumentValue::String(rest)
+            }
+            ArgumentType::GameMode => {
+                let name = reader.read_unquoted_string();
+                match GameMode::from_name(name) {
+                    Some(game_mode) => ArgumentValue::GameMode(game_mode),
+                    None => {
+                        reader.cursor = start;
+                        return Err(CommandSyntaxError::at(format!("Unknown game mode: {name}"), reader))
+                    }
+                }
+            }
+            ArgumentType::Time { min } => {
+                let value: f32 = reader.read_number("float")?;
+                let scale = match reader.peek() {
+                    Some('d') => { reader.skip(); 24000.0 }
+                    Some('s') => { reader.skip(); 20.0 }
+                    Some('t') => { reader.skip(); 1.0 }
+                    Some(' ') | None => 1.0,
+                    Some(_) => return Err(CommandSyntaxError::at("Invalid unit", reader)),
+                };
+                let ticks = (value * scale).round() as i32;
+                if ticks < *min {
+                    reader.cursor = start;
+                    return Err(CommandSyntaxError::at(format!("Tick count must not be less than {min}, found {ticks}"), reader))
+                }
+                ArgumentValue::Time(ticks)
+            }
+            ArgumentType::Uuid => {
+                let word = reader.read_word();
+                match Uuid::parse_str(word) {
+                    Ok(uuid) => ArgumentValue::Uuid(uuid),
+                    Err(_) => {
+                        reader.cursor = start;
+                        return Err(CommandSyntaxError::at("Invalid UUID", reader))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Suggestions that don't depend on server state, used for console completion and
+    /// `ask_server` nodes.
+    pub fn suggestions(&self) -> Vec<String> {
+        match self {
+            ArgumentType::Bool => vec!["true".into(), "false".into()],
+            ArgumentType::GameMode => GameMode::ALL.iter().map(|mode| mode.name().into()).collect(),
+            ArgumentType::Entity { single, players_only } => {
+                let mut selectors = vec!["@p", "@r", "@s"];
+                if !single { selectors.push("@a") }
+                if !single && !players_only { selectors.push("@e") }
+                selectors.into_iter().map(String::from).collect()
+            }
+            ArgumentType::GameProfile => vec!["@p".into(), "@r".into(), "@s".into(), "@a".into()],
+            ArgumentType::BlockPos | ArgumentType::Vec3 => vec!["~ ~ ~".into()],
+            _ => vec![],
+        }
+    }
+
+    /// Whether suggestions for online player names apply to this argument.
+    pub fn suggests_players(&self) -> bool {
+        matches!(self, ArgumentType::Entity { .. } | ArgumentType::GameProfile)
+    }
+
+    /// The parser ID (index in the `command_argument_type` registry) and its properties.
+    pub fn parser(&self) -> ArgumentParser {
+        let mut properties = PacketByteBuffer::empty();
+        let id = match self {
+            ArgumentType::Bool => 0,
+            ArgumentType::Float { min, max } => {
+                write_range_flags(&mut properties, min.is_some(), max.is_some());
+                min.iter().chain(max.iter()).for_each(|v| properties.write_bytes(&v.to_be_bytes()));
+                1
+            }
+            ArgumentType::Double { min, max } => {
+                write_range_flags(&mut properties, min.is_some(), max.is_some());
+                min.iter().chain(max.iter()).for_each(|v| properties.write_bytes(&v.to_be_bytes()));
+                2
+            }
+            ArgumentType::Integer { min, max } => {
+                write_range_flags(&mut properties, min.is_some(), max.is_some());
+                min.iter().chain(max.iter()).for_each(|v| properties.write_bytes(&v.to_be_bytes()));
+                3
+            }
+            ArgumentType::Long { min, max } => {
+                write_range_flags(&mut properties, min.is_some(), max.is_some());
+                min.iter().chain(max.iter()).for_each(|v| properties.write_i64(*v));
+                4
+            }
+            ArgumentType::String(kind) => {
+                properties.write_var_int(&VarInt(match kind {
+                    StringKind::Word => 0,
+                    StringKind::Quotable => 1,
+                    StringKind::Greedy => 2,
+                }));
+                5
+            }
+            ArgumentType::Entity { single, players_only } => {
+                properties.write_bytes(&[(*single as u8) | ((*players_only as u8) << 1)]);
+                6
+            }
+            ArgumentType::GameProfile => 7,
+            ArgumentType::BlockPos => 8,
+            ArgumentType::Vec3 => 10,
+            ArgumentType::ItemStack => 14,
+            ArgumentType::Component => 17,
+            ArgumentType::Message => 19,
+            ArgumentType::ResourceLocation => 35,
+            ArgumentType::Dimension => 40,
+            ArgumentType::GameMode => 41,
+            ArgumentType::Time { min } => {
+                properties.write_bytes(&min.to_be_bytes());
+                42
+            }
+            ArgumentType::Uuid => 53,
+        };
+        ArgumentParser { id, properties: properties.buffer().to_vec() }
+    }
+}
+
+fn write_range_flags(properties: &mut PacketByteBuffer, min: bool, max: bool) {
+    properties.write_bytes(&[(min as u8) | ((max as u8) << 1)]);
+}
+
+fn parse_selector(reader: &mut StringReader) -> Result<EntitySelector, CommandSyntaxError> {
+    let start = reader.cursor;
+    if reader.peek() == Some('@') {
+        reader.skip();
+        let selector = match reader.read() {
+            Some('s') => EntitySelector::Sender,
+            Some('a') => EntitySelector::AllPlayers,
+            Some('p') => EntitySelector::NearestPlayer,
+            Some('r') => EntitySelector::RandomPlayer,
+            Some('e') => EntitySelector::AllEntities,
+            _ => {
+                reader.cursor = start;
+                return Err(CommandSyntaxError::at("Unknown selector type", reader))
+            }
+        };
+        if reader.peek() == Some('[') {
+            return Err(CommandSyntaxError::at("Selector arguments are not supported", reader))
+        }
+        return Ok(selector)
+    }
+
+    let word = reader.read_word();
+    if word.is_empty() {
+        return Err(CommandSyntaxError::at("Invalid name or UUID", reader))
+    }
+    if word.len() > 16 {
+        if let Ok(uuid) = Uuid::parse_str(word) {
+            return Ok(EntitySelector::Uuid(uuid))
+        }
+        reader.cursor = start;
+        return Err(CommandSyntaxError::at("Invalid name or UUID", reader))
+    }
+    Ok(EntitySelector::Player(word.to_string()))
+}
+
+fn parse_coordinates(reader: &mut StringReader, block: bool) -> Result<Coordinates, CommandSyntaxError> {
+    let start = reader.cursor;
+    let local = reader.peek() == Some('^');
+    let mut values = [WorldCoordinate { relative: false, value: 0.0 }; 3];
+
+    for (i, value) in values.iter_mut().enumerate() {
+        if i > 0 {
+            if reader.peek() != Some(' ') {
+                reader.cursor = start;
+                return Err(CommandSyntaxError::at("Incomplete (expected 3 coordinates)", reader))
+            }
+            reader.skip();
+        }
+
+        let prefix = reader.peek();
+        if (prefix == Some('^')) != local {
+            return Err(CommandSyntaxError::at("Cannot mix world & local coordinates (everything must either use ^ or not)", reader))
+        }
+        let relative = matches!(prefix, Some('~' | '^'));
+        if relative {
+            reader.skip();
+        }
+
+        let number_start = reader.cursor;
+        let number = if relative && reader.peek().is_none_or(|c| c == ' ') {
+            0.0
+        } else if block && !relative {
+            reader.read_number::<i32>("integer")? as f64
+        } else {
+            reader.read_number::<f64>("double")?
+        };
+        // like vanilla, whole absolute x/z positions point to the center of the block
+        let centered = !block && !relative && i != 1 && !reader.input()[number_start..reader.cursor].contains('.');
+        *value = WorldCoordinate { relative, value: if centered { number + 0.5 } else { number } };
+    }
+
+    Ok(match local {
+        true => Coordinates::Local(values.map(|value| value.value)),
+        false => Coordinates::World(values),
+    })
+}
+
+fn parse_resource_location(reader: &mut StringReader) -> Result<String, CommandSyntaxError> {
+    let start = reader.cursor;
+    while reader.peek().is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.' | ':' | '/')) {
+        reader.skip();
+    }
+    let location = reader.consumed()[start..].to_string();
+    let (namespace, path) = location.split_once(':').unwrap_or(("minecraft", &location));
+    if path.is_empty() || path.contains(':') || namespace.contains('/') {
+        reader.cursor = start;
+        return Err(CommandSyntaxError::at("Invalid ID", reader))
+    }
+    Ok(format!("{namespace}:{path}"))
+}
+
+fn parse_component(reader: &mut StringReader) -> Result<TextComponent, CommandSyntaxError> {
+    let mut stream = serde_json::Deserializer::from_str(reader.remaining())
+        .into_iter::<serde_json::Value>();
+    match stream.next() {
+        Some(Ok(value)) => {
+            reader.cursor += stream.byte_offset();
+            Ok(component_from_json(&value))
+        }
+        _ => Err(CommandSyntaxError::at("Invalid chat component", reader)),
+    }
+}
+
+fn component_from_json(value: &serde_json::Value) -> TextComponent {
+    match value {
+        serde_json::Value::String(text) => TextComponent::text(text),
+        serde_json::Value::Array(values) => values.iter()
+            .fold(TextComponent::text(""), |component, value| component.append(component_from_json(value))),
+        serde_json::Value::Object(object) => {
+            let mut component = TextComponent::text(object.get("text").and_then(|t| t.as_str()).unwrap_or(""));
+            component.color = object.get("color").and_then(|c| c.as_str()).map(String::from);
+            component.bold = object.get("bold").and_then(|b| b.as_bool());
+            component.italic = object.get("italic").and_then(|i| i.as_bool());
+            if let Some(extra) = object.get("extra").and_then(|e| e.as_array()) {
+                component.extra = extra.iter().map(component_from_json).collect();
+            }
+            component
+        }
+        value => TextComponent::text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argument: ArgumentType, input: &str) -> Result<ArgumentValue, CommandSyntaxError> {
+        argument.parse(&mut StringReader::new(input))
+    }
+
+    #[test]
+    fn ranges() {
+        let integer = ArgumentType::Integer { min: Some(0), max: Some(10) };
+        assert_eq!(parse(integer.clone(), "10").unwrap(), ArgumentValue::Integer(10));
+        assert_eq!(parse(integer.clone(), "11").unwrap_err().message, "Integer must not be more than 10, found 11");
+        assert_eq!(parse(integer, "-1").unwrap_err().message, "Integer must not be less than 0, found -1");
+    }
+
+    #[test]
+    fn strings() {
+        let mut reader = StringReader::new("one two");
+        assert_eq!(ArgumentType::String(StringKind::Word).parse(&mut reader).unwrap(), ArgumentValue::String("one".into()));
+        assert_eq!(parse(ArgumentType::String(StringKind::Quotable), r#""one two" three"#).unwrap(),
+            ArgumentValue::String("one two".into()));
+        assert_eq!(parse(ArgumentType::String(StringKind::Greedy), "one two").unwrap(),
+            ArgumentValue::String("one two".into()));
+    }
+
+    #[test]
+    fn selectors() {
+        let single = ArgumentType::Entity { single: true, players_only: true };
+        assert_eq!(parse(single.clone(), "Notch").unwrap(), ArgumentValue::Entity(EntitySelector::Player("Notch".into())));
+        assert_eq!(parse(single.clone(), "@s").unwrap(), ArgumentValue::Entity(EntitySelector::Sender));
+        assert!(parse(single.clone(), "@a").is_err());
+        assert!(parse(single.clone(), "@x").is_err());
+        assert!(parse(single.clone(), "@p[distance=1]").is_err());
+        assert!(parse(single, "a_name_that_is_too_long").is_err());
+
+        let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        assert_eq!(parse(ArgumentType::GameProfile, uuid).unwrap(),
+            ArgumentValue::Entity(EntitySelector::Uuid(Uuid::parse_str(uuid).unwrap())));
+        assert!(parse(ArgumentType::Entity { single: false, players_only: true }, "@e").is_err());
+    }
+
+    #[test]
+    fn coordinates() {
+        let ArgumentValue::Coordinates(position) = parse(ArgumentType::Vec3, "1 ~2 -3.5").unwrap() else { panic!() };
+        assert_eq!(position.resolve((10.0, 20.0, 30.0), (0.0, 0.0)), (1.5, 22.0, -3.5));
+
+        let ArgumentValue::Coordinates(block) = parse(ArgumentType::BlockPos, "~ ~-1 4").unwrap() else { panic!() };
+        assert_eq!(block.resolve_block((0.5, 64.0, 0.5), (0.0, 0.0)), (0, 63, 4));
+
+        let ArgumentValue::Coordinates(local) = parse(ArgumentType::Vec3, "^ ^ ^2").unwrap() else { panic!() };
+        let (x, y, z) = local.resolve((0.0, 0.0, 0.0), (0.0, 0.0));
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6 && (z - 2.0).abs() < 1e-6, "yaw 0 faces south");
+
+        assert_eq!(parse(ArgumentType::Vec3, "1 2").unwrap_err().message, "Incomplete (expected 3 coordinates)");
+        assert!(parse(ArgumentType::Vec3, "^ ~ ^").is_err());
+        assert!(parse(ArgumentType::BlockPos, "1.5 2 3").is_err());
+    }
+
+    #[test]
+    fn time_and_resources() {
+        assert_eq!(parse(ArgumentType::Time { min: 0 }, "1d").unwrap(), ArgumentValue::Time(24000));
+        assert_eq!(parse(ArgumentType::Time { min: 0 }, "2s").unwrap(), ArgumentValue::Time(40));
+        assert!(parse(ArgumentType::Time { min: 0 }, "1x").is_err());
+        assert!(parse(ArgumentType::Time { min: 1 }, "0").is_err());
+
+        assert_eq!(parse(ArgumentType::Dimension, "the_nether").unwrap(),
+            ArgumentValue::ResourceLocation("minecraft:the_nether".into()));
+        assert!(parse(ArgumentType::ResourceLocation, "a:b:c").is_err());
+        assert_eq!(parse(ArgumentType::GameMode, "creative").unwrap(), ArgumentValue::GameMode(GameMode::Creative));
+        assert_eq!(parse(ArgumentType::GameMode, "hard").unwrap_err().message, "Unknown game mode: hard");
+    }
+}
@@ -0,0 +1,147 @@
+use std::net::IpAddr;
+use crate::command::arguments::{ArgumentType, StringKind};
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal};
+use crate::command::{CommandDispatcher, CommandFuture, SuggestionFuture, ADMIN_PERMISSION_LEVEL};
+use crate::server::access::{self, IpBanEntry, UserBanEntry};
+use super::permission;
+
+const DEFAULT_REASON: &str = "Banned by an operator";
+const IP_BANNED: &str = "You have been IP banned from this server";
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("ban")
+        .requires(permission("minecraft.command.ban", ADMIN_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::GameProfile)
+            .executes(ban)
+            .then(argument("reason", ArgumentType::Message)
+                .executes(ban))));
+    dispatcher.register(literal("ban-ip")
+        .requires(permission("minecraft.command.ban-ip", ADMIN_PERMISSION_LEVEL))
+        .then(argument("target", ArgumentType::String(StringKind::Word))
+            .executes(ban_ip)
+            .then(argument("reason", ArgumentType::Message)
+                .executes(ban_ip))));
+    dispatcher.register(literal("pardon")
+        .requires(permission("minecraft.command.pardon", ADMIN_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::GameProfile)
+            .suggests(banned_players)
+            .executes(pardon)));
+    dispatcher.register(literal("pardon-ip")
+        .requires(permission("minecraft.command.pardon-ip", ADMIN_PERMISSION_LEVEL))
+        .then(argument("target", ArgumentType::String(StringKind::Word))
+            .suggests(banned_ips)
+            .executes(pardon_ip)));
+}
+
+fn ban<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let reason = context.string("reason").unwrap_or_else(|| DEFAULT_REASON.into());
+        let source = context.sender.name().await;
+        let mut banned = 0;
+        for profile in context.profiles("targets").await? {
+            let entry = UserBanEntry {
+                uuid: profile.uuid,
+                name: profile.name.clone(),
+                created: access::now(),
+                source: source.clone(),
+                expires: None,
+                reason: reason.clone(),
+            };
+            let message = entry.kick_message();
+            if !context.server.access.banned_players.add(entry) {
+                continue
+            }
+            banned += 1;
+            context.feedback(format!("Banned {}: {}", profile.name, reason)).await;
+            if let Some(player) = context.server.player_by_uuid(&profile.uuid).await {
+                player.disconnect(&message).await
+            }
+        }
+
+        match banned {
+            0 => Err("Nothing changed. The player is already banned".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn ban_ip<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let target = context.string("target").unwrap_or_default();
+        let ip = match target.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => match context.server.player_by_name(&target).await {
+                Some(player) => player.address.read().await.ip(),
+                None => return Err("Invalid IP address or unknown player".into()),
+            },
+        };
+
+        let reason = context.string("reason").unwrap_or_else(|| DEFAULT_REASON.into());
+        let added = context.server.access.banned_ips.add(IpBanEntry {
+            ip: ip.to_string(),
+            created: access::now(),
+            source: context.sender.name().await,
+            expires: None,
+            reason: reason.clone(),
+        });
+        if !added {
+            return Err("Nothing changed. That IP is already banned".into())
+        }
+
+        let mut names = vec![];
+        for player in context.server.online_players().await {
+            if player.address.read().await.ip() == ip {
+                names.push(player.name().await);
+                player.disconnect(IP_BANNED).await
+            }
+        }
+        context.feedback(format!("Banned IP {ip}: {reason}")).await;
+        if !names.is_empty() {
+            context.feedback(format!("IP ban affected {} player(s): {}", names.len(), names.join(", "))).await
+        }
+        Ok(())
+    })
+}
+
+fn pardon<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let mut pardoned = 0;
+        for profile in context.profiles("targets").await? {
+            let entry = context.server.access.banned_players.entries().into_iter()
+                .find(|entry| entry.uuid == profile.uuid || entry.name.eq_ignore_ascii_case(&profile.name));
+            if let Some(entry) = entry {
+                context.server.access.banned_players.remove(&entry.uuid);
+                pardoned += 1;
+                context.feedback(format!("Unbanned {}", entry.name)).await
+            }
+        }
+
+        match pardoned {
+            0 => Err("Nothing changed. The player isn't banned".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn pardon_ip<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let target = context.string("target").unwrap_or_default();
+        let Ok(ip) = target.parse::<IpAddr>() else {
+            return Err("Invalid IP address".into())
+        };
+        if context.server.access.banned_ips.remove(&ip.to_string()).is_none() {
+            return Err("Nothing changed. That IP isn't banned".into())
+        }
+        context.feedback(format!("Unbanned IP {ip}")).await;
+        Ok(())
+    })
+}
+
+fn banned_players<'a>(context: &'a CommandContext<'a>) -> SuggestionFuture<'a> {
+    Box::pin(async move { context.server.access.banned_players.names() })
+}
+
+fn banned_ips<'a>(context: &'a CommandContext<'a>) -> SuggestionFuture<'a> {
+    Box::pin(async move { context.server.access.banned_ips.names() })
+}
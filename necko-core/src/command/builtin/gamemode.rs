@@ -0,0 +1,53 @@
+use necko_protocol::types::GameMode;
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, CommandSender, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("gamemode")
+        .requires(permission("minecraft.command.gamemode", GAMEMASTER_PERMISSION_LEVEL))
+        .then(argument("gamemode", ArgumentType::GameMode)
+            .executes(gamemode)
+            .then(argument("target", ArgumentType::Entity { single: false, players_only: true })
+                .executes(gamemode)))
+}
+
+fn display_name(game_mode: GameMode) -> &'static str {
+    match game_mode {
+        GameMode::Survival => "Survival Mode",
+        GameMode::Creative => "Creative Mode",
+        GameMode::Adventure => "Adventure Mode",
+        GameMode::Spectator => "Spectator Mode",
+    }
+}
+
+fn gamemode<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let game_mode = context.game_mode("gamemode").unwrap_or_default();
+        let targets = match (context.selector("target"), context.sender) {
+            (Some(_), _) => context.players("target").await?,
+            (None, CommandSender::Player(client)) => vec![client.clone()],
+            (None, _) => return Err("A player is required to run this command here".into()),
+        };
+
+        let mode = display_name(game_mode);
+        for target in targets {
+            if target.player.lock().await.game_mode == game_mode {
+                continue
+            }
+            target.set_game_mode(game_mode).await;
+
+            let is_sender = matches!(context.sender, CommandSender::Player(sender) if std::sync::Arc::ptr_eq(sender, &target));
+            if is_sender {
+                context.feedback(format!("Set own game mode to {mode}")).await
+            } else {
+                context.feedback(format!("Set {}'s game mode to {mode}", target.name().await)).await;
+                CommandSender::Player(target)
+                    .send_message(format!("Your game mode has been updated to {mode}")).await
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,52 @@
+use necko_protocol::text::TextComponent;
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use crate::client::player::MAX_STACK_SIZE;
+use crate::registry::Item;
+use super::permission;
+
+/// Vanilla caps `/give` at 100 stacks.
+const MAX_COUNT: i32 = MAX_STACK_SIZE * 100;
+
+pub fn command() -> CommandBuilder {
+    literal("give")
+        .requires(permission("minecraft.command.give", GAMEMASTER_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::Entity { single: false, players_only: true })
+            .then(argument("item", ArgumentType::ItemStack)
+                .executes(give)
+                .then(argument("count", ArgumentType::Integer { min: Some(1), max: Some(MAX_COUNT) })
+                    .executes(give))))
+}
+
+fn give<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let name = context.resource_location("item").unwrap_or_default();
+        let Some(item) = Item::from_name(&name).filter(|item| *item != Item::Air) else {
+            return Err(format!("Unknown item '{name}'"))
+        };
+        let item = item.name();
+        let count = context.integer("count").unwrap_or(1);
+        let targets = context.players("targets").await?;
+
+        let name = item.strip_prefix("minecraft:").unwrap_or(item);
+        let mut leftovers = Vec::new();
+        for target in &targets {
+            let left = target.give(item, count).await;
+            if left > 0 {
+                leftovers.push(format!("{left} [{name}] didn't fit into the inventory of {}", target.name().await));
+            }
+        }
+
+        let message = match targets.as_slice() {
+            [target] => format!("Gave {count} [{name}] to {}", target.name().await),
+            targets => format!("Gave {count} [{name}] to {} players", targets.len()),
+        };
+        context.feedback(message).await;
+        for leftover in leftovers {
+            context.sender.send_message(TextComponent::text(leftover).color("red")).await
+        }
+        Ok(())
+    })
+}
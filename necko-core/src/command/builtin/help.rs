@@ -0,0 +1,34 @@
+use crate::command::arguments::{ArgumentType, StringKind};
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, ALL_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("help")
+        .requires(permission("minecraft.command.help", ALL_PERMISSION_LEVEL))
+        .executes(list)
+        .then(argument("command", ArgumentType::String(StringKind::Greedy))
+            .executes(usage))
+}
+
+fn list<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        for usage in context.server.commands.smart_usage(context.sender) {
+            context.sender.send_message(format!("/{usage}")).await
+        }
+        Ok(())
+    })
+}
+
+fn usage<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let command = context.string("command").unwrap_or_default();
+        let name = command.split(' ').next().unwrap_or_default();
+        let Some(usage) = context.server.commands.command_usage(context.sender, name) else {
+            return Err("Unknown command or insufficient permissions".into())
+        };
+        context.sender.send_message(format!("/{usage}")).await;
+        Ok(())
+    })
+}
@@ -0,0 +1,28 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, ADMIN_PERMISSION_LEVEL};
+use super::permission;
+
+const DEFAULT_REASON: &str = "Kicked by an operator";
+
+pub fn command() -> CommandBuilder {
+    literal("kick")
+        .requires(permission("minecraft.command.kick", ADMIN_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::Entity { single: false, players_only: true })
+            .executes(kick)
+            .then(argument("reason", ArgumentType::Message)
+                .executes(kick)))
+}
+
+fn kick<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let reason = context.string("reason").unwrap_or_else(|| DEFAULT_REASON.into());
+        for player in context.players("targets").await? {
+            let name = player.name().await;
+            player.disconnect(&reason).await;
+            context.feedback(format!("Kicked {name}: {reason}")).await
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,33 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, CommandSender, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("kill")
+        .requires(permission("minecraft.command.kill", GAMEMASTER_PERMISSION_LEVEL))
+        .executes(kill)
+        .then(argument("targets", ArgumentType::Entity { single: false, players_only: false })
+            .executes(kill))
+}
+
+fn kill<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let targets = match (context.selector("targets"), context.sender) {
+            (Some(_), _) => context.players("targets").await?,
+            (None, CommandSender::Player(client)) => vec![client.clone()],
+            (None, _) => return Err("A player is required to run this command here".into()),
+        };
+
+        for target in &targets {
+            target.set_health(0.0).await
+        }
+        let message = match targets.as_slice() {
+            [target] => format!("Killed {}", target.name().await),
+            targets => format!("Killed {} entities", targets.len()),
+        };
+        context.feedback(message).await;
+        Ok(())
+    })
+}
@@ -0,0 +1,20 @@
+use crate::command::context::CommandContext;
+use crate::command::node::{literal, CommandBuilder};
+use crate::command::{CommandFuture, ALL_PERMISSION_LEVEL};
+use super::{join_names, permission};
+
+pub fn command() -> CommandBuilder {
+    literal("list")
+        .requires(permission("minecraft.command.list", ALL_PERMISSION_LEVEL))
+        .executes(list)
+}
+
+fn list<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let names = context.server.player_names().await;
+        let max = context.server.config.max_players;
+        context.sender.send_message(format!("There are {} of a max of {} players online: {}",
+            names.len(), max, join_names(&names))).await;
+        Ok(())
+    })
+}
@@ -0,0 +1,53 @@
+use crate::command::{CommandDispatcher, CommandSender};
+
+mod ban;
+mod gamemode;
+mod give;
+mod help;
+mod kick;
+mod kill;
+mod list;
+mod op;
+mod permissions;
+mod say;
+mod seed;
+mod stop;
+mod teleport;
+mod tell;
+mod time;
+mod tps;
+mod weather;
+mod whitelist;
+mod world;
+
+pub fn register_all(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(help::command());
+    dispatcher.register(stop::command());
+    dispatcher.register(list::command());
+    dispatcher.register(kick::command());
+    ban::register(dispatcher);
+    op::register(dispatcher);
+    dispatcher.register(permissions::command());
+    dispatcher.register(whitelist::command());
+    dispatcher.register(say::command());
+    tell::register(dispatcher);
+    teleport::register(dispatcher);
+    dispatcher.register(gamemode::command());
+    dispatcher.register(time::command());
+    dispatcher.register(weather::command());
+    dispatcher.register(give::command());
+    dispatcher.register(kill::command());
+    dispatcher.register(seed::command());
+    dispatcher.register(tps::command());
+    dispatcher.register(world::command());
+}
+
+/// Requires a permission node, granted by default from the given op level.
+fn permission(node: &'static str, level: u8) -> impl Fn(&CommandSender) -> bool + Send + Sync + 'static {
+    move |sender| sender.has_permission(node, level)
+}
+
+/// Joins names like vanilla lists them in messages.
+fn join_names(names: &[String]) -> String {
+    names.join(", ")
+}
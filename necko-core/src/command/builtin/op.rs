@@ -0,0 +1,71 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal};
+use crate::command::{CommandDispatcher, CommandFuture, SuggestionFuture, ADMIN_PERMISSION_LEVEL};
+use crate::server::access::OpEntry;
+use super::permission;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("op")
+        .requires(permission("minecraft.command.op", ADMIN_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::GameProfile)
+            .executes(op)));
+    dispatcher.register(literal("deop")
+        .requires(permission("minecraft.command.deop", ADMIN_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::GameProfile)
+            .suggests(operators)
+            .executes(deop)));
+}
+
+fn op<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let level = context.server.config.access.op_permission_level;
+        let mut changed = 0;
+        for profile in context.profiles("targets").await? {
+            if context.server.access.ops.contains(&profile.uuid) {
+                continue
+            }
+            context.server.access.ops.add(OpEntry {
+                uuid: profile.uuid,
+                name: profile.name.clone(),
+                level,
+                bypasses_player_limit: false,
+            });
+            changed += 1;
+            context.feedback(format!("Made {} a server operator", profile.name)).await;
+            if let Some(player) = context.server.player_by_uuid(&profile.uuid).await {
+                player.update_permission_level(context.server).await
+            }
+        }
+
+        match changed {
+            0 => Err("Nothing changed. The player already is an operator".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn deop<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let mut changed = 0;
+        for profile in context.profiles("targets").await? {
+            if context.server.access.ops.remove(&profile.uuid).is_none() {
+                continue
+            }
+            changed += 1;
+            context.feedback(format!("Made {} no longer a server operator", profile.name)).await;
+            if let Some(player) = context.server.player_by_uuid(&profile.uuid).await {
+                player.update_permission_level(context.server).await
+            }
+        }
+
+        match changed {
+            0 => Err("Nothing changed. The player is not an operator".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn operators<'a>(context: &'a CommandContext<'a>) -> SuggestionFuture<'a> {
+    Box::pin(async move { context.server.access.ops.names() })
+}
@@ -0,0 +1,23 @@
+use crate::command::context::CommandContext;
+use crate::command::node::{literal, CommandBuilder};
+use crate::command::{CommandFuture, ADMIN_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("permissions")
+        .requires(permission("necko.command.permissions", ADMIN_PERMISSION_LEVEL))
+        .then(literal("reload")
+            .executes(reload))
+}
+
+/// Reads the permissions again and applies them to everyone online.
+fn reload<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        context.server.permissions.reload().map_err(|e| e.to_string())?;
+        for player in context.server.online_players().await {
+            player.update_permission_level(context.server).await
+        }
+        context.feedback("Reloaded the permissions").await;
+        Ok(())
+    })
+}
@@ -0,0 +1,21 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("say")
+        .requires(permission("minecraft.command.say", GAMEMASTER_PERMISSION_LEVEL))
+        .then(argument("message", ArgumentType::Message)
+            .executes(say))
+}
+
+fn say<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let message = context.string("message").unwrap_or_default();
+        let name = context.sender.name().await;
+        context.server.broadcast_message(format!("[{name}] {message}")).await;
+        Ok(())
+    })
+}
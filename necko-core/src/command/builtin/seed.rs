@@ -0,0 +1,17 @@
+use crate::command::context::CommandContext;
+use crate::command::node::{literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("seed")
+        .requires(permission("minecraft.command.seed", GAMEMASTER_PERMISSION_LEVEL))
+        .executes(seed)
+}
+
+fn seed<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        context.sender.send_message(format!("Seed: [{}]", context.world().await.seed)).await;
+        Ok(())
+    })
+}
@@ -0,0 +1,18 @@
+use crate::command::context::CommandContext;
+use crate::command::node::{literal, CommandBuilder};
+use crate::command::{CommandFuture, MAX_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("stop")
+        .requires(permission("minecraft.command.stop", MAX_PERMISSION_LEVEL))
+        .executes(stop)
+}
+
+fn stop<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        context.feedback("Stopping the server").await;
+        context.server.request_stop();
+        Ok(())
+    })
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use crate::client::Client;
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal};
+use crate::command::{CommandDispatcher, CommandFuture, CommandSender, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+const ENTITY: ArgumentType = ArgumentType::Entity { single: true, players_only: false };
+const ENTITIES: ArgumentType = ArgumentType::Entity { single: false, players_only: false };
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("teleport")
+        .requires(permission("minecraft.command.teleport", GAMEMASTER_PERMISSION_LEVEL))
+        .then(argument("location", ArgumentType::Vec3)
+            .executes(to_location))
+        .then(argument("destination", ENTITY)
+            .executes(to_entity))
+        .then(argument("targets", ENTITIES)
+            .then(argument("location", ArgumentType::Vec3)
+                .executes(to_location))
+            .then(argument("destination", ENTITY)
+                .executes(to_entity))));
+    dispatcher.register(literal("tp")
+        .requires(permission("minecraft.command.teleport", GAMEMASTER_PERMISSION_LEVEL))
+        .redirect("teleport"));
+}
+
+/// The `targets` argument, or the sender when it was left out.
+pub(super) async fn targets(context: &CommandContext<'_>) -> Result<Vec<Arc<Client>>, String> {
+    if context.selector("targets").is_some() {
+        return context.players("targets").await
+    }
+    match context.sender {
+        CommandSender::Player(client) => Ok(vec![client.clone()]),
+        _ => Err("A player is required to run this command here".into()),
+    }
+}
+
+fn to_location<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let targets = targets(context).await?;
+        let (origin, rotation) = context.origin().await;
+        let location = context.coordinates("location").unwrap().resolve(origin, rotation);
+        let world = context.world().await;
+
+        for target in &targets {
+            let player = target.player.lock().await.clone();
+            target.teleport_to(context.server, world.clone(), location, player.yaw, player.pitch).await
+        }
+        let (x, y, z) = location;
+        context.feedback(format!("Teleported {} to {x:.2}, {y:.2}, {z:.2}", describe(&targets).await)).await;
+        Ok(())
+    })
+}
+
+fn to_entity<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let targets = targets(context).await?;
+        let destination = context.player("destination").await?;
+        let player = destination.player.lock().await.clone();
+        let world = destination.world(context.server).await;
+
+        for target in &targets {
+            target.teleport_to(context.server, world.clone(), player.position, player.yaw, player.pitch).await
+        }
+        context.feedback(format!("Teleported {} to {}", describe(&targets).await, destination.name().await)).await;
+        Ok(())
+    })
+}
+
+pub(super) async fn describe(targets: &[Arc<Client>]) -> String {
+    match targets {
+        [target] => target.name().await,
+        targets => format!("{} entities", targets.len()),
+    }
+}
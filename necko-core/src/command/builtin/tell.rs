@@ -0,0 +1,34 @@
+use necko_protocol::text::TextComponent;
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal};
+use crate::command::{CommandDispatcher, CommandFuture, CommandSender, ALL_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("msg")
+        .requires(permission("minecraft.command.msg", ALL_PERMISSION_LEVEL))
+        .then(argument("targets", ArgumentType::Entity { single: false, players_only: true })
+            .then(argument("message", ArgumentType::Message)
+                .executes(tell))));
+    dispatcher.register(literal("tell").redirect("msg"));
+    dispatcher.register(literal("w").redirect("msg"));
+}
+
+fn tell<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let message = context.string("message").unwrap_or_default();
+        let name = context.sender.name().await;
+        for player in context.players("targets").await? {
+            let target = player.name().await;
+            context.sender.send_message(whisper(format!("You whisper to {target}: {message}"))).await;
+            CommandSender::Player(player)
+                .send_message(whisper(format!("{name} whispers to you: {message}"))).await
+        }
+        Ok(())
+    })
+}
+
+fn whisper(text: String) -> TextComponent {
+    TextComponent::text(text).color("gray").italic(true)
+}
@@ -0,0 +1,76 @@
+use necko_protocol::packets::clientbound::play::set_time::SetTimeClientbound;
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use crate::world::{World, WorldTime, DAY_LENGTH};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("time")
+        .requires(permission("minecraft.command.time", GAMEMASTER_PERMISSION_LEVEL))
+        .then(literal("set")
+            .then(literal("day").executes(set))
+            .then(literal("noon").executes(set))
+            .then(literal("night").executes(set))
+            .then(literal("midnight").executes(set))
+            .then(argument("time", ArgumentType::Time { min: 0 }).executes(set)))
+        .then(literal("add")
+            .then(argument("time", ArgumentType::Time { min: 0 }).executes(add)))
+        .then(literal("query")
+            .then(literal("daytime").executes(query))
+            .then(literal("gametime").executes(query))
+            .then(literal("day").executes(query)))
+}
+
+/// The literal the command ended with, e.g. `noon` in `time set noon`.
+fn last_word<'a>(context: &CommandContext<'a>) -> &'a str {
+    context.input.trim_end().rsplit(' ').next().unwrap_or_default()
+}
+
+fn set<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let day_time = match context.time("time") {
+            Some(ticks) => ticks as i64,
+            None => match last_word(context) {
+                "day" => 1000,
+                "noon" => 6000,
+                "night" => 13000,
+                _ => 18000,
+            },
+        };
+        let world = context.world().await;
+        let time = world.set_day_time(day_time);
+        broadcast_time(context, &world, time).await;
+        context.feedback(format!("Set the time to {day_time}")).await;
+        Ok(())
+    })
+}
+
+fn add<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let ticks = context.time("time").unwrap_or_default() as i64;
+        let world = context.world().await;
+        let time = world.add_day_time(ticks);
+        broadcast_time(context, &world, time).await;
+        context.feedback(format!("Set the time to {}", time.day_time % DAY_LENGTH)).await;
+        Ok(())
+    })
+}
+
+fn query<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let time = context.world().await.time();
+        let value = match last_word(context) {
+            "daytime" => time.day_time % DAY_LENGTH,
+            "gametime" => time.game_time % i32::MAX as i64,
+            _ => time.day_time / DAY_LENGTH % i32::MAX as i64,
+        };
+        context.sender.send_message(format!("The time is {value}")).await;
+        Ok(())
+    })
+}
+
+async fn broadcast_time(context: &CommandContext<'_>, world: &World, time: WorldTime) {
+    context.server.broadcast_in(world, &SetTimeClientbound::new(time.game_time, time.day_time, true)).await
+}
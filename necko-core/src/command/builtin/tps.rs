@@ -0,0 +1,22 @@
+use crate::command::context::CommandContext;
+use crate::command::node::{literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use super::permission;
+
+pub fn command() -> CommandBuilder {
+    literal("tps")
+        .requires(permission("necko.command.tps", GAMEMASTER_PERMISSION_LEVEL))
+        .executes(tps)
+}
+
+fn tps<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let ticks = &context.server.ticks;
+        if ticks.count() == 0 {
+            return Err("No ticks have run yet".into())
+        }
+        context.sender.send_message(format!("TPS: {:.1}, MSPT: {:.2}, ticks: {}, skipped: {}",
+            ticks.tps(), ticks.mspt(), ticks.count(), ticks.skipped())).await;
+        Ok(())
+    })
+}
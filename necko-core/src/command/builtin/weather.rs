@@ -0,0 +1,44 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use crate::world::Weather;
+use super::permission;
+
+/// Duration used when none is given, five minutes.
+const DEFAULT_DURATION: i32 = 6000;
+
+pub fn command() -> CommandBuilder {
+    let duration = || argument("duration", ArgumentType::Time { min: 1 }).executes(weather);
+    literal("weather")
+        .requires(permission("minecraft.command.weather", GAMEMASTER_PERMISSION_LEVEL))
+        .then(literal("clear").executes(weather).then(duration()))
+        .then(literal("rain").executes(weather).then(duration()))
+        .then(literal("thunder").executes(weather).then(duration()))
+}
+
+fn weather<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let duration = context.time("duration").unwrap_or(DEFAULT_DURATION);
+        let kind = context.input.split(' ').nth(1).unwrap_or_default();
+        let (weather, message) = match kind {
+            "clear" => (Weather { clear_time: duration, ..Default::default() }, "clear"),
+            "rain" => (Weather { rain_time: duration, raining: true, ..Default::default() }, "rain"),
+            _ => (Weather {
+                rain_time: duration,
+                thunder_time: duration,
+                raining: true,
+                thundering: true,
+                ..Default::default()
+            }, "rain & thunder"),
+        };
+
+        let world = context.world().await;
+        let previous = world.weather();
+        world.set_weather(weather);
+        context.server.send_weather(&world, previous).await;
+
+        context.feedback(format!("Set the weather to {message}")).await;
+        Ok(())
+    })
+}
@@ -0,0 +1,113 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, SuggestionFuture, ADMIN_PERMISSION_LEVEL};
+use crate::server::access::WhitelistEntry;
+use super::{join_names, permission};
+
+pub fn command() -> CommandBuilder {
+    literal("whitelist")
+        .requires(permission("minecraft.command.whitelist", ADMIN_PERMISSION_LEVEL))
+        .then(literal("on")
+            .executes(on))
+        .then(literal("off")
+            .executes(off))
+        .then(literal("list")
+            .executes(list))
+        .then(literal("reload")
+            .executes(reload))
+        .then(literal("add")
+            .then(argument("targets", ArgumentType::GameProfile)
+                .executes(add)))
+        .then(literal("remove")
+            .then(argument("targets", ArgumentType::GameProfile)
+                .suggests(whitelisted)
+                .executes(remove)))
+}
+
+fn on<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        if context.server.access.whitelist_enabled() {
+            return Err("Whitelist is already turned on".into())
+        }
+        context.server.access.set_whitelist_enabled(true);
+        context.feedback("Whitelist is now turned on").await;
+        context.server.enforce_whitelist().await;
+        Ok(())
+    })
+}
+
+fn off<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        if !context.server.access.whitelist_enabled() {
+            return Err("Whitelist is already turned off".into())
+        }
+        context.server.access.set_whitelist_enabled(false);
+        context.feedback("Whitelist is now turned off").await;
+        Ok(())
+    })
+}
+
+fn list<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let names = context.server.access.whitelist.names();
+        let message = match names.len() {
+            0 => "There are no whitelisted players".to_string(),
+            count => format!("There are {count} whitelisted player(s): {}", join_names(&names)),
+        };
+        context.sender.send_message(message).await;
+        Ok(())
+    })
+}
+
+fn reload<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        context.server.access.whitelist.reload().map_err(|e| e.to_string())?;
+        context.feedback("Reloaded the whitelist").await;
+        context.server.enforce_whitelist().await;
+        Ok(())
+    })
+}
+
+fn add<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let mut added = 0;
+        for profile in context.profiles("targets").await? {
+            let entry = WhitelistEntry { uuid: profile.uuid, name: profile.name.clone() };
+            if context.server.access.whitelist.contains(&entry.uuid) {
+                continue
+            }
+            context.server.access.whitelist.add(entry);
+            added += 1;
+            context.feedback(format!("Added {} to the whitelist", profile.name)).await
+        }
+
+        match added {
+            0 => Err("Player is already whitelisted".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn remove<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let mut removed = 0;
+        for profile in context.profiles("targets").await? {
+            if context.server.access.whitelist.remove(&profile.uuid).is_none() {
+                continue
+            }
+            removed += 1;
+            context.feedback(format!("Removed {} from the whitelist", profile.name)).await
+        }
+        context.server.enforce_whitelist().await;
+
+        match removed {
+            0 => Err("Player is not whitelisted".into()),
+            _ => Ok(()),
+        }
+    })
+}
+
+fn whitelisted<'a>(context: &'a CommandContext<'a>) -> SuggestionFuture<'a> {
+    Box::pin(async move { context.server.access.whitelist.names() })
+}
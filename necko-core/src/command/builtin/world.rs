@@ -0,0 +1,46 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::context::CommandContext;
+use crate::command::node::{argument, literal, CommandBuilder};
+use crate::command::{CommandFuture, GAMEMASTER_PERMISSION_LEVEL};
+use super::teleport::{describe, targets};
+use super::{join_names, permission};
+
+const ENTITIES: ArgumentType = ArgumentType::Entity { single: false, players_only: false };
+
+pub fn command() -> CommandBuilder {
+    literal("world")
+        .requires(permission("necko.command.world", GAMEMASTER_PERMISSION_LEVEL))
+        .executes(list)
+        .then(argument("dimension", ArgumentType::Dimension)
+            .executes(send)
+            .then(argument("targets", ENTITIES)
+                .executes(send)))
+}
+
+fn list<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let worlds: Vec<_> = context.server.worlds.iter()
+            .map(|world| format!("{} ({})", world.name, world.dimension))
+            .collect();
+        context.sender.send_message(format!("There are {} worlds: {}", worlds.len(), join_names(&worlds))).await;
+        Ok(())
+    })
+}
+
+/// Sends players to the spawn point of a world.
+fn send<'a>(context: &'a CommandContext<'a>) -> CommandFuture<'a> {
+    Box::pin(async move {
+        let dimension = context.resource_location("dimension").unwrap_or_default();
+        let world = context.server.world(&dimension)
+            .ok_or_else(|| format!("Unknown world '{dimension}'"))?
+            .clone();
+        let targets = targets(context).await?;
+        let spawn = world.spawn();
+
+        for target in &targets {
+            target.teleport_to(context.server, world.clone(), spawn.center(), spawn.angle, 0.0).await
+        }
+        context.feedback(format!("Sent {} to {}", describe(&targets).await, world.name)).await;
+        Ok(())
+    })
+}
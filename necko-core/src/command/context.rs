@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use necko_protocol::packets::clientbound::play::system_chat::SystemChatClientbound;
+use necko_protocol::text::TextComponent;
+use necko_protocol::types::{GameMode, GameProfile};
+use uuid::Uuid;
+use crate::client::Client;
+use crate::client::forwarding;
+use crate::command::arguments::{ArgumentValue, Coordinates, EntitySelector};
+use crate::command::CommandSender;
+use crate::server::Server;
+use crate::world::World;
+
+/// The parsed arguments of a command, passed to its executor.
+pub struct CommandContext<'a> {
+    pub server: &'a Server,
+    pub sender: &'a CommandSender,
+    pub input: &'a str,
+    pub arguments: HashMap<String, ArgumentValue>,
+}
+
+macro_rules! getter {
+    ($name:ident, $variant:ident, $ty:ty) => {
+        pub fn $name(&self, name: &str) -> Option<$ty> {
+            match self.arguments.get(name) {
+                Some(ArgumentValue::$variant(value)) => Some(value.clone()),
+                _ => None,
+            }
+        }
+    };
+}
+
+impl CommandContext<'_> {
+    getter!(bool, Bool, bool);
+    getter!(float, Float, f32);
+    getter!(double, Double, f64);
+    getter!(integer, Integer, i32);
+    getter!(long, Long, i64);
+    getter!(string, String, String);
+    getter!(selector, Entity, EntitySelector);
+    getter!(coordinates, Coordinates, Coordinates);
+    getter!(resource_location, ResourceLocation, String);
+    getter!(component, Component, TextComponent);
+    getter!(game_mode, GameMode, GameMode);
+    getter!(time, Time, i32);
+    getter!(uuid, Uuid, Uuid);
+
+    /// Resolves an entity or game profile argument to the online players it selects.
+    pub async fn players(&self, name: &str) -> Result<Vec<Arc<Client>>, String> {
+        let selector = self.selector(name).ok_or_else(|| format!("No such argument '{name}'"))?;
+        let online = self.server.online_players().await;
+
+        let players = match selector {
+            EntitySelector::Player(player) => {
+                let mut found = vec![];
+                for client in online {
+                    if client.profile.read().await.as_ref().is_some_and(|p| p.name.eq_ignore_ascii_case(&player)) {
+                        found.push(client)
+                    }
+                }
+                found
+            }
+            EntitySelector::Uuid(uuid) => {
+                let mut found = vec![];
+                for client in online {
+                    if client.profile.read().await.as_ref().is_some_and(|p| p.uuid == uuid) {
+                        found.push(client)
+                    }
+                }
+                found
+            }
+            EntitySelector::Sender => match self.sender {
+                CommandSender::Player(client) => vec![client.clone()],
+                _ => return Err("A player is required to run this command here".into()),
+            },
+            EntitySelector::AllPlayers | EntitySelector::AllEntities => online,
+            EntitySelector::NearestPlayer => match self.sender {
+                CommandSender::Player(client) => vec![client.clone()],
+                _ => online.into_iter().take(1).collect(),
+            },
+            EntitySelector::RandomPlayer => {
+                let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos() as usize;
+                match online.len() {
+                    0 => vec![],
+                    len => vec![online[seed % len].clone()],
+                }
+            }
+        };
+
+        match players.is_empty() {
+            true => Err("No player was found".into()),
+            false => Ok(players),
+        }
+    }
+
+    /// Like [`Self::players`], for arguments that select a single player.
+    pub async fn player(&self, name: &str) -> Result<Arc<Client>, String> {
+        let mut players = self.players(name).await?;
+        match players.len() {
+            1 => Ok(players.remove(0)),
+            _ => Err("Only one player is allowed, but the provided selector allows more than one".into()),
+        }
+    }
+
+    /// Position and rotation relative coordinates are resolved against.
+    pub async fn origin(&self) -> ((f64, f64, f64), (f32, f32)) {
+        match self.sender {
+            CommandSender::Player(client) => {
+                let player = client.player.lock().await;
+                (player.position, (player.yaw, player.pitch))
+            }
+            _ => (self.server.default_world().spawn().center(), (0.0, 0.0)),
+        }
+    }
+
+    /// The world the sender is in, the one players join for the console.
+    pub async fn world(&self) -> Arc<World> {
+        match self.sender {
+            CommandSender::Player(client) => client.world(self.server).await,
+            _ => self.server.default_world().clone(),
+        }
+    }
+
+    /// Resolves a game profile argument, including players that aren't online by looking them
+    /// up in the user cache.
+    pub async fn profiles(&self, name: &str) -> Result<Vec<GameProfile>, String> {
+        if let Some(EntitySelector::Player(player)) = self.selector(name) {
+            for client in self.server.online_players().await {
+                if let Some(profile) = client.profile.read().await.as_ref().filter(|p| p.name.eq_ignore_ascii_case(&player)) {
+                    return Ok(vec![profile.clone()])
+                }
+            }
+            let (uuid, name) = match self.server.user_cache.get_by_name(&player) {
+                Some(entry) => (entry.uuid, entry.name),
+                None => (forwarding::offline_uuid(&player), player),
+            };
+            return Ok(vec![GameProfile { uuid, name, properties: vec![] }])
+        }
+
+        let mut profiles = vec![];
+        for client in self.players(name).await? {
+            if let Some(profile) = client.profile.read().await.clone() {
+                profiles.push(profile)
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// Sends the result of a command to its sender and, like vanilla, relays it to the console
+    /// and other operators when a player or RCON ran it.
+    pub async fn feedback(&self, message: impl Into<TextComponent>) {
+        let message = message.into();
+        self.sender.send_message(message.clone()).await;
+
+        let source = match self.sender {
+            CommandSender::Console => return,
+            CommandSender::Player(client) => Some(client),
+            CommandSender::Rcon(_) => None,
+        };
+        let relayed = TextComponent::text(format!("[{}: {}]", self.sender.name().await, message.plain()))
+            .color("gray")
+            .italic(true);
+        tracing::info!("{}", relayed.plain());
+        for player in self.server.online_players().await {
+            if !source.is_some_and(|source| Arc::ptr_eq(&player, source)) && player.has_permission("minecraft.admin.command_feedback") {
+                player.send_packet(&SystemChatClientbound::new(&relayed, false)).await
+            }
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use necko_protocol::packets::clientbound::play::commands::{
+    CommandNodeData, CommandsClientbound, FLAG_EXECUTABLE, NODE_ARGUMENT, NODE_LITERAL, NODE_ROOT
+};
+use necko_protocol::packets::clientbound::play::system_chat::SystemChatClientbound;
+use necko_protocol::text::TextComponent;
+use necko_protocol::types::VarIntType;
+use crate::client::Client;
+use crate::command::arguments::ArgumentValue;
+use crate::command::context::CommandContext;
+use crate::command::node::{CommandBuilder, CommandNode, NodeKind};
+use crate::command::reader::{CommandSyntaxError, StringReader};
+use crate::server::Server;
+
+pub mod arguments;
+pub mod builtin;
+pub mod context;
+pub mod node;
+pub mod reader;
+
+/// An error message shown to the sender when a command fails.
+pub type CommandResult = Result<(), String>;
+pub type CommandFuture<'a> = Pin<Box<dyn Future<Output = CommandResult> + Send + 'a>>;
+pub type Executor = for<'a> fn(&'a CommandContext<'a>) -> CommandFuture<'a>;
+pub type Requirement = Box<dyn Fn(&CommandSender) -> bool + Send + Sync>;
+pub type SuggestionFuture<'a> = Pin<Box<dyn Future<Output = Vec<String>> + Send + 'a>>;
+pub type SuggestionProvider = for<'a> fn(&'a CommandContext<'a>) -> SuggestionFuture<'a>;
+
+/// Op level granting access to every command, the level the console runs with.
+pub const MAX_PERMISSION_LEVEL: u8 = 4;
+/// Op level for server management commands like `/kick` and `/ban`.
+pub const ADMIN_PERMISSION_LEVEL: u8 = 3;
+/// Op level for gameplay commands like `/tp` and `/gamemode`.
+pub const GAMEMASTER_PERMISSION_LEVEL: u8 = 2;
+/// Op level of regular players, for commands everyone can use unless a permission denies it.
+pub const ALL_PERMISSION_LEVEL: u8 = 0;
+
+const ROOT: usize = 0;
+const ASK_SERVER: &str = "minecraft:ask_server";
+
+pub enum CommandSender {
+    Console,
+    Player(Arc<Client>),
+    /// A command received over RCON, collecting the messages sent back to it.
+    Rcon(Mutex<String>),
+}
+
+impl CommandSender {
+    pub async fn name(&self) -> String {
+        match self {
+            CommandSender::Console => "Server".into(),
+            CommandSender::Rcon(_) => "Rcon".into(),
+            CommandSender::Player(client) => client.name().await,
+        }
+    }
+
+    pub fn permission_level(&self) -> u8 {
+        match self {
+            CommandSender::Console | CommandSender::Rcon(_) => MAX_PERMISSION_LEVEL,
+            CommandSender::Player(client) => client.permission_level.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn has_permission_level(&self, level: u8) -> bool {
+        self.permission_level() >= level
+    }
+
+    /// Checks a permission node, falling back to the op level when the node isn't set.
+    pub fn has_permission(&self, node: &str, level: u8) -> bool {
+        match self {
+            CommandSender::Console | CommandSender::Rcon(_) => true,
+            CommandSender::Player(client) => client.permission(node)
+                .unwrap_or_else(|| self.has_permission_level(level)),
+        }
+    }
+
+    pub async fn send_message(&self, message: impl Into<TextComponent>) {
+        let message = message.into();
+        match self {
+            CommandSender::Console => tracing::info!("{}", message.plain()),
+            CommandSender::Player(client) => client
+                .send_packet(&SystemChatClientbound::new(&message, false)).await,
+            CommandSender::Rcon(output) => {
+                let mut output = output.lock().unwrap();
+                output.push_str(&message.plain());
+                output.push('\n')
+            }
+        }
+    }
+}
+
+/// A Brigadier-style command tree. Commands are parsed node by node, trying literals before
+/// arguments and backtracking when a branch doesn't match the rest of the input.
+pub struct CommandDispatcher {
+    nodes: Vec<CommandNode>,
+}
+
+impl Default for CommandDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct ParseResult {
+    executor: Option<Executor>,
+    arguments: HashMap<String, ArgumentValue>,
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        let mut dispatcher = CommandDispatcher { nodes: vec![CommandNode::root()] };
+        builtin::register_all(&mut dispatcher);
+        dispatcher
+    }
+
+    /// Adds a command to the tree, merging it with an existing command of the same name.
+    pub fn register(&mut self, command: CommandBuilder) {
+        self.insert(ROOT, command);
+    }
+
+    fn insert(&mut self, parent: usize, builder: CommandBuilder) -> usize {
+        let existing = self.nodes[parent].children.iter()
+            .copied()
+            .find(|child| self.nodes[*child].kind == builder.kind);
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                self.nodes.push(CommandNode {
+                    kind: builder.kind,
+                    ..CommandNode::root()
+                });
+                let index = self.nodes.len() - 1;
+                self.nodes[parent].children.push(index);
+                index
+            }
+        };
+
+        if builder.executor.is_some() { self.nodes[index].executor = builder.executor }
+        if builder.requirement.is_some() { self.nodes[index].requirement = builder.requirement }
+        if builder.suggestions.is_some() { self.nodes[index].suggestions = builder.suggestions }
+        if let Some(redirect) = builder.redirect {
+            match self.find_command(&redirect) {
+                Some(target) => self.nodes[index].redirect = Some(target),
+                None => tracing::warn!("Can't redirect '{}' to unknown command '{}'", self.nodes[index].name(), redirect),
+            }
+        }
+        for child in builder.children {
+            self.insert(index, child);
+        }
+        index
+    }
+
+    fn find_command(&self, name: &str) -> Option<usize> {
+        self.nodes[ROOT].children.iter()
+            .copied()
+            .find(|child| self.nodes[*child].kind == NodeKind::Literal(name.to_string()))
+    }
+
+    fn can_use(&self, node: usize, sender: &CommandSender) -> bool {
+        self.nodes[node].requirement.as_ref().is_none_or(|requirement| requirement(sender))
+    }
+
+    /// Children of `node`, or of its redirect target, with literals first.
+    fn children(&self, node: usize) -> Vec<usize> {
+        let node = self.nodes[node].redirect.unwrap_or(node);
+        let (mut literals, arguments): (Vec<usize>, Vec<usize>) = self.nodes[node].children.iter()
+            .partition(|child| self.nodes[**child].is_literal());
+        literals.extend(arguments);
+        literals
+    }
+
+    /// Runs a command line (without the leading `/`) as `sender`, reporting failures back to it.
+    pub async fn execute(&self, server: &Server, sender: &CommandSender, line: &str) {
+        let parsed = self.parse(sender, line).and_then(|result| match result.executor {
+            Some(executor) => Ok((executor, result.arguments)),
+            None => {
+                let mut reader = StringReader::new(line);
+                reader.cursor = line.len();
+                Err(CommandSyntaxError::at("Unknown or incomplete command, see below for error", &reader))
+            }
+        });
+        let (executor, arguments) = match parsed {
+            Ok(parsed) => parsed,
+            Err(error) => return report_syntax_error(sender, error).await,
+        };
+
+        let context = CommandContext { server, sender, input: line, arguments };
+        if let Err(message) = executor(&context).await {
+            sender.send_message(TextComponent::text(message).color("red")).await
+        }
+    }
+
+    fn parse(&self, sender: &CommandSender, line: &str) -> Result<ParseResult, CommandSyntaxError> {
+        self.parse_node(ROOT, &StringReader::new(line), sender, &HashMap::new())
+    }
+
+    fn parse_node(
+        &self,
+        node: usize,
+        reader: &StringReader,
+        sender: &CommandSender,
+        arguments: &HashMap<String, ArgumentValue>,
+    ) -> Result<ParseResult, CommandSyntaxError> {
+        let mut error = None;
+        for child in self.children(node) {
+            if !self.can_use(child, sender) {
+                continue
+            }
+
+            let mut reader = reader.clone();
+            let mut arguments = arguments.clone();
+            match &self.nodes[child].kind {
+                NodeKind::Root => continue,
+                NodeKind::Literal(name) => {
+                    if reader.read_word() != name {
+                        continue
+                    }
+                }
+                NodeKind::Argument { name, argument } => {
+                    let value = match argument.parse(&mut reader) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            error.get_or_insert(e);
+                            continue
+                        }
+                    };
+                    if reader.peek().is_some_and(|c| c != ' ') {
+                        error.get_or_insert(CommandSyntaxError::at(
+                            "Expected whitespace to end one argument, but found trailing data", &reader));
+                        continue
+                    }
+                    arguments.insert(name.clone(), value);
+                }
+            }
+
+            if !reader.can_read() {
+                return Ok(ParseResult { executor: self.nodes[child].executor, arguments })
+            }
+            reader.skip();
+            match self.parse_node(child, &reader, sender, &arguments) {
+                Ok(result) => return Ok(result),
+                Err(e) => error = Some(e),
+            }
+        }
+
+        Err(error.unwrap_or_else(|| match node {
+            ROOT => CommandSyntaxError::at("Unknown or incomplete command, see below for error", reader),
+            _ => CommandSyntaxError::at("Incorrect argument for command", reader),
+        }))
+    }
+
+    /// Completes the last word of `line` (without the leading `/`), returning where the
+    /// replaced text starts and the candidates.
+    pub async fn suggest(&self, server: &Server, sender: &CommandSender, line: &str) -> (usize, Vec<String>) {
+        let mut pending = vec![];
+        self.collect_suggestions(ROOT, &StringReader::new(line), sender, &HashMap::new(), &mut pending);
+
+        let start = pending.iter().map(|(start, ..)| *start).min().unwrap_or(line.len());
+        let mut matches = BTreeSet::new();
+        for (node_start, node, arguments) in pending {
+            let partial = &line[node_start..];
+            let candidates = match &self.nodes[node].kind {
+                NodeKind::Root => vec![],
+                NodeKind::Literal(name) => vec![name.clone()],
+                NodeKind::Argument { argument, .. } => match self.nodes[node].suggestions {
+                    Some(provider) => {
+                        let context = CommandContext { server, sender, input: line, arguments };
+                        provider(&context).await
+                    }
+                    None => {
+                        let mut candidates = argument.suggestions();
+                        if argument.suggests_players() {
+                            candidates.extend(server.player_names().await);
+                        }
+                        candidates
+                    }
+                },
+            };
+            let prefix = &line[start..node_start];
+            matches.extend(candidates.into_iter()
+                .filter(|candidate| candidate.to_lowercase().starts_with(&partial.to_lowercase()))
+                .map(|candidate| format!("{prefix}{candidate}")));
+        }
+        (start, matches.into_iter().collect())
+    }
+
+    fn collect_suggestions(
+        &self,
+        node: usize,
+        reader: &StringReader,
+        sender: &CommandSender,
+        arguments: &HashMap<String, ArgumentValue>,
+        pending: &mut Vec<(usize, usize, HashMap<String, ArgumentValue>)>,
+    ) {
+        for child in self.children(node) {
+            if !self.can_use(child, sender) {
+                continue
+            }
+
+            let start = reader.cursor;
+            let mut reader = reader.clone();
+            let mut arguments = arguments.clone();
+            let parsed = match &self.nodes[child].kind {
+                NodeKind::Root => continue,
+                NodeKind::Literal(name) => reader.read_word() == name,
+                NodeKind::Argument { name, argument } => match argument.parse(&mut reader) {
+                    Ok(value) => {
+                        arguments.insert(name.clone(), value);
+                        true
+                    }
+                    Err(_) => false,
+                },
+            };
+
+            if parsed && reader.peek() == Some(' ') {
+                reader.skip();
+                self.collect_suggestions(child, &reader, sender, &arguments, pending);
+            } else if !reader.input()[start..].contains(' ') || (parsed && !reader.can_read()) {
+                pending.push((start, child, arguments));
+            }
+        }
+    }
+
+    /// The usage of every root command the sender can use, e.g. `gamemode <gamemode> [<target>]`.
+    pub fn smart_usage(&self, sender: &CommandSender) -> Vec<String> {
+        self.nodes[ROOT].children.iter()
+            .filter(|child| self.can_use(**child, sender))
+            .map(|child| self.usage(*child, sender, false, true))
+            .collect()
+    }
+
+    pub fn command_usage(&self, sender: &CommandSender, name: &str) -> Option<String> {
+        self.find_command(name)
+            .filter(|command| self.can_use(*command, sender))
+            .map(|command| self.usage(command, sender, false, true))
+    }
+
+    fn usage(&self, node: usize, sender: &CommandSender, optional: bool, deep: bool) -> String {
+        let this = match optional {
+            true => format!("[{}]", self.nodes[node].usage_text()),
+            false => self.nodes[node].usage_text(),
+        };
+        if !deep {
+            return this
+        }
+        if let Some(redirect) = self.nodes[node].redirect {
+            return format!("{this} -> {}", self.nodes[redirect].usage_text())
+        }
+
+        let child_optional = self.nodes[node].executor.is_some();
+        let children: Vec<usize> = self.nodes[node].children.iter()
+            .copied()
+            .filter(|child| self.can_use(*child, sender))
+            .collect();
+        match children.as_slice() {
+            [] => this,
+            [child] => format!("{this} {}", self.usage(*child, sender, child_optional, true)),
+            children => {
+                let names: Vec<String> = children.iter().map(|child| self.nodes[*child].usage_text()).collect();
+                match child_optional {
+                    true => format!("{this} [{}]", names.join("|")),
+                    false => format!("{this} ({})", names.join("|")),
+                }
+            }
+        }
+    }
+
+    /// The tree as sent to a player, leaving out the commands it isn't allowed to use.
+    pub fn build_packet(&self, sender: &CommandSender) -> CommandsClientbound {
+        let mut indices = HashMap::from([(ROOT, 0)]);
+        let mut order = vec![ROOT];
+        let mut i = 0;
+        while i < order.len() {
+            let node = order[i];
+            for child in self.nodes[node].children.iter().chain(self.nodes[node].redirect.iter()) {
+                if !indices.contains_key(child) && self.can_use(*child, sender) {
+                    indices.insert(*child, order.len());
+                    order.push(*child);
+                }
+            }
+            i += 1;
+        }
+
+        let nodes = order.iter().map(|index| {
+            let node = &self.nodes[*index];
+            let mut flags = match node.kind {
+                NodeKind::Root => NODE_ROOT,
+                NodeKind::Literal(_) => NODE_LITERAL,
+                NodeKind::Argument { .. } => NODE_ARGUMENT,
+            };
+            if node.executor.is_some() {
+                flags |= FLAG_EXECUTABLE
+            }
+            CommandNodeData {
+                flags,
+                children: node.children.iter()
+                    .filter_map(|child| indices.get(child).map(|i| *i as VarIntType))
+                    .collect(),
+                redirect: node.redirect.and_then(|redirect| indices.get(&redirect).map(|i| *i as VarIntType)),
+                name: match node.kind {
+                    NodeKind::Root => None,
+                    _ => Some(node.name().to_string()),
+                },
+                parser: match &node.kind {
+                    NodeKind::Argument { argument, .. } => Some(argument.parser()),
+                    _ => None,
+                },
+                suggestions: node.suggestions.map(|_| ASK_SERVER.to_string()),
+            }
+        }).collect();
+        CommandsClientbound::new(nodes, 0)
+    }
+}
+
+async fn report_syntax_error(sender: &CommandSender, error: CommandSyntaxError) {
+    sender.send_message(TextComponent::text(&error.message).color("red")).await;
+    if let Some(context) = error.context() {
+        sender.send_message(TextComponent::text(context).color("red")).await
+    }
+}
@@ -0,0 +1,110 @@
+use crate::command::arguments::ArgumentType;
+use crate::command::{Executor, Requirement, SuggestionProvider};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Root,
+    Literal(String),
+    Argument { name: String, argument: ArgumentType },
+}
+
+/// A node of the command tree, stored in the dispatcher and referenced by index.
+pub struct CommandNode {
+    pub kind: NodeKind,
+    pub children: Vec<usize>,
+    pub executor: Option<Executor>,
+    pub requirement: Option<Requirement>,
+    pub redirect: Option<usize>,
+    pub suggestions: Option<SuggestionProvider>,
+}
+
+impl CommandNode {
+    pub fn root() -> Self {
+        CommandNode {
+            kind: NodeKind::Root,
+            children: vec![],
+            executor: None,
+            requirement: None,
+            redirect: None,
+            suggestions: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            NodeKind::Root => "",
+            NodeKind::Literal(name) | NodeKind::Argument { name, .. } => name,
+        }
+    }
+
+    /// How the node is shown in usage, `name` for literals and `<name>` for arguments.
+    pub fn usage_text(&self) -> String {
+        match &self.kind {
+            NodeKind::Root => String::new(),
+            NodeKind::Literal(name) => name.clone(),
+            NodeKind::Argument { name, .. } => format!("<{name}>"),
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        matches!(self.kind, NodeKind::Literal(_))
+    }
+}
+
+/// Describes a command tree before it's registered, see [`literal`] and [`argument`].
+pub struct CommandBuilder {
+    pub(crate) kind: NodeKind,
+    pub(crate) children: Vec<CommandBuilder>,
+    pub(crate) executor: Option<Executor>,
+    pub(crate) requirement: Option<Requirement>,
+    pub(crate) redirect: Option<String>,
+    pub(crate) suggestions: Option<SuggestionProvider>,
+}
+
+pub fn literal(name: &str) -> CommandBuilder {
+    CommandBuilder::new(NodeKind::Literal(name.to_string()))
+}
+
+pub fn argument(name: &str, argument: ArgumentType) -> CommandBuilder {
+    CommandBuilder::new(NodeKind::Argument { name: name.to_string(), argument })
+}
+
+impl CommandBuilder {
+    fn new(kind: NodeKind) -> Self {
+        CommandBuilder {
+            kind,
+            children: vec![],
+            executor: None,
+            requirement: None,
+            redirect: None,
+            suggestions: None,
+        }
+    }
+
+    pub fn then(mut self, child: CommandBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn executes(mut self, executor: Executor) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    pub fn requires(mut self, requirement: impl Fn(&crate::command::CommandSender) -> bool + Send + Sync + 'static) -> Self {
+        self.requirement = Some(Box::new(requirement));
+        self
+    }
+
+    /// Continues parsing at the children of an already registered root command, used for aliases.
+    pub fn redirect(mut self, command: &str) -> Self {
+        self.redirect = Some(command.to_string());
+        self
+    }
+
+    /// Completes this argument on the server instead of the client.
+    pub fn suggests(mut self, provider: SuggestionProvider) -> Self {
+        self.suggestions = Some(provider);
+        self
+    }
+}
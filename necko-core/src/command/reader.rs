@@ -0,0 +1,251 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How many characters before the error position are shown in its context.
+const CONTEXT_AMOUNT: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct CommandSyntaxError {
+    pub message: String,
+    input: Option<(String, usize)>,
+}
+
+impl CommandSyntaxError {
+    pub fn new(message: impl Into<String>) -> Self {
+        CommandSyntaxError { message: message.into(), input: None }
+    }
+
+    pub fn at(message: impl Into<String>, reader: &StringReader) -> Self {
+        CommandSyntaxError {
+            message: message.into(),
+            input: Some((reader.input.to_string(), reader.cursor)),
+        }
+    }
+
+    /// The input around the error, e.g. `...amemode foo<--[HERE]`. Like vanilla, the input
+    /// after the error position is kept before the marker.
+    pub fn context(&self) -> Option<String> {
+        let (input, cursor) = self.input.as_ref()?;
+        let cursor = (*cursor).min(input.len());
+        let start = cursor.saturating_sub(CONTEXT_AMOUNT);
+        let start = (start..=cursor).find(|i| input.is_char_boundary(*i)).unwrap_or(cursor);
+        let prefix = if start > 0 { "..." } else { "" };
+        Some(format!("{prefix}{}{}<--[HERE]", &input[start..cursor], &input[cursor..]))
+    }
+}
+
+impl Display for CommandSyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.input {
+            Some((_, cursor)) => write!(f, "{} at position {}", self.message, cursor),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A cursor over command input, following Brigadier's `StringReader` rules.
+#[derive(Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    pub cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        StringReader { input, cursor: 0 }
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn consumed(&self) -> &'a str {
+        &self.input[..self.cursor]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn read(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.skip();
+        c
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.skip();
+        }
+    }
+
+    pub fn expect(&mut self, c: char) -> Result<(), CommandSyntaxError> {
+        if self.peek() != Some(c) {
+            return Err(CommandSyntaxError::at(format!("Expected '{c}'"), self))
+        }
+        self.skip();
+        Ok(())
+    }
+
+    /// Reads until the next space or the end of input.
+    pub fn read_word(&mut self) -> &'a str {
+        let start = self.cursor;
+        while self.peek().is_some_and(|c| c != ' ') {
+            self.skip();
+        }
+        &self.input[start..self.cursor]
+    }
+
+    pub fn read_unquoted_string(&mut self) -> &'a str {
+        let start = self.cursor;
+        while self.peek().is_some_and(is_allowed_in_unquoted_string) {
+            self.skip();
+        }
+        &self.input[start..self.cursor]
+    }
+
+    pub fn read_quoted_string(&mut self) -> Result<String, CommandSyntaxError> {
+        let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+            return Err(CommandSyntaxError::at("Expected quote to start a string", self))
+        };
+        self.skip();
+
+        let mut result = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.read() {
+            if escaped {
+                if c != quote && c != '\\' {
+                    self.cursor -= c.len_utf8();
+                    return Err(CommandSyntaxError::at(format!("Invalid escape sequence '{c}' in quoted string"), self))
+                }
+                result.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                return Ok(result)
+            } else {
+                result.push(c);
+            }
+        }
+        Err(CommandSyntaxError::at("Unclosed quoted string", self))
+    }
+
+    pub fn read_string(&mut self) -> Result<String, CommandSyntaxError> {
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted_string(),
+            _ => Ok(self.read_unquoted_string().to_string()),
+        }
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, CommandSyntaxError> {
+        let start = self.cursor;
+        match self.read_unquoted_string() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            "" => Err(CommandSyntaxError::at("Expected bool", self)),
+            value => {
+                self.cursor = start;
+                Err(CommandSyntaxError::at(format!("Invalid bool, expected true or false but found '{value}'"), self))
+            }
+        }
+    }
+
+    pub fn read_number<T: FromStr>(&mut self, kind: &str) -> Result<T, CommandSyntaxError> {
+        let start = self.cursor;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.' || c == '-') {
+            self.skip();
+        }
+        let number = &self.input[start..self.cursor];
+        if number.is_empty() {
+            return Err(CommandSyntaxError::at(format!("Expected {kind}"), self))
+        }
+        number.parse().map_err(|_| {
+            self.cursor = start;
+            CommandSyntaxError::at(format!("Invalid {kind} '{number}'"), self)
+        })
+    }
+}
+
+pub fn is_allowed_in_unquoted_string(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_and_whitespace() {
+        let mut reader = StringReader::new("give  é:x rest");
+        assert_eq!(reader.read_word(), "give");
+        reader.skip_whitespace();
+        assert_eq!(reader.read_unquoted_string(), "");
+        assert_eq!(reader.read(), Some('é'));
+        assert_eq!(reader.cursor, 8);
+        reader.expect(':').unwrap();
+        assert!(reader.expect(':').is_err());
+        assert_eq!(reader.read_word(), "x");
+        assert_eq!(reader.consumed(), "give  é:x");
+        assert_eq!(reader.remaining(), " rest");
+    }
+
+    #[test]
+    fn quoted_strings() {
+        let mut reader = StringReader::new(r#""a \"b\" \\c" 'it\'s' plain"#);
+        assert_eq!(reader.read_string().unwrap(), r#"a "b" \c"#);
+        reader.skip();
+        assert_eq!(reader.read_string().unwrap(), "it's");
+        reader.skip();
+        assert_eq!(reader.read_string().unwrap(), "plain");
+
+        let error = StringReader::new(r#""bad \n""#).read_quoted_string().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid escape sequence 'n' in quoted string at position 6");
+        assert!(StringReader::new(r#""open"#).read_quoted_string().is_err());
+        assert!(StringReader::new("plain").read_quoted_string().is_err());
+    }
+
+    #[test]
+    fn bools_and_numbers() {
+        assert!(StringReader::new("true").read_bool().unwrap());
+        assert!(!StringReader::new("false").read_bool().unwrap());
+
+        let mut reader = StringReader::new("yes");
+        let error = reader.read_bool().unwrap_err();
+        assert_eq!(error.message, "Invalid bool, expected true or false but found 'yes'");
+        assert_eq!(reader.cursor, 0);
+
+        let mut reader = StringReader::new("-12 1.5 1-2");
+        assert_eq!(reader.read_number::<i32>("integer").unwrap(), -12);
+        reader.skip();
+        assert_eq!(reader.read_number::<f64>("double").unwrap(), 1.5);
+        reader.skip();
+        let error = reader.read_number::<i32>("integer").unwrap_err();
+        assert_eq!(error.message, "Invalid integer '1-2'");
+        assert_eq!(reader.cursor, 8);
+        assert!(StringReader::new("x").read_number::<i32>("integer").is_err());
+    }
+
+    #[test]
+    fn error_context() {
+        let mut reader = StringReader::new("gamemode creative foo");
+        reader.cursor = 18;
+        let error = CommandSyntaxError::at("Incorrect argument", &reader);
+        assert_eq!(error.context().unwrap(), "... creative foo<--[HERE]");
+        assert!(CommandSyntaxError::new("Unknown command").context().is_none());
+    }
+}
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::client::forwarding::ForwardingConfig;
+use crate::client::proxy::ProxyProtocolConfig;
+use crate::client::timeout::TimeoutConfig;
+use crate::logging::LoggingConfig;
+use crate::metrics::MetricsConfig;
+use crate::permission::file::PermissionConfig;
+use crate::query::QueryConfig;
+use crate::rcon::RconConfig;
+use crate::server::access::AccessConfig;
+use crate::server::shutdown::ShutdownConfig;
+use crate::server::status::StatusExtensions;
+use crate::server::throttle::ThrottleConfig;
+use crate::world::{check_worlds, WorldConfig};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub address: String,
+    pub motd: String,
+    pub max_players: i32,
+    pub icon: String,
+    pub enforces_secure_chat: bool,
+    /// Chunks sent around players, in chunks. Clients may ask for less.
+    pub view_distance: i32,
+    /// Chunks around players that are ticked.
+    pub simulation_distance: i32,
+    pub previews_chat: bool,
+    pub status: StatusExtensions,
+    pub proxy_protocol: ProxyProtocolConfig,
+    pub forwarding: ForwardingConfig,
+    pub throttle: ThrottleConfig,
+    pub timeouts: TimeoutConfig,
+    pub shutdown: ShutdownConfig,
+    pub access: AccessConfig,
+    pub permissions: PermissionConfig,
+    pub rcon: RconConfig,
+    pub query: QueryConfig,
+    pub metrics: MetricsConfig,
+    pub logging: LoggingConfig,
+    /// Worlds hosted by the server. Players join the first one.
+    pub worlds: Vec<WorldConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "127.0.0.1:25565".into(),
+            motd: "hello from necko-core :3".into(),
+            max_players: -1,
+            icon: "icon.png".into(),
+            enforces_secure_chat: false,
+            view_distance: 10,
+            simulation_distance: 10,
+            previews_chat: false,
+            status: StatusExtensions::default(),
+            proxy_protocol: ProxyProtocolConfig::default(),
+            forwarding: ForwardingConfig::default(),
+            throttle: ThrottleConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            access: AccessConfig::default(),
+            permissions: PermissionConfig::default(),
+            rcon: RconConfig::default(),
+            query: QueryConfig::default(),
+            metrics: MetricsConfig::default(),
+            logging: LoggingConfig::default(),
+            worlds: vec![WorldConfig::default()],
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Loads the config from `path`, writing the defaults there first if the file is missing.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let config = ServerConfig::default();
+            fs::write(path, toml::to_string_pretty(&config)?)?;
+            return Ok(config)
+        }
+
+        let config: ServerConfig = toml::from_str(&fs::read_to_string(path)?)?;
+        config.status.check()?;
+        config.forwarding.check()?;
+        config.access.check()?;
+        check_worlds(&config.worlds)?;
+        Ok(config)
+    }
+}
@@ -0,0 +1,136 @@
+use std::io::{Error, Write};
+use std::sync::{Arc, Mutex};
+use rustyline::error::ReadlineError;
+use rustyline::{DefaultEditor, ExternalPrinter};
+use tokio::sync::mpsc;
+use crate::command::CommandSender;
+use crate::server::Server;
+
+pub const HISTORY_PATH: &str = ".console_history";
+const PROMPT: &str = "> ";
+
+type Printer = Box<dyn ExternalPrinter + Send>;
+
+/// Reads commands from stdin with line editing and history. Log lines are routed through
+/// [`ConsoleWriter`] so they're printed above the prompt instead of breaking it.
+pub struct Console {
+    editor: DefaultEditor,
+    printer: Option<Arc<Mutex<Printer>>>,
+    #[cfg(unix)]
+    terminal: Option<libc::termios>,
+}
+
+impl Console {
+    pub fn new() -> Result<Self, ReadlineError> {
+        let mut editor = DefaultEditor::new()?;
+        let _ = editor.load_history(HISTORY_PATH);
+        let printer = editor.create_external_printer().ok()
+            .map(|printer| Arc::new(Mutex::new(Box::new(printer) as Printer)));
+
+        Ok(Console {
+            editor,
+            printer,
+            #[cfg(unix)]
+            terminal: save_terminal(),
+        })
+    }
+
+    /// A writer printing above the prompt, or `None` if stdin/stdout aren't a terminal.
+    pub fn writer(&self) -> Option<ConsoleWriter> {
+        self.printer.clone().map(|printer| ConsoleWriter { printer, line: Vec::new() })
+    }
+
+    /// Starts reading commands on a dedicated thread and runs them on the server as the console.
+    /// Returns a guard restoring the terminal when the server exits mid-prompt.
+    pub fn start(self, server: Arc<Server>) -> TerminalGuard {
+        let Console { mut editor, #[cfg(unix)] terminal, .. } = self;
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        let stop_server = server.clone();
+        std::thread::Builder::new()
+            .name("console".into())
+            .spawn(move || loop {
+                match editor.readline(PROMPT) {
+                    Ok(line) => {
+                        let line = line.trim();
+                        if line.is_empty() { continue }
+                        let _ = editor.add_history_entry(line);
+                        let _ = editor.append_history(HISTORY_PATH);
+                        if sender.send(line.to_string()).is_err() { break }
+                    }
+                    Err(ReadlineError::Interrupted) => {
+                        stop_server.request_stop();
+                        break
+                    }
+                    Err(ReadlineError::Eof) => break,
+                    Err(e) => {
+                        tracing::error!("Failed to read console input: {}", e);
+                        break
+                    }
+                }
+            })
+            .expect("Could not spawn console thread");
+
+        tokio::spawn(async move {
+            while let Some(line) = receiver.recv().await {
+                let line = line.strip_prefix('/').unwrap_or(&line);
+                server.commands.execute(&server, &CommandSender::Console, line).await
+            }
+        });
+
+        TerminalGuard {
+            #[cfg(unix)]
+            terminal,
+        }
+    }
+}
+
+pub struct TerminalGuard {
+    #[cfg(unix)]
+    terminal: Option<libc::termios>,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(terminal) = &self.terminal {
+            // SAFETY: restores attributes previously read from the same file descriptor
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, terminal) };
+        }
+    }
+}
+
+#[cfg(unix)]
+fn save_terminal() -> Option<libc::termios> {
+    // SAFETY: termios is plain data and tcgetattr fully initializes it on success
+    unsafe {
+        let mut terminal = std::mem::zeroed::<libc::termios>();
+        (libc::tcgetattr(libc::STDIN_FILENO, &mut terminal) == 0).then_some(terminal)
+    }
+}
+
+pub struct ConsoleWriter {
+    printer: Arc<Mutex<Printer>>,
+    line: Vec<u8>,
+}
+
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.line.extend_from_slice(buf);
+        if self.line.ends_with(b"\n") {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.line.is_empty() {
+            return Ok(())
+        }
+        let message = String::from_utf8_lossy(&self.line).into_owned();
+        self.line.clear();
+        self.printer.lock().map_err(|_| Error::other("Console printer poisoned"))?
+            .print(message)
+            .map_err(Error::other)
+    }
+}
@@ -1,2 +1,12 @@
 pub mod client;
+pub mod command;
+pub mod config;
+pub mod console;
+pub mod logging;
+pub mod metrics;
+pub mod permission;
+pub mod query;
+pub mod rcon;
+pub mod registry;
 pub mod server;
+pub mod world;
\ No newline at end of file
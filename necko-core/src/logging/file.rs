@@ -0,0 +1,93 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Error, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, NaiveDate};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+pub const LATEST_LOG: &str = "latest.log";
+
+/// Writes to `latest.log` and, like vanilla, archives it as `<date>-<n>.log.gz` when the day
+/// changes or the server starts with a log from an earlier run.
+pub struct RollingFile {
+    directory: PathBuf,
+    file: BufWriter<File>,
+    date: NaiveDate,
+}
+
+impl RollingFile {
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self, Error> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        let latest = directory.join(LATEST_LOG);
+        if let Ok(metadata) = fs::metadata(&latest) {
+            let modified = metadata.modified().map(DateTime::<Local>::from).unwrap_or_else(|_| Local::now());
+            archive(&directory, modified.date_naive())?
+        }
+
+        Ok(RollingFile {
+            file: BufWriter::new(create(&latest)?),
+            date: Local::now().date_naive(),
+            directory,
+        })
+    }
+
+    fn rotate(&mut self, today: NaiveDate) -> Result<(), Error> {
+        self.file.flush()?;
+        archive(&self.directory, self.date)?;
+        self.file = BufWriter::new(create(&self.directory.join(LATEST_LOG))?);
+        self.date = today;
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let today = Local::now().date_naive();
+        if today != self.date {
+            self.rotate(today)?
+        }
+        let written = self.file.write(buf)?;
+        if buf.ends_with(b"\n") {
+            self.file.flush()?
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.file.flush()
+    }
+}
+
+fn create(path: &Path) -> Result<File, Error> {
+    OpenOptions::new().create(true).write(true).truncate(true).open(path)
+}
+
+/// Moves `latest.log` aside under the first free name for its date and compresses it in the
+/// background.
+fn archive(directory: &Path, date: NaiveDate) -> Result<(), Error> {
+    let name = (1..).map(|n| format!("{}-{n}.log", date.format("%Y-%m-%d")))
+        .find(|name| !directory.join(name).exists() && !directory.join(format!("{name}.gz")).exists())
+        .unwrap();
+    let path = directory.join(name);
+    fs::rename(directory.join(LATEST_LOG), &path)?;
+
+    std::thread::spawn(move || {
+        if let Err(e) = compress(&path) {
+            eprintln!("Could not compress log file {}: {}", path.display(), e)
+        }
+    });
+    Ok(())
+}
+
+fn compress(path: &Path) -> Result<(), Error> {
+    let mut archive = path.as_os_str().to_owned();
+    archive.push(".gz");
+
+    let mut input = BufReader::new(File::open(path)?);
+    let mut encoder = GzEncoder::new(File::create(archive)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
@@ -0,0 +1,169 @@
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use chrono::Local;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Record};
+use tracing::{Event, Id, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use crate::console::ConsoleWriter;
+use crate::logging::file::RollingFile;
+
+pub mod file;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Which logs are shown, like `info,necko_core::client=debug`. `RUST_LOG` overrides it.
+    pub filter: String,
+    /// Also writes logs to `latest.log` in this directory, archiving it every day.
+    pub file: bool,
+    pub directory: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            filter: "debug".into(),
+            file: true,
+            directory: "logs".into(),
+        }
+    }
+}
+
+/// Installs the global subscriber printing to the console, above the prompt if there is one,
+/// and to the log file.
+pub fn init(config: &LoggingConfig, console: Option<ConsoleWriter>) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.filter))
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid log filter {:?}: {}", config.filter, e);
+            EnvFilter::new("info")
+        });
+
+    let console_writer = match console {
+        Some(console) => BoxMakeWriter::new(Mutex::new(console)),
+        None => BoxMakeWriter::new(std::io::stdout),
+    };
+    let console_layer = tracing_subscriber::fmt::layer()
+        .event_format(LogFormat { colored: true })
+        .with_writer(console_writer);
+
+    let file_layer = config.file.then(|| match RollingFile::open(&config.directory) {
+        Ok(file) => Some(tracing_subscriber::fmt::layer()
+            .event_format(LogFormat { colored: false })
+            .with_writer(Mutex::new(file))),
+        Err(e) => {
+            eprintln!("Could not open log file in {}: {}", config.directory, e);
+            None
+        }
+    }).flatten();
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(SpanFieldsLayer)
+        .with(console_layer)
+        .with(file_layer)
+        .init();
+}
+
+/// Formats events like `[12:34:56 INFO] connection{address=127.0.0.1:54321}: message key=value`.
+pub struct LogFormat {
+    pub colored: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for LogFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, context: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> std::fmt::Result {
+        let level = *event.metadata().level();
+        let mut line = format!("[{} {}]", Local::now().format("%H:%M:%S"), level);
+
+        if let Some(scope) = context.event_scope() {
+            for span in scope.from_root() {
+                line.push(' ');
+                line.push_str(span.name());
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    fields.write(&mut line)
+                }
+            }
+        }
+        line.push_str(": ");
+        context.field_format().format_fields(Writer::new(&mut line), event)?;
+
+        if !self.colored {
+            return writeln!(writer, "{line}")
+        }
+        let line = match level {
+            Level::ERROR => line.bright_red(),
+            Level::WARN => line.yellow(),
+            Level::INFO => line.white(),
+            Level::DEBUG => line.white().dimmed(),
+            Level::TRACE => line.bright_black(),
+        };
+        writeln!(writer, "{line}")
+    }
+}
+
+/// The latest values of a span's fields. The formatter from `tracing_subscriber` appends
+/// recorded values instead, which would repeat fields like the connection state.
+#[derive(Default)]
+struct SpanFields(Vec<(&'static str, String)>);
+
+impl SpanFields {
+    fn write(&self, line: &mut String) {
+        line.push('{');
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            let _ = write!(line, "{name}={value}");
+        }
+        line.push('}');
+    }
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{value}"))
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{value:?}");
+        match self.0.iter_mut().find(|(name, _)| *name == field.name()) {
+            Some((_, old)) => *old = value,
+            None => self.0.push((field.name(), value)),
+        }
+    }
+}
+
+struct SpanFieldsLayer;
+
+impl<S> Layer<S> for SpanFieldsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, context: Context<'_, S>) {
+        let Some(span) = context.span(id) else { return };
+        let mut fields = SpanFields::default();
+        attributes.record(&mut fields);
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, context: Context<'_, S>) {
+        let Some(span) = context.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            values.record(fields)
+        }
+    }
+}
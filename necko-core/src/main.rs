@@ -1,72 +1,153 @@
-use std::io::Write;
 use std::sync::Arc;
 use std::time::Instant;
-use colored::Colorize;
-use log::{Level, LevelFilter};
+use bytes::BytesMut;
 use tokio::net::{TcpListener};
+use tokio::time::timeout_at;
+use tracing::Instrument;
 use necko_core::client::Client;
-use chrono::Local;
-use necko_core::server::Server;
+use necko_core::client::{chunks, keep_alive, writer};
+use necko_core::client::proxy::read_proxy_header;
+use necko_core::client::forwarding::ForwardingMode;
+use necko_core::config::{ServerConfig, CONFIG_PATH};
+use necko_core::console::Console;
+use necko_core::{logging, metrics, query, rcon};
+use necko_core::server::{access, ticks, Server};
 use necko_core::server::status::MINECRAFT_VERSION;
 
-const TCP_ADDRESS: &str = "127.0.0.1:25565";
-
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     let time = Instant::now();
-    init_logger(LevelFilter::Debug);
-    log::info!("Starting necko-server v{} on Minecraft {MINECRAFT_VERSION}", env!("CARGO_PKG_VERSION"));
+    let config = ServerConfig::load(CONFIG_PATH)
+        .expect("Could not load server config");
+
+    let console = Console::new().ok();
+    logging::init(&config.logging, console.as_ref().and_then(Console::writer));
+    tracing::info!("Starting necko-server v{} on Minecraft {MINECRAFT_VERSION}", env!("CARGO_PKG_VERSION"));
 
-    let listener = TcpListener::bind(TCP_ADDRESS).await
+    let listener = TcpListener::bind(&config.address).await
         .expect("Could not bind TCP listener");
-    log::info!("Bound TCP socket on {}", listener.local_addr().expect("Could not get local address"));
+    tracing::info!("Bound TCP socket on {}", listener.local_addr().expect("Could not get local address"));
     
-    let server = Arc::new(Server::new());
+    let server = Arc::new(Server::new(config));
+    let _terminal = console.map(|console| console.start(server.clone()));
+    tokio::spawn(access::watch(server.clone()));
+    tokio::spawn(rcon::listen(server.clone()));
+    tokio::spawn(query::listen(server.clone()));
+    tokio::spawn(metrics::listen(server.clone()));
+    tokio::spawn(ticks::run(server.clone()));
     
-    log::info!("Server started in {}s", time.elapsed().as_secs_f32());
+    tracing::info!("Server started in {}s", time.elapsed().as_secs_f32());
+    // Created once, so no signal is missed between two connections.
+    let shutdown = shutdown_signal(&server);
+    tokio::pin!(shutdown);
     loop {
-        let (stream, addr) = listener.accept().await?;
-        log::debug!("Accepted connection from {}", addr);
+        let (mut stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = &mut shutdown => break,
+        };
+        tracing::debug!("Accepted connection from {}", addr);
 
-        stream.set_nodelay(true).unwrap_or_else(
-            |e| log::warn!("Failed to set TCP_NODELAY on socket: {}", e));
+        let proxy_protocol = &server.config.proxy_protocol;
+        if proxy_protocol.enabled && !proxy_protocol.is_trusted(addr.ip()) {
+            tracing::warn!("Rejected connection from untrusted PROXY source {}", addr);
+            continue
+        }
 
-        let client = Arc::new(Client::new(stream, addr));
+        let Some(permit) = server.throttle.acquire_connection() else {
+            tracing::warn!("Rejected connection from {}: too many open connections", addr);
+            continue
+        };
+
+        stream.set_nodelay(true).unwrap_or_else(
+            |e| tracing::warn!("Failed to set TCP_NODELAY on socket: {}", e));
 
         let server = server.clone();
         tokio::spawn(async move {
-            log::debug!("Starting polling for {:?}", client.address.read().await);
-            while !client.closed.load(std::sync::atomic::Ordering::Relaxed) {
-                let done = client.poll().await;
-                if done {
-                    client.process_packets(&server).await;
+            let _permit = permit;
+            let handshake_deadline = Instant::now() + server.config.timeouts.handshake();
+
+            let mut addr = addr;
+            let mut first_bytes = BytesMut::new();
+            if server.config.proxy_protocol.enabled {
+                match timeout_at(handshake_deadline.into(), read_proxy_header(&mut stream)).await {
+                    Ok(Ok((source, rest))) => {
+                        if let Some(real) = source {
+                            tracing::debug!("PROXY header from {} forwarded {}", addr, real);
+                            addr = real
+                        }
+                        first_bytes = rest
+                    }
+                    Ok(Err(e)) => {
+                        tracing::warn!("Invalid PROXY header from {}: {}", addr, e);
+                        return
+                    }
+                    Err(_) => {
+                        tracing::debug!("Timed out waiting for PROXY header from {}", addr);
+                        return
+                    }
                 }
             }
+
+            // Behind a proxy without PROXY headers every connection comes from the proxy itself,
+            // only the login throttle sees the forwarded addresses.
+            let behind_proxy = server.config.forwarding.mode != ForwardingMode::None
+                && !server.config.proxy_protocol.enabled;
+            if !behind_proxy && !server.throttle.allow_connection(addr.ip()) {
+                tracing::warn!("Rejected connection from {}: connecting too fast", addr);
+                return
+            }
+
+            if server.is_stopping() {
+                return
+            }
+
+            let client = Arc::new(Client::new(stream, addr));
+            client.decoder.lock().await.append_bytes(first_bytes);
+            let span = client.span.clone();
+            async move {
+                let _guard = server.add_client(client.clone()).await;
+                tracing::debug!("Starting polling");
+                let writing = tokio::spawn(writer::run(client.clone()).instrument(client.span.clone()));
+                tokio::spawn(keep_alive::run(client.clone(), server.config.timeouts.clone())
+                    .instrument(client.span.clone()));
+                tokio::spawn(chunks::run(client.clone(), server.clone())
+                    .instrument(client.span.clone()));
+                while !client.closed.load(std::sync::atomic::Ordering::Relaxed) {
+                    let done = client.poll(&server).await;
+                    if done {
+                        client.process_packets(&server).await;
+                    }
+                }
+                // The client stays connected until what was queued for it is written.
+                let _ = writing.await;
+            }.instrument(span).await
         });
     }
-}
 
-fn init_logger(level: LevelFilter) {
-    env_logger::Builder::from_default_env()
-        .format(|buf, record| {
-            let message = format!(
-                "[{} {}]: {}",
-                Local::now().format("%H:%M:%S"),
-                record.level(),
-                record.args()
-            );
+    tracing::info!("Stopping the server");
+    drop(listener);
+    server.shutdown().await;
+    tracing::info!("Server stopped");
+    Ok(())
+}
 
-            let colored_message = match record.level() {
-                Level::Error => message.bright_red(),
-                Level::Warn => message.yellow(),
-                Level::Info => message.white(),
-                Level::Debug => message.white().dimmed(),
-                Level::Trace => message.bright_black(),
-            };
+async fn shutdown_signal(server: &Server) {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => { signal.recv().await; }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-            writeln!(buf, "{}", colored_message)
-        })
-        .filter_level(level)
-        .init();
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => (),
+        _ = terminate => (),
+        _ = server.stop_requested() => (),
+    }
 }
-
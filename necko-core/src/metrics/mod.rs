@@ -0,0 +1,163 @@
+use std::fmt::Write;
+use std::io::Error;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use necko_protocol::metrics::{state_name, ALL_STATES, METRICS};
+use necko_protocol::packets::serverbound::intention::NextState;
+use necko_protocol::types::VarIntType;
+use crate::server::{Server, ACCEPT_ERROR_DELAY};
+
+pub const METRICS_PATH: &str = "/metrics";
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// Requests with a longer head are rejected.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// HTTP address serving the metrics in the Prometheus text format.
+    pub address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: false,
+            address: "127.0.0.1:9225".into(),
+        }
+    }
+}
+
+/// Serves metrics over HTTP if they're enabled in the config.
+pub async fn listen(server: Arc<Server>) {
+    let config = &server.config.metrics;
+    if !config.enabled {
+        return
+    }
+
+    let listener = match TcpListener::bind(&config.address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Could not bind metrics listener on {}: {}", config.address, e);
+            return
+        }
+    };
+    tracing::info!("Serving metrics on http://{}{METRICS_PATH}", config.address);
+
+    while !server.is_stopping() {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("Failed to accept metrics connection: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue
+            }
+        };
+
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_request(&server, stream).await {
+                tracing::debug!("Metrics request from {} failed: {}", addr, e)
+            }
+        });
+    }
+}
+
+async fn handle_request(server: &Server, mut stream: TcpStream) -> Result<(), Error> {
+    let mut request = Vec::new();
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        if request.len() >= MAX_REQUEST_SIZE {
+            return respond(&mut stream, "431 Request Header Fields Too Large", "").await
+        }
+        let mut chunk = [0; 1024];
+        match stream.read(&mut chunk).await? {
+            0 => return Ok(()),
+            read => request.extend_from_slice(&chunk[..read]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.lines().next().unwrap_or_default().split(' ');
+    match (line.next(), line.next()) {
+        (Some("GET"), Some(METRICS_PATH)) => respond(&mut stream, "200 OK", &render(server).await).await,
+        (Some("GET"), _) => respond(&mut stream, "404 Not Found", "").await,
+        _ => respond(&mut stream, "405 Method Not Allowed", "").await,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), Error> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Renders every metric in the Prometheus text format.
+pub async fn render(server: &Server) -> String {
+    let mut out = String::new();
+
+    let clients: Vec<_> = server.clients.read().await.values().map(|client| client.state.load()).collect();
+    header(&mut out, "necko_connections", "gauge", "Open connections by protocol state.");
+    for state in ALL_STATES {
+        let count = clients.iter().filter(|&&client| client == state).count();
+        let _ = writeln!(out, "necko_connections{{state=\"{}\"}} {count}", state_name(state));
+    }
+
+    let players = server.online_players().await.len();
+    header(&mut out, "necko_players", "gauge", "Players in the game.");
+    let _ = writeln!(out, "necko_players {players}");
+    // players are the only entities so far
+    header(&mut out, "necko_entities", "gauge", "Loaded entities.");
+    let _ = writeln!(out, "necko_entities {players}");
+    header(&mut out, "necko_loaded_chunks", "gauge", "Chunks kept in memory by world.");
+    for world in &server.worlds {
+        let _ = writeln!(out, "necko_loaded_chunks{{world=\"{}\"}} {}", world.name, world.loaded_chunks());
+    }
+
+    header(&mut out, "necko_packets_received_total", "counter", "Packets received by state and packet ID.");
+    packets(&mut out, "necko_packets_received_total", METRICS.packets_in());
+    header(&mut out, "necko_packets_sent_total", "counter", "Packets sent by state and packet ID.");
+    packets(&mut out, "necko_packets_sent_total", METRICS.packets_out());
+
+    header(&mut out, "necko_bytes_received_total", "counter", "Bytes of packets received.");
+    let _ = writeln!(out, "necko_bytes_received_total {}", METRICS.bytes_in.load(Ordering::Relaxed));
+    header(&mut out, "necko_bytes_sent_total", "counter", "Bytes of packets sent.");
+    let _ = writeln!(out, "necko_bytes_sent_total {}", METRICS.bytes_out.load(Ordering::Relaxed));
+    header(&mut out, "necko_decode_errors_total", "counter", "Connections closed because a packet couldn't be decoded.");
+    let _ = writeln!(out, "necko_decode_errors_total {}", METRICS.decode_errors.load(Ordering::Relaxed));
+
+    let histogram = server.ticks.histogram();
+    header(&mut out, "necko_tick_duration_seconds", "histogram", "Time spent running ticks.");
+    for (bound, count) in &histogram.buckets {
+        let _ = writeln!(out, "necko_tick_duration_seconds_bucket{{le=\"{bound}\"}} {count}");
+    }
+    let _ = writeln!(out, "necko_tick_duration_seconds_bucket{{le=\"+Inf\"}} {}", histogram.count);
+    let _ = writeln!(out, "necko_tick_duration_seconds_sum {}", histogram.sum);
+    let _ = writeln!(out, "necko_tick_duration_seconds_count {}", histogram.count);
+
+    header(&mut out, "necko_tps", "gauge", "Ticks per second over the latest ticks.");
+    let _ = writeln!(out, "necko_tps {}", server.ticks.tps());
+    header(&mut out, "necko_mspt", "gauge", "Average milliseconds per tick over the latest ticks.");
+    let _ = writeln!(out, "necko_mspt {}", server.ticks.mspt());
+    header(&mut out, "necko_ticks_skipped_total", "counter", "Ticks skipped because the server fell too far behind.");
+    let _ = writeln!(out, "necko_ticks_skipped_total {}", server.ticks.skipped());
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn packets(out: &mut String, name: &str, counts: Vec<(NextState, VarIntType, u64)>) {
+    for (state, id, count) in counts {
+        let _ = writeln!(out, "{name}{{state=\"{}\",id=\"0x{id:02X}\"}} {count}", state_name(state));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::permission::{PermissionBackend, PermissionSet};
+
+/// Group every player is in.
+pub const DEFAULT_GROUP: &str = "default";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PermissionConfig {
+    pub path: String,
+}
+
+impl Default for PermissionConfig {
+    fn default() -> Self {
+        PermissionConfig {
+            path: "permissions.toml".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GroupData {
+    /// Groups whose permissions this one starts from, later ones overriding earlier ones.
+    pub inherits: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PlayerData {
+    /// Only a reminder for whoever edits the file, players are looked up by UUID.
+    pub name: Option<String>,
+    pub groups: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PermissionData {
+    pub groups: HashMap<String, GroupData>,
+    pub players: HashMap<Uuid, PlayerData>,
+}
+
+impl Default for PermissionData {
+    fn default() -> Self {
+        PermissionData {
+            groups: HashMap::from([(DEFAULT_GROUP.to_string(), GroupData::default())]),
+            players: HashMap::new(),
+        }
+    }
+}
+
+impl PermissionData {
+    fn group(&self, name: &str, visited: &mut HashSet<String>) -> PermissionSet {
+        let mut set = PermissionSet::default();
+        if !visited.insert(name.to_string()) {
+            tracing::warn!("Permission group '{}' inherits from itself", name);
+            return set
+        }
+        let Some(group) = self.groups.get(name) else {
+            tracing::warn!("Unknown permission group '{}'", name);
+            return set
+        };
+
+        for parent in &group.inherits {
+            set.extend(&self.group(parent, visited));
+        }
+        set.extend(&PermissionSet::from_rules(&group.permissions));
+        visited.remove(name);
+        set
+    }
+
+    pub fn resolve(&self, uuid: &Uuid) -> PermissionSet {
+        let player = self.players.get(uuid);
+        let mut set = match self.groups.contains_key(DEFAULT_GROUP) {
+            true => self.group(DEFAULT_GROUP, &mut HashSet::new()),
+            false => PermissionSet::default(),
+        };
+        for group in player.iter().flat_map(|player| &player.groups) {
+            set.extend(&self.group(group, &mut HashSet::new()));
+        }
+        if let Some(player) = player {
+            set.extend(&PermissionSet::from_rules(&player.permissions));
+        }
+        set
+    }
+}
+
+/// Groups and players stored in a TOML file:
+///
+/// ```toml
+/// [groups.default]
+/// permissions = ["minecraft.command.list"]
+///
+/// [groups.moderator]
+/// inherits = ["default"]
+/// permissions = ["minecraft.command.kick", "necko.*", "-necko.fly"]
+///
+/// [players.069a79f4-44e9-4726-a5be-fca90e38aaf5]
+/// name = "Notch"
+/// groups = ["moderator"]
+/// permissions = ["necko.fly"]
+/// ```
+pub struct FilePermissions {
+    path: PathBuf,
+    data: RwLock<PermissionData>,
+}
+
+impl FilePermissions {
+    /// Loads the file at `path`, writing an empty one there first if it's missing.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            fs::write(path, toml::to_string_pretty(&PermissionData::default())?)?;
+        }
+        Ok(FilePermissions {
+            path: path.to_path_buf(),
+            data: RwLock::new(toml::from_str(&fs::read_to_string(path)?)?),
+        })
+    }
+}
+
+impl PermissionBackend for FilePermissions {
+    fn permissions(&self, uuid: &Uuid, _name: &str) -> PermissionSet {
+        self.data.read().unwrap().resolve(uuid)
+    }
+
+    fn reload(&self) -> Result<(), Box<dyn Error>> {
+        let data = toml::from_str(&fs::read_to_string(&self.path)?)?;
+        *self.data.write().unwrap() = data;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_keeps_old_data_on_error() {
+        let path = std::env::temp_dir().join(format!("necko-permissions-{}.toml", std::process::id()));
+        let uuid = Uuid::from_u128(42);
+        let permissions = FilePermissions::load(&path).unwrap();
+        assert_eq!(permissions.permissions(&uuid, "Notch").check("necko.fly"), None);
+
+        fs::write(&path, format!("[players.{uuid}]\npermissions = [\"necko.fly\"]\n")).unwrap();
+        permissions.reload().unwrap();
+        assert_eq!(permissions.permissions(&uuid, "Notch").check("necko.fly"), Some(true));
+
+        fs::write(&path, "[players.").unwrap();
+        assert!(permissions.reload().is_err());
+        assert_eq!(permissions.permissions(&uuid, "Notch").check("necko.fly"), Some(true));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::error::Error;
+use uuid::Uuid;
+
+pub mod file;
+
+/// Matches every node.
+pub const WILDCARD: &str = "*";
+
+/// Permission rules of a player or group. Nodes are dot separated like `necko.fly`; a rule
+/// ending in `*` matches every node below it and a leading `-` denies instead of grants.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PermissionSet {
+    rules: HashMap<String, bool>,
+}
+
+impl PermissionSet {
+    /// Parses rules like `necko.fly`, `necko.command.*` or `-necko.fly`.
+    pub fn from_rules<S: AsRef<str>>(rules: &[S]) -> Self {
+        let mut set = PermissionSet::default();
+        for rule in rules {
+            let rule = rule.as_ref().trim();
+            match rule.strip_prefix('-') {
+                Some(node) => set.set(node, false),
+                None => set.set(rule, true),
+            }
+        }
+        set
+    }
+
+    pub fn set(&mut self, node: &str, value: bool) {
+        self.rules.insert(node.to_lowercase(), value);
+    }
+
+    pub fn unset(&mut self, node: &str) {
+        self.rules.remove(&node.to_lowercase());
+    }
+
+    /// Applies the rules of `other` on top of these.
+    pub fn extend(&mut self, other: &PermissionSet) {
+        self.rules.extend(other.rules.iter().map(|(node, value)| (node.clone(), *value)));
+    }
+
+    /// Whether the node is granted, using the most specific matching rule. `None` if no rule
+    /// matches.
+    pub fn check(&self, node: &str) -> Option<bool> {
+        let node = node.to_lowercase();
+        if let Some(value) = self.rules.get(&node) {
+            return Some(*value)
+        }
+
+        let mut parent = node.as_str();
+        while let Some(i) = parent.rfind('.') {
+            parent = &parent[..i];
+            if let Some(value) = self.rules.get(&format!("{parent}.{WILDCARD}")) {
+                return Some(*value)
+            }
+        }
+        self.rules.get(WILDCARD).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Where permissions come from. The server uses [`file::FilePermissions`] unless another
+/// backend is set with [`crate::server::Server::with_permissions`].
+pub trait PermissionBackend: Send + Sync {
+    /// The effective permissions of a player, with its groups and own overrides resolved.
+    fn permissions(&self, uuid: &Uuid, name: &str) -> PermissionSet;
+
+    /// Reads the permissions again, if the backend caches them. Run by `/permissions reload`.
+    fn reload(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_rules_win() {
+        let set = PermissionSet::from_rules(&["necko.command.*", "-necko.command.stop", " Necko.Fly "]);
+        assert_eq!(set.check("necko.command.stop"), Some(false));
+        assert_eq!(set.check("necko.command.give"), Some(true));
+        assert_eq!(set.check("NECKO.FLY"), Some(true));
+        assert_eq!(set.check("necko.build"), None);
+    }
+
+    #[test]
+    fn most_specific_wildcard_wins() {
+        let set = PermissionSet::from_rules(&["*", "-necko.*", "necko.command.*"]);
+        assert_eq!(set.check("necko.command.kick.other"), Some(true));
+        assert_eq!(set.check("necko.fly"), Some(false));
+        assert_eq!(set.check("minecraft.command.give"), Some(true));
+        assert_eq!(set.check("necko"), Some(true), "a wildcard only matches nodes below it");
+    }
+
+    #[test]
+    fn extend_and_unset() {
+        let mut set = PermissionSet::from_rules(&["necko.fly", "necko.build"]);
+        set.extend(&PermissionSet::from_rules(&["-necko.fly"]));
+        assert_eq!(set.check("necko.fly"), Some(false));
+        set.unset("NECKO.FLY");
+        assert_eq!(set.check("necko.fly"), None);
+        assert!(!set.is_empty());
+        set.unset("necko.build");
+        assert!(set.is_empty());
+    }
+}
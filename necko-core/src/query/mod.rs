@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+use crate::server::Server;
+
+pub const MAGIC: [u8; 2] = [0xFE, 0xFD];
+pub const TYPE_HANDSHAKE: u8 = 9;
+pub const TYPE_STAT: u8 = 0;
+
+const GAME_TYPE: &str = "SMP";
+const GAME_ID: &str = "MINECRAFT";
+/// Challenge tokens stop being accepted after this long, like vanilla.
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+/// Sent before the key-value section of a full stat response.
+const FULL_STAT_PADDING: &[u8] = b"splitnum\0\x80\0";
+/// Sent before the player list of a full stat response.
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct QueryConfig {
+    pub enabled: bool,
+    /// UDP address to listen on, usually the same port as the game.
+    pub address: String,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        QueryConfig {
+            enabled: false,
+            address: "0.0.0.0:25565".into(),
+        }
+    }
+}
+
+struct Challenge {
+    token: i32,
+    created: Instant,
+}
+
+/// Answers GameSpy4 Query requests if it's enabled in the config.
+pub async fn listen(server: Arc<Server>) {
+    let config = &server.config.query;
+    if !config.enabled {
+        return
+    }
+
+    let socket = match UdpSocket::bind(&config.address).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::error!("Could not bind Query socket on {}: {}", config.address, e);
+            return
+        }
+    };
+    tracing::info!("Query running on {}", config.address);
+
+    let random = RandomState::new();
+    let mut challenges: HashMap<SocketAddr, Challenge> = HashMap::new();
+    let mut buffer = [0; 1460];
+    while !server.is_stopping() {
+        let (length, addr) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(e) => {
+                tracing::debug!("Failed to receive Query packet: {}", e);
+                continue
+            }
+        };
+
+        let request = &buffer[..length];
+        if length < 7 || request[0..2] != MAGIC {
+            continue
+        }
+        let kind = request[2];
+        let session = i32::from_be_bytes(request[3..7].try_into().unwrap());
+        let payload = &request[7..];
+
+        let response = match kind {
+            TYPE_HANDSHAKE => {
+                challenges.retain(|_, challenge| challenge.created.elapsed() < CHALLENGE_LIFETIME);
+                let token = (random.hash_one((addr, Instant::now())) as i32) & 0x0F0F0F0F;
+                challenges.insert(addr, Challenge { token, created: Instant::now() });
+                let mut response = header(TYPE_HANDSHAKE, session);
+                push_string(&mut response, &token.to_string());
+                response
+            }
+            TYPE_STAT if payload.len() >= 4 => {
+                let token = i32::from_be_bytes(payload[0..4].try_into().unwrap());
+                let valid = challenges.get(&addr).is_some_and(
+                    |challenge| challenge.token == token && challenge.created.elapsed() < CHALLENGE_LIFETIME);
+                if !valid {
+                    tracing::debug!("Invalid Query challenge from {}", addr);
+                    continue
+                }
+                match payload.len() {
+                    4 => basic_stat(&server, session).await,
+                    _ => full_stat(&server, session).await,
+                }
+            }
+            _ => continue,
+        };
+
+        if let Err(e) = socket.send_to(&response, addr).await {
+            tracing::debug!("Failed to send Query response to {}: {}", addr, e)
+        }
+    }
+}
+
+async fn basic_stat(server: &Server, session: i32) -> Vec<u8> {
+    let info = ServerInfo::collect(server).await;
+    let mut response = header(TYPE_STAT, session);
+    push_string(&mut response, &info.motd);
+    push_string(&mut response, GAME_TYPE);
+    push_string(&mut response, &info.map);
+    push_string(&mut response, &info.players.len().to_string());
+    push_string(&mut response, &info.max_players.to_string());
+    response.extend_from_slice(&info.host_port.to_le_bytes());
+    push_string(&mut response, &info.host_ip);
+    response
+}
+
+async fn full_stat(server: &Server, session: i32) -> Vec<u8> {
+    let info = ServerInfo::collect(server).await;
+    let mut response = header(TYPE_STAT, session);
+    response.extend_from_slice(FULL_STAT_PADDING);
+    for (key, value) in [
+        ("hostname", info.motd),
+        ("gametype", GAME_TYPE.into()),
+        ("game_id", GAME_ID.into()),
+        ("version", info.version),
+        ("plugins", info.plugins),
+        ("map", info.map),
+        ("numplayers", info.players.len().to_string()),
+        ("maxplayers", info.max_players.to_string()),
+        ("hostport", info.host_port.to_string()),
+        ("hostip", info.host_ip),
+    ] {
+        push_string(&mut response, key);
+        push_string(&mut response, &value);
+    }
+    response.push(0);
+
+    response.extend_from_slice(PLAYERS_PADDING);
+    for player in &info.players {
+        push_string(&mut response, player);
+    }
+    response.push(0);
+    response
+}
+
+/// What Query reports about the server, taken from the status shown in the server list.
+struct ServerInfo {
+    motd: String,
+    version: String,
+    plugins: String,
+    map: String,
+    players: Vec<String>,
+    max_players: i32,
+    host_ip: String,
+    host_port: u16,
+}
+
+impl ServerInfo {
+    async fn collect(server: &Server) -> Self {
+        let (motd, version, max_players) = {
+            let cached = server.cached_status.read().await;
+            (cached.status.description.clone(), cached.status.version.name.clone(), cached.status.players.max)
+        };
+        let address = server.config.address.parse::<SocketAddr>().ok();
+
+        ServerInfo {
+            motd,
+            version,
+            plugins: format!("necko-server {}", env!("CARGO_PKG_VERSION")),
+            map: server.default_world().name.clone(),
+            players: server.player_names().await,
+            max_players,
+            host_ip: address.map_or_else(|| "0.0.0.0".into(), |address| address.ip().to_string()),
+            host_port: address.map_or(25565, |address| address.port()),
+        }
+    }
+}
+
+fn header(kind: u8, session: i32) -> Vec<u8> {
+    let mut response = vec![kind];
+    response.extend_from_slice(&session.to_be_bytes());
+    response
+}
+
+fn push_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.push(0)
+}
@@ -0,0 +1,293 @@
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use crate::command::CommandSender;
+use crate::server::{Server, ACCEPT_ERROR_DELAY};
+
+pub const SERVERDATA_AUTH: i32 = 3;
+pub const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+pub const SERVERDATA_EXECCOMMAND: i32 = 2;
+pub const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// Largest packet accepted from clients, as in vanilla.
+const MAX_INCOMING_SIZE: i32 = 1460;
+/// Longer responses are split across several packets.
+const MAX_RESPONSE_BODY: usize = 4096;
+/// Request ID sent back when authentication fails.
+const AUTH_FAILED: i32 = -1;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RconConfig {
+    pub enabled: bool,
+    pub address: String,
+    /// RCON stays off while this is empty, even when enabled.
+    pub password: String,
+    /// Connections open at once, more are closed right away. `0` to disable.
+    pub max_connections: usize,
+    /// Connections that send nothing for this long are closed, `0` to disable.
+    pub idle_timeout_ms: u64,
+}
+
+impl Default for RconConfig {
+    fn default() -> Self {
+        RconConfig {
+            enabled: false,
+            address: "0.0.0.0:25575".into(),
+            password: String::new(),
+            max_connections: 8,
+            idle_timeout_ms: 60_000,
+        }
+    }
+}
+
+pub struct RconPacket {
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub async fn read<R: AsyncRead + Unpin>(stream: &mut R) -> Result<Self, Error> {
+        let length = stream.read_i32_le().await?;
+        if !(10..=MAX_INCOMING_SIZE).contains(&length) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid RCON packet length {length}")))
+        }
+
+        let mut data = vec![0; length as usize];
+        stream.read_exact(&mut data).await?;
+        let id = i32::from_le_bytes(data[0..4].try_into().unwrap());
+        let kind = i32::from_le_bytes(data[4..8].try_into().unwrap());
+        // the body is null terminated and followed by an empty string
+        let body = &data[8..data.len() - 2];
+        let body = body.split(|&b| b == 0).next().unwrap_or_default();
+
+        Ok(RconPacket { id, kind, body: String::from_utf8_lossy(body).into_owned() })
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin>(&self, stream: &mut W) -> Result<(), Error> {
+        let body = self.body.as_bytes();
+        let mut data = Vec::with_capacity(body.len() + 14);
+        data.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+        data.extend_from_slice(&self.id.to_le_bytes());
+        data.extend_from_slice(&self.kind.to_le_bytes());
+        data.extend_from_slice(body);
+        data.extend_from_slice(&[0, 0]);
+        stream.write_all(&data).await
+    }
+}
+
+/// Listens for RCON connections if it's enabled in the config.
+pub async fn listen(server: Arc<Server>) {
+    let config = &server.config.rcon;
+    if !config.enabled {
+        return
+    }
+    if config.password.is_empty() {
+        tracing::warn!("No RCON password set in the config, RCON will not be started");
+        return
+    }
+
+    let listener = match TcpListener::bind(&config.address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Could not bind RCON listener on {}: {}", config.address, e);
+            return
+        }
+    };
+    tracing::info!("RCON running on {}", config.address);
+
+    let connections = Arc::new(Semaphore::new(match config.max_connections {
+        0 => Semaphore::MAX_PERMITS,
+        max => max,
+    }));
+    while !server.is_stopping() {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("Failed to accept RCON connection: {}", e);
+                tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue
+            }
+        };
+        tracing::debug!("Accepted RCON connection from {}", addr);
+        let Ok(permit) = connections.clone().try_acquire_owned() else {
+            tracing::warn!("Rejected RCON connection from {}: too many open connections", addr);
+            continue
+        };
+
+        let server = server.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Err(e) = handle_connection(&server, stream, addr).await {
+                if e.kind() != ErrorKind::UnexpectedEof {
+                    tracing::debug!("RCON connection from {} failed: {}", addr, e)
+                }
+            }
+            tracing::debug!("RCON connection from {} closed", addr);
+        });
+    }
+}
+
+async fn handle_connection(server: &Server, mut stream: TcpStream, addr: SocketAddr) -> Result<(), Error> {
+    let idle_timeout = match server.config.rcon.idle_timeout_ms {
+        0 => Duration::MAX,
+        ms => Duration::from_millis(ms),
+    };
+    let mut authenticated = false;
+    loop {
+        let packet = tokio::time::timeout(idle_timeout, RconPacket::read(&mut stream)).await
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "Idle for too long"))??;
+        match packet.kind {
+            SERVERDATA_AUTH => {
+                authenticated = constant_time_eq(packet.body.as_bytes(), server.config.rcon.password.as_bytes());
+                let id = if authenticated { packet.id } else { AUTH_FAILED };
+                RconPacket { id, kind: SERVERDATA_AUTH_RESPONSE, body: String::new() }
+                    .write(&mut stream).await?;
+                if !authenticated {
+                    tracing::warn!("Failed RCON login from {}", addr);
+                    return Ok(())
+                }
+            }
+            SERVERDATA_EXECCOMMAND if authenticated => {
+                let output = execute(server, &packet.body).await;
+                respond(&mut stream, packet.id, &output).await?
+            }
+            // clients send an empty response packet after a command and wait for it to come
+            // back to know where a multi-packet response ends
+            SERVERDATA_RESPONSE_VALUE if authenticated => {
+                respond(&mut stream, packet.id, "").await?
+            }
+            _ => {
+                RconPacket { id: AUTH_FAILED, kind: SERVERDATA_AUTH_RESPONSE, body: String::new() }
+                    .write(&mut stream).await?
+            }
+        }
+    }
+}
+
+/// Runs a command and returns everything it sent back to the sender.
+async fn execute(server: &Server, command: &str) -> String {
+    let sender = CommandSender::Rcon(Mutex::new(String::new()));
+    let command = command.strip_prefix('/').unwrap_or(command);
+    server.commands.execute(server, &sender, command).await;
+    let CommandSender::Rcon(output) = sender else { unreachable!() };
+    output.into_inner().unwrap()
+}
+
+async fn respond<W: AsyncWrite + Unpin>(stream: &mut W, id: i32, output: &str) -> Result<(), Error> {
+    if output.is_empty() {
+        return RconPacket { id, kind: SERVERDATA_RESPONSE_VALUE, body: String::new() }.write(stream).await
+    }
+
+    let mut rest = output;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_RESPONSE_BODY);
+        while !rest.is_char_boundary(end) {
+            end -= 1
+        }
+        let (body, next) = rest.split_at(end);
+        RconPacket { id, kind: SERVERDATA_RESPONSE_VALUE, body: body.into() }.write(stream).await?;
+        rest = next
+    }
+    Ok(())
+}
+
+/// Compares without returning early, so the time taken doesn't tell how much of a guess matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut difference = a.len() ^ b.len();
+    for (i, x) in a.iter().enumerate() {
+        difference |= (x ^ b.get(i).copied().unwrap_or(!x)) as usize;
+    }
+    difference == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(id: i32, kind: i32, body: &[u8]) -> Vec<u8> {
+        let mut data = (body.len() as i32 + 10).to_le_bytes().to_vec();
+        data.extend(id.to_le_bytes());
+        data.extend(kind.to_le_bytes());
+        data.extend(body);
+        data.extend([0, 0]);
+        data
+    }
+
+    #[tokio::test]
+    async fn packet_round_trip() {
+        let mut data = vec![];
+        RconPacket { id: 7, kind: SERVERDATA_EXECCOMMAND, body: "list".into() }.write(&mut data).await.unwrap();
+        assert_eq!(data, frame(7, SERVERDATA_EXECCOMMAND, b"list"));
+
+        let packet = RconPacket::read(&mut data.as_slice()).await.unwrap();
+        assert_eq!((packet.id, packet.kind, packet.body.as_str()), (7, SERVERDATA_EXECCOMMAND, "list"));
+    }
+
+    #[tokio::test]
+    async fn body_ends_at_the_first_null() {
+        let data = frame(1, SERVERDATA_AUTH, b"secret\0junk");
+        assert_eq!(RconPacket::read(&mut data.as_slice()).await.unwrap().body, "secret");
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_lengths() {
+        for length in [9, MAX_INCOMING_SIZE + 1, -1] {
+            let data = length.to_le_bytes();
+            let error = RconPacket::read(&mut data.as_slice()).await.err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+        let mut truncated = frame(1, SERVERDATA_AUTH, b"secret");
+        truncated.pop();
+        let error = RconPacket::read(&mut truncated.as_slice()).await.err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn long_responses_are_split() {
+        let output = format!("{}é", "a".repeat(MAX_RESPONSE_BODY - 1));
+        let mut data = vec![];
+        respond(&mut data, 3, &output).await.unwrap();
+
+        let mut reader = data.as_slice();
+        let first = RconPacket::read_unchecked(&mut reader).await;
+        let second = RconPacket::read_unchecked(&mut reader).await;
+        assert!(reader.is_empty());
+        assert_eq!(first.body.len(), MAX_RESPONSE_BODY - 1, "splits on a char boundary");
+        assert_eq!(second.body, "é");
+        assert_eq!((second.id, second.kind), (3, SERVERDATA_RESPONSE_VALUE));
+
+        let mut data = vec![];
+        respond(&mut data, 4, "").await.unwrap();
+        assert_eq!(data, frame(4, SERVERDATA_RESPONSE_VALUE, b""));
+    }
+
+    #[test]
+    fn password_comparison() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    impl RconPacket {
+        /// Reads a response, which may be longer than what clients are allowed to send.
+        async fn read_unchecked(stream: &mut &[u8]) -> RconPacket {
+            let length = stream.read_i32_le().await.unwrap() as usize;
+            let mut data = vec![0; length];
+            stream.read_exact(&mut data).await.unwrap();
+            RconPacket {
+                id: i32::from_le_bytes(data[0..4].try_into().unwrap()),
+                kind: i32::from_le_bytes(data[4..8].try_into().unwrap()),
+                body: String::from_utf8(data[8..length - 2].to_vec()).unwrap(),
+            }
+        }
+    }
+}
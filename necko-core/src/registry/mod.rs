@@ -0,0 +1,134 @@
+use necko_protocol::packets::clientbound::configuration::registry_data::RegistryDataClientbound;
+use necko_protocol::packets::clientbound::configuration::select_known_packs::KnownPack;
+use necko_protocol::types::VarIntType;
+use crate::server::status::MINECRAFT_VERSION;
+
+include!(concat!(env!("OUT_DIR"), "/registries.rs"));
+
+pub const DIMENSION_TYPE: &str = "minecraft:dimension_type";
+pub const BIOME: &str = "minecraft:worldgen/biome";
+
+/// The vanilla data pack. Clients of the same version have it, so entries from it are sent
+/// by name only.
+pub fn core_pack() -> KnownPack {
+    KnownPack::new("minecraft", "core", MINECRAFT_VERSION)
+}
+
+/// Registries the client needs before it can join, with the vanilla entries in the order
+/// that assigns their network IDs.
+pub const SYNCHRONIZED_REGISTRIES: &[(&str, &[&str])] = &[
+    ("minecraft:banner_pattern", &[
+        "base", "border", "bricks", "circle", "creeper", "cross", "curly_border", "diagonal_left",
+        "diagonal_right", "diagonal_up_left", "diagonal_up_right", "flow", "flower", "globe",
+        "gradient", "gradient_up", "guster", "half_horizontal", "half_horizontal_bottom",
+        "half_vertical", "half_vertical_right", "mojang", "piglin", "rhombus", "skull",
+        "small_stripes", "square_bottom_left", "square_bottom_right", "square_top_left",
+        "square_top_right", "straight_cross", "stripe_bottom", "stripe_center", "stripe_downleft",
+        "stripe_downright", "stripe_left", "stripe_middle", "stripe_right", "stripe_top",
+        "triangle_bottom", "triangle_top", "triangles_bottom", "triangles_top",
+    ]),
+    ("minecraft:chat_type", &[
+        "chat", "emote_command", "msg_command_incoming", "msg_command_outgoing", "say_command",
+        "team_msg_command_incoming", "team_msg_command_outgoing",
+    ]),
+    ("minecraft:damage_type", &[
+        "arrow", "bad_respawn_point", "cactus", "campfire", "cramming", "dragon_breath", "drown",
+        "dry_out", "ender_pearl", "explosion", "fall", "falling_anvil", "falling_block",
+        "falling_stalactite", "fireball", "fireworks", "fly_into_wall", "freeze", "generic",
+        "generic_kill", "hot_floor", "in_fire", "in_wall", "indirect_magic", "lava",
+        "lightning_bolt", "mace_smash", "magic", "mob_attack", "mob_attack_no_aggro",
+        "mob_projectile", "on_fire", "out_of_world", "outside_border", "player_attack",
+        "player_explosion", "sonic_boom", "spit", "stalagmite", "starve", "sting",
+        "sweet_berry_bush", "thorns", "thrown", "trident", "unattributed_fireball", "wind_charge",
+        "wither", "wither_skull",
+    ]),
+    (DIMENSION_TYPE, &[
+        "overworld", "overworld_caves", "the_end", "the_nether",
+    ]),
+    ("minecraft:painting_variant", &[
+        "alban", "aztec", "aztec2", "backyard", "baroque", "bomb", "bouquet", "burning_skull",
+        "bust", "cavebird", "changing", "cotan", "courbet", "creebet", "donkey_kong", "earth",
+        "endboss", "fern", "fighters", "finding", "fire", "graham", "humble", "kebab", "lowmist",
+        "match", "meditative", "orb", "owlemons", "passage", "pigscene", "plant", "pointer", "pond",
+        "pool", "prairie_ride", "sea", "skeleton", "skull_and_roses", "stage", "sunflowers",
+        "sunset", "tides", "unpacked", "void", "wanderer", "wasteland", "water", "wind", "wither",
+    ]),
+    ("minecraft:trim_material", &[
+        "amethyst", "copper", "diamond", "emerald", "gold", "iron", "lapis", "netherite", "quartz",
+        "redstone", "resin",
+    ]),
+    ("minecraft:trim_pattern", &[
+        "bolt", "coast", "dune", "eye", "flow", "host", "raiser", "rib", "sentry", "shaper",
+        "silence", "snout", "spire", "tide", "vex", "ward", "wayfinder", "wild",
+    ]),
+    ("minecraft:wolf_variant", &[
+        "ashen", "black", "chestnut", "pale", "rusty", "snowy", "spotted", "striped", "woods",
+    ]),
+    (BIOME, &[
+        "badlands", "bamboo_jungle", "basalt_deltas", "beach", "birch_forest", "cherry_grove",
+        "cold_ocean", "crimson_forest", "dark_forest", "deep_cold_ocean", "deep_dark",
+        "deep_frozen_ocean", "deep_lukewarm_ocean", "deep_ocean", "desert", "dripstone_caves",
+        "end_barrens", "end_highlands", "end_midlands", "eroded_badlands", "flower_forest",
+        "forest", "frozen_ocean", "frozen_peaks", "frozen_river", "grove", "ice_spikes",
+        "jagged_peaks", "jungle", "lukewarm_ocean", "lush_caves", "mangrove_swamp", "meadow",
+        "mushroom_fields", "nether_wastes", "ocean", "old_growth_birch_forest",
+        "old_growth_pine_taiga", "old_growth_spruce_taiga", "pale_garden", "plains", "river",
+        "savanna", "savanna_plateau", "small_end_islands", "snowy_beach", "snowy_plains",
+        "snowy_slopes", "snowy_taiga", "soul_sand_valley", "sparse_jungle", "stony_peaks",
+        "stony_shore", "sunflower_plains", "swamp", "taiga", "the_end", "the_void", "warm_ocean",
+        "warped_forest", "windswept_forest", "windswept_gravelly_hills", "windswept_hills",
+        "windswept_savanna", "wooded_badlands",
+    ]),
+    ("minecraft:enchantment", &[
+        "aqua_affinity", "bane_of_arthropods", "binding_curse", "blast_protection", "breach",
+        "channeling", "density", "depth_strider", "efficiency", "feather_falling", "fire_aspect",
+        "fire_protection", "flame", "fortune", "frost_walker", "impaling", "infinity", "knockback",
+        "looting", "loyalty", "luck_of_the_sea", "lure", "mending", "multishot", "piercing",
+        "power", "projectile_protection", "protection", "punch", "quick_charge", "respiration",
+        "riptide", "sharpness", "silk_touch", "smite", "soul_speed", "sweeping_edge",
+        "swift_sneak", "thorns", "unbreaking", "vanishing_curse", "wind_burst",
+    ]),
+    ("minecraft:jukebox_song", &[
+        "11", "13", "5", "blocks", "cat", "chirp", "creator", "creator_music_box", "far", "mall",
+        "mellohi", "otherside", "pigstep", "precipice", "relic", "stal", "strad", "wait", "ward",
+    ]),
+    ("minecraft:instrument", &[
+        "admire_goat_horn", "call_goat_horn", "dream_goat_horn", "feel_goat_horn",
+        "ponder_goat_horn", "seek_goat_horn", "sing_goat_horn", "yearn_goat_horn",
+    ]),
+];
+
+/// Registry Data packets for every synchronized registry, without entry data.
+pub fn registry_packets() -> Vec<RegistryDataClientbound> {
+    SYNCHRONIZED_REGISTRIES.iter()
+        .map(|(registry, entries)| RegistryDataClientbound::new(registry, entries.iter()
+            .map(|entry| (format!("minecraft:{entry}"), None))
+            .collect()))
+        .collect()
+}
+
+/// Network ID of an entry like `minecraft:overworld` in a synchronized registry.
+pub fn network_id(registry: &str, entry: &str) -> Option<VarIntType> {
+    let entry = entry.strip_prefix("minecraft:")?;
+    let (_, entries) = SYNCHRONIZED_REGISTRIES.iter().find(|(name, _)| *name == registry)?;
+    entries.iter().position(|e| *e == entry).map(|id| id as VarIntType)
+}
+
+/// Name of the entry with a network ID, like `minecraft:plains`.
+pub fn entry_name(registry: &str, id: VarIntType) -> Option<String> {
+    let (_, entries) = SYNCHRONIZED_REGISTRIES.iter().find(|(name, _)| *name == registry)?;
+    entries.get(usize::try_from(id).ok()?).map(|entry| format!("minecraft:{entry}"))
+}
+
+/// Lowest block and height of a vanilla dimension type.
+pub fn dimension_height(dimension_type: &str) -> (i32, u32) {
+    match dimension_type {
+        "minecraft:the_nether" | "minecraft:the_end" => (0, 256),
+        _ => (-64, 384),
+    }
+}
+
+/// Whether a vanilla dimension type has sky light.
+pub fn has_skylight(dimension_type: &str) -> bool {
+    !matches!(dimension_type, "minecraft:the_nether" | "minecraft:the_end")
+}
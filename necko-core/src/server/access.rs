@@ -0,0 +1,454 @@
+use std::fs;
+use std::io::Error;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, FixedOffset, Local};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::server::Server;
+
+pub const BANNED_PLAYERS_PATH: &str = "banned-players.json";
+pub const BANNED_IPS_PATH: &str = "banned-ips.json";
+pub const WHITELIST_PATH: &str = "whitelist.json";
+pub const OPS_PATH: &str = "ops.json";
+
+pub const NOT_WHITELISTED: &str = "You are not white-listed on this server!";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AccessConfig {
+    pub whitelist: bool,
+    /// Kicks players that aren't whitelisted when the whitelist is turned on.
+    pub enforce_whitelist: bool,
+    /// Level given by `/op`.
+    pub op_permission_level: u8,
+    /// How often the list files are checked for changes made outside the server.
+    pub reload_interval_ms: u64,
+}
+
+impl AccessConfig {
+    pub fn check(&self) -> Result<(), String> {
+        if self.reload_interval_ms == 0 {
+            return Err("access.reload_interval_ms must be greater than 0".into())
+        }
+        Ok(())
+    }
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        AccessConfig {
+            whitelist: false,
+            enforce_whitelist: false,
+            op_permission_level: 4,
+            reload_interval_ms: 5000,
+        }
+    }
+}
+
+/// Dates as vanilla writes them, e.g. `2024-12-03 18:22:05 +0100`.
+pub mod date {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub const FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+    /// Written instead of a date for bans that never expire.
+    const FOREVER: &str = "forever";
+
+    pub fn format(date: &DateTime<FixedOffset>) -> String {
+        date.format(FORMAT).to_string()
+    }
+
+    pub fn serialize<S: Serializer>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(date))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error> {
+        let date = String::deserialize(deserializer)?;
+        DateTime::parse_from_str(&date, FORMAT).map_err(serde::de::Error::custom)
+    }
+
+    pub mod expiry {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(date: &Option<DateTime<FixedOffset>>, serializer: S) -> Result<S::Ok, S::Error> {
+            match date {
+                Some(date) => super::serialize(date, serializer),
+                None => serializer.serialize_str(FOREVER),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+            let date = String::deserialize(deserializer)?;
+            match date.as_str() {
+                FOREVER => Ok(None),
+                date => DateTime::parse_from_str(date, FORMAT).map(Some).map_err(serde::de::Error::custom),
+            }
+        }
+    }
+}
+
+pub trait ListEntry {
+    type Key: PartialEq;
+
+    fn key(&self) -> &Self::Key;
+    /// The player name, or the address for IP bans.
+    fn name(&self) -> &str;
+
+    fn expires(&self) -> Option<DateTime<FixedOffset>> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserBanEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(with = "date")]
+    pub created: DateTime<FixedOffset>,
+    pub source: String,
+    /// `None` for permanent bans.
+    #[serde(with = "date::expiry")]
+    pub expires: Option<DateTime<FixedOffset>>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpBanEntry {
+    pub ip: String,
+    #[serde(with = "date")]
+    pub created: DateTime<FixedOffset>,
+    pub source: String,
+    #[serde(with = "date::expiry")]
+    pub expires: Option<DateTime<FixedOffset>>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub level: u8,
+    pub bypasses_player_limit: bool,
+}
+
+impl UserBanEntry {
+    pub fn kick_message(&self) -> String {
+        with_expiry(format!("You are banned from this server.\nReason: {}", self.reason), self.expires)
+    }
+}
+
+impl IpBanEntry {
+    pub fn kick_message(&self) -> String {
+        with_expiry(format!("Your IP address is banned from this server.\nReason: {}", self.reason), self.expires)
+    }
+}
+
+fn with_expiry(message: String, expires: Option<DateTime<FixedOffset>>) -> String {
+    match expires {
+        Some(expires) => format!("{message}\nYour ban will be removed on {}", date::format(&expires)),
+        None => message,
+    }
+}
+
+impl ListEntry for UserBanEntry {
+    type Key = Uuid;
+    fn key(&self) -> &Uuid { &self.uuid }
+    fn name(&self) -> &str { &self.name }
+    fn expires(&self) -> Option<DateTime<FixedOffset>> { self.expires }
+}
+
+impl ListEntry for IpBanEntry {
+    type Key = String;
+    fn key(&self) -> &String { &self.ip }
+    fn name(&self) -> &str { &self.ip }
+    fn expires(&self) -> Option<DateTime<FixedOffset>> { self.expires }
+}
+
+impl ListEntry for WhitelistEntry {
+    type Key = Uuid;
+    fn key(&self) -> &Uuid { &self.uuid }
+    fn name(&self) -> &str { &self.name }
+}
+
+impl ListEntry for OpEntry {
+    type Key = Uuid;
+    fn key(&self) -> &Uuid { &self.uuid }
+    fn name(&self) -> &str { &self.name }
+}
+
+/// Entries keyed by UUID or address, like vanilla's `StoredUserList`. Every change is written
+/// to its JSON file right away.
+pub struct StoredList<E> {
+    path: PathBuf,
+    entries: RwLock<Vec<E>>,
+    /// Modification time of the file when it was last read or written by us.
+    modified: Mutex<Option<SystemTime>>,
+    /// Set while the file can't be parsed, so it isn't saved over until it's fixed.
+    unreadable: AtomicBool,
+}
+
+impl<E: ListEntry + Clone + Serialize + DeserializeOwned> StoredList<E> {
+    /// Loads the list, failing if the file exists but can't be parsed.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let list = StoredList {
+            path: path.into(),
+            entries: RwLock::new(vec![]),
+            modified: Mutex::new(None),
+            unreadable: AtomicBool::new(false),
+        };
+        match list.path.exists() {
+            true => list.reload()?,
+            false => list.save(),
+        }
+        Ok(list)
+    }
+
+    /// Reads the file again, keeping the current entries if it can't be parsed.
+    pub fn reload(&self) -> Result<(), Error> {
+        let result = fs::read_to_string(&self.path)
+            .and_then(|json| serde_json::from_str::<Vec<E>>(&json).map_err(Error::other))
+            .map_err(|e| Error::new(e.kind(), format!("Failed to load {}: {}", self.path.display(), e)));
+        *self.modified.lock().unwrap() = self.file_modified();
+        self.unreadable.store(result.is_err(), Ordering::Relaxed);
+        *self.entries.write().unwrap() = result?;
+        Ok(())
+    }
+
+    pub fn save(&self) {
+        if self.unreadable.load(Ordering::Relaxed) {
+            tracing::error!("Not saving {} until it can be loaded again", self.path.display());
+            return
+        }
+        let entries = self.entries.read().unwrap();
+        let result = serde_json::to_string_pretty(&*entries)
+            .map_err(Error::other)
+            .and_then(|json| fs::write(&self.path, json));
+        drop(entries);
+        if let Err(e) = result {
+            tracing::error!("Failed to save {}: {}", self.path.display(), e);
+        }
+        *self.modified.lock().unwrap() = self.file_modified();
+    }
+
+    fn file_modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Reloads the file if something else changed it. Returns whether it did.
+    pub fn reload_if_changed(&self) -> bool {
+        let modified = self.file_modified();
+        if modified.is_none() || modified == *self.modified.lock().unwrap() {
+            return false
+        }
+        tracing::info!("Reloading {}", self.path.display());
+        if let Err(e) = self.reload() {
+            tracing::error!("{}", e);
+        }
+        true
+    }
+
+    /// The entry for `key`, removing it instead if it has expired.
+    pub fn get(&self, key: &E::Key) -> Option<E> {
+        let entry = self.entries.read().unwrap().iter().find(|entry| entry.key() == key).cloned()?;
+        if entry.expires().is_some_and(|expires| expires < now()) {
+            self.remove(key);
+            return None
+        }
+        Some(entry)
+    }
+
+    pub fn contains(&self, key: &E::Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Adds the entry, replacing one with the same key. Returns whether it wasn't there before.
+    pub fn add(&self, entry: E) -> bool {
+        let mut entries = self.entries.write().unwrap();
+        let existed = entries.iter().position(|e| e.key() == entry.key())
+            .map(|i| entries.remove(i))
+            .is_some();
+        entries.push(entry);
+        drop(entries);
+        self.save();
+        !existed
+    }
+
+    pub fn remove(&self, key: &E::Key) -> Option<E> {
+        let mut entries = self.entries.write().unwrap();
+        let removed = entries.iter().position(|entry| entry.key() == key).map(|i| entries.remove(i));
+        drop(entries);
+        if removed.is_some() {
+            self.save();
+        }
+        removed
+    }
+
+    pub fn entries(&self) -> Vec<E> {
+        self.entries.read().unwrap().clone()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.entries.read().unwrap().iter().map(|entry| entry.name().to_string()).collect()
+    }
+}
+
+/// Bans, the whitelist and operators, stored in vanilla's JSON files.
+pub struct AccessLists {
+    pub banned_players: StoredList<UserBanEntry>,
+    pub banned_ips: StoredList<IpBanEntry>,
+    pub whitelist: StoredList<WhitelistEntry>,
+    pub ops: StoredList<OpEntry>,
+    whitelist_enabled: AtomicBool,
+}
+
+impl AccessLists {
+    pub fn load(config: &AccessConfig) -> Result<Self, Error> {
+        Ok(AccessLists {
+            banned_players: StoredList::load(BANNED_PLAYERS_PATH)?,
+            banned_ips: StoredList::load(BANNED_IPS_PATH)?,
+            whitelist: StoredList::load(WHITELIST_PATH)?,
+            ops: StoredList::load(OPS_PATH)?,
+            whitelist_enabled: AtomicBool::new(config.whitelist),
+        })
+    }
+
+    pub fn whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        self.whitelist_enabled.store(enabled, Ordering::Relaxed)
+    }
+
+    pub fn op_level(&self, uuid: &Uuid) -> u8 {
+        self.ops.get(uuid).map_or(0, |op| op.level)
+    }
+
+    /// Whether the player may join, operators are always allowed.
+    pub fn is_whitelisted(&self, uuid: &Uuid) -> bool {
+        !self.whitelist_enabled() || self.ops.contains(uuid) || self.whitelist.contains(uuid)
+    }
+
+    /// Why the player can't join, checked in the same order as vanilla.
+    pub fn login_denial(&self, uuid: &Uuid, ip: IpAddr) -> Option<String> {
+        if let Some(ban) = self.banned_players.get(uuid) {
+            return Some(ban.kick_message())
+        }
+        if !self.is_whitelisted(uuid) {
+            return Some(NOT_WHITELISTED.into())
+        }
+        if let Some(ban) = self.banned_ips.get(&ip.to_string()) {
+            return Some(ban.kick_message())
+        }
+        None
+    }
+
+    /// Reloads every list whose file was changed. Returns whether any was.
+    pub fn reload_changed(&self) -> bool {
+        let mut changed = self.banned_players.reload_if_changed();
+        changed |= self.banned_ips.reload_if_changed();
+        changed |= self.whitelist.reload_if_changed();
+        changed |= self.ops.reload_if_changed();
+        changed
+    }
+
+    pub fn reload(&self) -> Result<(), Error> {
+        self.banned_players.reload()?;
+        self.banned_ips.reload()?;
+        self.whitelist.reload()?;
+        self.ops.reload()
+    }
+}
+
+impl Server {
+    /// Kicks players that aren't whitelisted, if the whitelist is enforced.
+    pub async fn enforce_whitelist(&self) {
+        if !self.config.access.enforce_whitelist || !self.access.whitelist_enabled() {
+            return
+        }
+        for player in self.online_players().await {
+            let Some(uuid) = player.profile.read().await.as_ref().map(|profile| profile.uuid) else {
+                continue
+            };
+            if !self.access.is_whitelisted(&uuid) {
+                player.disconnect(NOT_WHITELISTED).await
+            }
+        }
+    }
+
+    /// Applies changed lists to the players that are online.
+    pub async fn apply_access_lists(&self) {
+        for player in self.online_players().await {
+            let Some(uuid) = player.profile.read().await.as_ref().map(|profile| profile.uuid) else {
+                continue
+            };
+            let ip = player.address.read().await.ip();
+            if let Some(reason) = self.access.banned_players.get(&uuid).map(|ban| ban.kick_message())
+                .or_else(|| self.access.banned_ips.get(&ip.to_string()).map(|ban| ban.kick_message())) {
+                player.disconnect(&reason).await;
+                continue
+            }
+            player.update_permission_level(self).await
+        }
+        self.enforce_whitelist().await
+    }
+}
+
+/// Reloads the list files when they're edited while the server runs.
+pub async fn watch(server: Arc<Server>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(server.config.access.reload_interval_ms));
+    while !server.is_stopping() {
+        interval.tick().await;
+        if server.access.reload_changed() {
+            server.apply_access_lists().await
+        }
+    }
+}
+
+pub fn now() -> DateTime<FixedOffset> {
+    Local::now().fixed_offset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_files_are_never_saved_over() {
+        let path = std::env::temp_dir().join(format!("necko-whitelist-{}.json", std::process::id()));
+        fs::write(&path, "[{\"uuid\": ").unwrap();
+        assert!(StoredList::<WhitelistEntry>::load(&path).is_err());
+
+        fs::write(&path, "[]").unwrap();
+        let list = StoredList::<WhitelistEntry>::load(&path).unwrap();
+        fs::write(&path, "not json").unwrap();
+        assert!(list.reload().is_err());
+        list.add(WhitelistEntry { uuid: Uuid::nil(), name: "Notch".into() });
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not json");
+        assert_eq!(list.names(), ["Notch"]);
+
+        fs::write(&path, "[]").unwrap();
+        list.reload().unwrap();
+        assert!(list.add(WhitelistEntry { uuid: Uuid::nil(), name: "Notch".into() }));
+        assert!(fs::read_to_string(&path).unwrap().contains("Notch"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reload_interval_must_be_positive() {
+        assert!(AccessConfig { reload_interval_ms: 0, ..AccessConfig::default() }.check().is_err());
+        assert!(AccessConfig::default().check().is_ok());
+    }
+}
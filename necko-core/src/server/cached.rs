@@ -1,3 +1,4 @@
+use crate::config::ServerConfig;
 use crate::server::status::Status;
 use base64::{engine::general_purpose, Engine};
 use std::error::Error;
@@ -10,13 +11,12 @@ pub struct CachedStatus {
 }
 
 impl CachedStatus {
-    pub fn new() -> Self {
+    pub fn new(config: &ServerConfig) -> Self {
         let status = Status::build(
-            -1, "hello from necko-core :3".into(), 
-            match Self::build_favicon("icon.png") {
-                Ok(data) => Some(data), _ => None
-            }, false, false
-        );
+            config.max_players, config.motd.clone(),
+            Self::build_favicon(&config.icon).ok(),
+            config.enforces_secure_chat, config.previews_chat
+        ).with_extensions(config.status.clone());
         let json = serde_json::to_string(&status)
             .expect("Could not serialize cache status.");
 
@@ -1,18 +1,226 @@
-use tokio::sync::RwLock;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+use uuid::Uuid;
+use necko_protocol::packets::ClientboundPacket;
+use necko_protocol::packets::clientbound::play::system_chat::SystemChatClientbound;
+use necko_protocol::text::TextComponent;
+use crate::client::Client;
+use crate::client::player_data::PLAYER_DATA_DIRECTORY;
+use crate::command::CommandDispatcher;
+use crate::config::ServerConfig;
 use crate::server::cached::CachedStatus;
+use crate::server::throttle::ConnectionThrottle;
+use crate::server::access::AccessLists;
+use crate::server::usercache::UserCache;
+use crate::server::ticks::TickTimes;
+use crate::permission::PermissionBackend;
+use crate::permission::file::FilePermissions;
+use crate::world::{Weather, World};
 
 pub mod status;
+pub mod throttle;
+pub mod shutdown;
+pub mod access;
+pub mod usercache;
+pub mod ticks;
 mod cached;
 
+/// Pause of the RCON and metrics listeners after a failed accept, so running out of file
+/// descriptors doesn't make them spin.
+pub const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
 pub struct Server {
-    pub cached_status: RwLock<CachedStatus>
+    pub config: ServerConfig,
+    pub cached_status: RwLock<CachedStatus>,
+    pub throttle: ConnectionThrottle,
+    pub clients: RwLock<HashMap<usize, Arc<Client>>>,
+    pub commands: CommandDispatcher,
+    pub access: AccessLists,
+    pub user_cache: UserCache,
+    pub permissions: Box<dyn PermissionBackend>,
+    /// Every world, the one players join first.
+    pub worlds: Vec<Arc<World>>,
+    pub ticks: TickTimes,
+    next_client_id: AtomicUsize,
+    next_entity_id: AtomicI32,
+    stopping: AtomicBool,
+    stop: Notify,
+}
+
+/// Removes a client from the server when dropped.
+pub struct ClientGuard {
+    server: Arc<Server>,
+    id: usize,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        let (server, id) = (self.server.clone(), self.id);
+        tokio::spawn(async move { server.remove_client(id).await });
+    }
 }
 
 impl Server {
-    pub fn new() -> Self {
+    pub fn new(config: ServerConfig) -> Self {
         Server {
-            cached_status: RwLock::new(CachedStatus::new())
+            cached_status: RwLock::new(CachedStatus::new(&config)),
+            throttle: ConnectionThrottle::new(config.throttle.clone()),
+            clients: RwLock::new(HashMap::new()),
+            commands: CommandDispatcher::new(),
+            access: AccessLists::load(&config.access).expect("Could not load access lists"),
+            user_cache: UserCache::load(),
+            permissions: Box::new(FilePermissions::load(&config.permissions.path)
+                .expect("Could not load permissions")),
+            worlds: config.worlds.iter().map(|world| World::new(world).map(Arc::new)).collect::<Result<_, _>>()
+                .expect("Could not load worlds"),
+            ticks: TickTimes::default(),
+            next_client_id: AtomicUsize::new(0),
+            next_entity_id: AtomicI32::new(1),
+            stopping: AtomicBool::new(false),
+            stop: Notify::new(),
+            config,
         }
     }
-}
 
+    /// Replaces the permissions file with another backend.
+    pub fn with_permissions(mut self, backend: impl PermissionBackend + 'static) -> Self {
+        self.permissions = Box::new(backend);
+        self
+    }
+
+    /// Registers a connection until the returned guard is dropped, even if its task panics.
+    pub async fn add_client(self: &Arc<Self>, client: Arc<Client>) -> ClientGuard {
+        let id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        self.clients.write().await.insert(id, client);
+        ClientGuard { server: self.clone(), id }
+    }
+
+    /// The world players join.
+    pub fn default_world(&self) -> &Arc<World> {
+        &self.worlds[0]
+    }
+
+    /// Where players are saved, in the world players join like vanilla.
+    pub fn player_data_directory(&self) -> PathBuf {
+        self.default_world().directory().join(PLAYER_DATA_DIRECTORY)
+    }
+
+    /// The world of a dimension like `minecraft:overworld`.
+    pub fn world(&self, dimension: &str) -> Option<&Arc<World>> {
+        self.worlds.iter().find(|world| world.dimension == dimension)
+    }
+
+    /// A new ID for an entity, unique as long as the server runs.
+    pub fn next_entity_id(&self) -> i32 {
+        self.next_entity_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub async fn remove_client(&self, id: usize) {
+        let client = self.clients.read().await.get(&id).cloned();
+        let Some(client) = client.filter(|client| client.is_playing()) else {
+            self.clients.write().await.remove(&id);
+            return
+        };
+        // Saved before the client is removed, so a shutdown waiting for clients waits for it too.
+        client.save_data(self).await;
+        self.clients.write().await.remove(&id);
+        let name = client.name().await;
+        self.broadcast_message(TextComponent::text(format!("{name} left the game")).color("yellow")).await
+    }
+
+    /// Clients that joined the game.
+    pub async fn online_players(&self) -> Vec<Arc<Client>> {
+        self.clients.read().await.values()
+            .filter(|client| client.is_playing())
+            .cloned()
+            .collect()
+    }
+
+    pub async fn player_by_name(&self, name: &str) -> Option<Arc<Client>> {
+        for player in self.online_players().await {
+            if player.profile.read().await.as_ref().is_some_and(|profile| profile.name.eq_ignore_ascii_case(name)) {
+                return Some(player)
+            }
+        }
+        None
+    }
+
+    pub async fn player_by_uuid(&self, uuid: &Uuid) -> Option<Arc<Client>> {
+        for player in self.online_players().await {
+            if player.profile.read().await.as_ref().is_some_and(|profile| profile.uuid == *uuid) {
+                return Some(player)
+            }
+        }
+        None
+    }
+
+    pub async fn player_names(&self) -> Vec<String> {
+        let mut names = vec![];
+        for client in self.online_players().await {
+            names.push(client.name().await)
+        }
+        names
+    }
+
+    pub async fn broadcast<P: ClientboundPacket>(&self, packet: &P) {
+        for player in self.online_players().await {
+            player.send_packet(packet).await
+        }
+    }
+
+    /// Players in a world.
+    pub async fn players_in(&self, world: &World) -> Vec<Arc<Client>> {
+        let mut players = vec![];
+        for player in self.online_players().await {
+            if player.player.lock().await.dimension == world.dimension {
+                players.push(player)
+            }
+        }
+        players
+    }
+
+    pub async fn broadcast_in<P: ClientboundPacket>(&self, world: &World, packet: &P) {
+        for player in self.players_in(world).await {
+            player.send_packet(packet).await
+        }
+    }
+
+    /// Sends the light of chunks that changed to the players that have them.
+    pub async fn send_light_updates(&self) {
+        for world in &self.worlds {
+            let updates = world.take_light_updates();
+            if updates.is_empty() {
+                continue
+            }
+            let players = self.online_players().await;
+            for (x, z) in updates {
+                let Some(chunk) = world.loaded_chunk(x, z) else { continue };
+                let packet = chunk.read().unwrap().light_packet();
+                for player in &players {
+                    if player.chunks.lock().await.has_chunk(world, x, z) {
+                        player.send_packet(&packet).await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tells the players in a world its weather changed from `previous`.
+    pub async fn send_weather(&self, world: &World, previous: Weather) {
+        let weather = world.weather();
+        for player in self.players_in(world).await {
+            player.send_weather(previous, weather).await
+        }
+    }
+
+    /// Sends a chat message to every player and the console.
+    pub async fn broadcast_message(&self, message: impl Into<TextComponent>) {
+        let message = message.into();
+        tracing::info!("{}", message.plain());
+        self.broadcast(&SystemChatClientbound::new(&message, false)).await
+    }
+}
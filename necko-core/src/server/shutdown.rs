@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use crate::server::Server;
+
+/// How often the shutdown checks whether every connection has been closed.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    pub message: String,
+    /// Time the whole shutdown may take before the process exits anyway.
+    pub timeout_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            message: "Server closed".into(),
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+impl Server {
+    /// Asks the accept loop to stop and shut the server down.
+    pub fn request_stop(&self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        self.stop.notify_one();
+    }
+
+    pub async fn stop_requested(&self) {
+        self.stop.notified().await
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+
+    /// Kicks every client and waits for their connections to close, giving up once the
+    /// configured timeout has passed, then saves the worlds.
+    pub async fn shutdown(self: &Arc<Self>) {
+        self.stopping.store(true, Ordering::Relaxed);
+        let timeout = Duration::from_millis(self.config.shutdown.timeout_ms);
+        if tokio::time::timeout(timeout, self.drain()).await.is_err() {
+            tracing::warn!("Disconnecting took longer than {}ms, saving anyway", self.config.shutdown.timeout_ms);
+        }
+        self.save_all().await;
+    }
+
+    async fn drain(self: &Arc<Self>) {
+        let clients: Vec<_> = self.clients.read().await.values().cloned().collect();
+        tracing::info!("Disconnecting {} connection(s)", clients.len());
+        let mut disconnects = JoinSet::new();
+        for client in clients {
+            let server = self.clone();
+            disconnects.spawn(async move {
+                client.disconnect(&server.config.shutdown.message).await
+            });
+        }
+        disconnects.join_all().await;
+
+        while !self.clients.read().await.is_empty() {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn save_all(&self) {
+        tracing::info!("Saving players and worlds");
+        for player in self.online_players().await {
+            player.save_data(self).await
+        }
+        for world in &self.worlds {
+            world.save_chunks().await;
+            if let Err(e) = world.save_level() {
+                tracing::error!("Could not save the level of world '{}': {}", world.name, e)
+            }
+        }
+    }
+}
@@ -1,7 +1,13 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 pub const PROTOCOL_VERSION: i32 = 769;
 pub const MINECRAFT_VERSION: &str = "1.21.4";
+/// Keys of the status JSON set by the server or by the other extensions, which `extra` can't use.
+const RESERVED_FIELDS: [&str; 9] = [
+    "version", "players", "description", "favicon", "enforcesSecureChat", "previewsChat",
+    "forgeData", "modinfo", "preventsChatReports",
+];
 
 
 #[derive(Serialize, Deserialize)]
@@ -23,6 +29,87 @@ pub struct Version {
     pub protocol: i32,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForgeChannel {
+    pub res: String,
+    pub version: String,
+    pub required: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForgeMod {
+    #[serde(rename = "modId")]
+    pub mod_id: String,
+    #[serde(rename = "modmarker")]
+    pub mod_marker: String,
+}
+
+/// Modern Forge/NeoForge mod list, read by modded clients to show compatibility icons.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForgeData {
+    #[serde(default)]
+    pub channels: Vec<ForgeChannel>,
+    #[serde(default)]
+    pub mods: Vec<ForgeMod>,
+    #[serde(rename = "fmlNetworkVersion")]
+    pub fml_network_version: i32,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LegacyMod {
+    #[serde(rename = "modid")]
+    pub mod_id: String,
+    pub version: String,
+}
+
+/// Legacy (pre-1.13) Forge mod list.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModInfo {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(rename = "modList", default)]
+    pub mod_list: Vec<LegacyMod>,
+}
+
+/// Optional fields appended to the status JSON, only serialized when present. Unknown keys
+/// are rejected so a typo doesn't go unnoticed, other fields go in `extra`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StatusExtensions {
+    #[serde(rename = "forgeData", skip_serializing_if = "Option::is_none")]
+    pub forge_data: Option<ForgeData>,
+    #[serde(rename = "modinfo", skip_serializing_if = "Option::is_none")]
+    pub mod_info: Option<ModInfo>,
+    #[serde(rename = "preventsChatReports", skip_serializing_if = "Option::is_none")]
+    pub prevents_chat_reports: Option<bool>,
+    /// Any other fields, added to the status JSON as they are.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+impl StatusExtensions {
+    pub fn check(&self) -> Result<(), String> {
+        match self.extra.keys().find(|key| RESERVED_FIELDS.contains(&key.as_str())) {
+            Some(key) => Err(format!("status.extra can't set '{key}', it is already in the status")),
+            None => Ok(()),
+        }
+    }
+
+    /// The fields as they appear in the status JSON, with `extra` merged in.
+    fn fields(&self) -> Map<String, Value> {
+        let mut fields = match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+        if let Some(Value::Object(extra)) = fields.remove("extra") {
+            fields.extend(extra)
+        }
+        fields
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Status {
     pub version: Version,
@@ -33,6 +120,8 @@ pub struct Status {
     pub enforces_secure_chat: bool,
     #[serde(rename = "previewsChat")]
     pub previews_chat: bool,
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
 }
 
 impl Status {
@@ -61,8 +150,63 @@ impl Status {
             favicon, // configurable
             enforces_secure_chat, // configurable
             previews_chat, // configurable
+            extensions: Map::new(),
         }
     }
+
+    pub fn with_extensions(mut self, extensions: StatusExtensions) -> Self {
+        self.extensions = extensions.fields();
+        self
+    }
     
 }
 
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn extra_fields_are_merged() {
+        let extensions = StatusExtensions {
+            prevents_chat_reports: Some(true),
+            extra: Map::from_iter([("isModded".into(), json!(true)), ("custom".into(), json!({ "a": 1 }))]),
+            ..StatusExtensions::default()
+        };
+        assert_eq!(Value::Object(extensions.fields()), json!({
+            "preventsChatReports": true,
+            "isModded": true,
+            "custom": { "a": 1 },
+        }));
+        assert!(StatusExtensions::default().fields().is_empty());
+    }
+
+    #[test]
+    fn status_includes_extensions() {
+        let extensions = StatusExtensions {
+            extra: Map::from_iter([("isModded".into(), json!(true))]),
+            ..StatusExtensions::default()
+        };
+        let status = Status::build(20, "hi".into(), None, false, false).with_extensions(extensions);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["isModded"], json!(true));
+        assert_eq!(json["description"], json!("hi"));
+        assert_eq!(json["players"]["max"], json!(20));
+    }
+
+    #[test]
+    fn reserved_extra_keys_are_rejected() {
+        for key in RESERVED_FIELDS {
+            let extensions = StatusExtensions {
+                extra: Map::from_iter([(key.into(), json!(1))]),
+                ..StatusExtensions::default()
+            };
+            assert!(extensions.check().is_err(), "{key}");
+        }
+        let extensions = StatusExtensions {
+            extra: Map::from_iter([("isModded".into(), json!(true))]),
+            ..StatusExtensions::default()
+        };
+        assert!(extensions.check().is_ok());
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Tracked addresses are only pruned once the map grows past this size.
+const CLEANUP_THRESHOLD: usize = 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// Maximum number of open connections across all addresses, `0` to disable.
+    pub max_connections: usize,
+    /// Maximum number of connections a single address may open per window, `0` to disable.
+    /// Not applied behind a forwarding proxy unless it also sends PROXY protocol headers.
+    pub connections_per_ip: u32,
+    pub rate_limit_window_ms: u64,
+    /// Minimum time between two login attempts from the same address, `0` to disable
+    /// (vanilla's `connection-throttle`). Uses the forwarded address when forwarding is enabled.
+    pub login_throttle_ms: u64,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            max_connections: 1024,
+            connections_per_ip: 20,
+            rate_limit_window_ms: 10_000,
+            login_throttle_ms: 4_000,
+        }
+    }
+}
+
+pub struct ConnectionThrottle {
+    pub config: ThrottleConfig,
+    connections: Arc<Semaphore>,
+    attempts: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+    logins: Mutex<HashMap<IpAddr, Instant>>,
+}
+
+impl ConnectionThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        let permits = match config.max_connections {
+            0 => Semaphore::MAX_PERMITS,
+            max => max,
+        };
+        ConnectionThrottle {
+            config,
+            connections: Arc::new(Semaphore::new(permits)),
+            attempts: Mutex::new(HashMap::new()),
+            logins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves a connection slot, held until the returned permit is dropped.
+    pub fn acquire_connection(&self) -> Option<OwnedSemaphorePermit> {
+        self.connections.clone().try_acquire_owned().ok()
+    }
+
+    /// Counts a new connection from `address`, returning `false` if it went over the rate limit.
+    pub fn allow_connection(&self, address: IpAddr) -> bool {
+        if self.config.connections_per_ip == 0 {
+            return true
+        }
+
+        let window = Duration::from_millis(self.config.rate_limit_window_ms);
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().expect("Throttle lock poisoned");
+        if attempts.len() > CLEANUP_THRESHOLD {
+            attempts.retain(|_, (start, _)| now.duration_since(*start) < window);
+        }
+
+        let (start, count) = attempts.entry(address).or_insert((now, 0));
+        if now.duration_since(*start) >= window {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.config.connections_per_ip
+    }
+
+    /// Records a login attempt from `address`, returning `false` if the previous one was too recent.
+    pub fn allow_login(&self, address: IpAddr) -> bool {
+        if self.config.login_throttle_ms == 0 {
+            return true
+        }
+
+        let throttle = Duration::from_millis(self.config.login_throttle_ms);
+        let now = Instant::now();
+        let mut logins = self.logins.lock().expect("Throttle lock poisoned");
+        if logins.len() > CLEANUP_THRESHOLD {
+            logins.retain(|_, last| now.duration_since(*last) < throttle);
+        }
+
+        match logins.insert(address, now) {
+            Some(last) => now.duration_since(last) >= throttle,
+            None => true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use super::*;
+
+    fn throttle(window_ms: u64) -> ConnectionThrottle {
+        ConnectionThrottle::new(ThrottleConfig {
+            max_connections: 2,
+            connections_per_ip: 3,
+            rate_limit_window_ms: window_ms,
+            login_throttle_ms: window_ms,
+        })
+    }
+
+    fn ip(last: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(0x0A00_0000 + last))
+    }
+
+    #[test]
+    fn connections_per_ip_in_a_window() {
+        let throttle = throttle(50);
+        assert!((0..3).all(|_| throttle.allow_connection(ip(1))));
+        assert!(!throttle.allow_connection(ip(1)));
+        assert!(throttle.allow_connection(ip(2)), "other addresses have their own count");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(throttle.allow_connection(ip(1)), "the count restarts with the window");
+    }
+
+    #[test]
+    fn login_throttle() {
+        let throttle = throttle(50);
+        assert!(throttle.allow_login(ip(1)));
+        assert!(!throttle.allow_login(ip(1)));
+        assert!(throttle.allow_login(ip(2)));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(throttle.allow_login(ip(1)));
+    }
+
+    #[test]
+    fn open_connection_permits() {
+        let throttle = throttle(50);
+        let first = throttle.acquire_connection().unwrap();
+        let _second = throttle.acquire_connection().unwrap();
+        assert!(throttle.acquire_connection().is_none());
+        drop(first);
+        assert!(throttle.acquire_connection().is_some(), "closing a connection frees its slot");
+
+        let unlimited = ConnectionThrottle::new(ThrottleConfig { max_connections: 0, ..ThrottleConfig::default() });
+        let permits: Vec<_> = (0..100).map(|_| unlimited.acquire_connection()).collect();
+        assert!(permits.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn disabled_limits() {
+        let throttle = ConnectionThrottle::new(ThrottleConfig {
+            connections_per_ip: 0,
+            login_throttle_ms: 0,
+            ..ThrottleConfig::default()
+        });
+        assert!((0..100).all(|_| throttle.allow_connection(ip(1)) && throttle.allow_login(ip(1))));
+    }
+
+    #[test]
+    fn old_entries_expire() {
+        let throttle = throttle(20);
+        for last in 0..=CLEANUP_THRESHOLD as u32 {
+            throttle.allow_connection(ip(last));
+            throttle.allow_login(ip(last));
+        }
+        std::thread::sleep(Duration::from_millis(30));
+        throttle.allow_connection(ip(u16::MAX as u32));
+        throttle.allow_login(ip(u16::MAX as u32));
+        assert_eq!(throttle.attempts.lock().unwrap().len(), 1);
+        assert_eq!(throttle.logins.lock().unwrap().len(), 1);
+    }
+}
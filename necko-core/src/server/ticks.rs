@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use necko_protocol::packets::clientbound::play::set_time::SetTimeClientbound;
+use crate::server::Server;
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+/// How far behind the server may fall before it stops catching up and skips ticks.
+const MAX_BEHIND: Duration = Duration::from_secs(2);
+/// Least time between two "Can't keep up!" warnings.
+const OVERLOAD_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+/// Ticks between the times sent to players, whose clients move the time on in between.
+const TIME_SYNC_INTERVAL: u64 = 20;
+/// How many of the latest ticks the averages are taken over.
+const SAMPLES: usize = 100;
+/// Upper bounds of the tick duration histogram buckets in milliseconds.
+pub const HISTOGRAM_BUCKETS_MS: [u64; 8] = [5, 10, 25, 50, 100, 250, 500, 1000];
+
+/// Durations and start times of the latest ticks, for `/tps`, and a histogram of all of
+/// them for metrics.
+#[derive(Default)]
+pub struct TickTimes {
+    samples: Mutex<VecDeque<Duration>>,
+    starts: Mutex<VecDeque<Instant>>,
+    /// Ticks the server skipped because it fell too far behind.
+    skipped: AtomicU64,
+    /// Ticks per bucket, the last one counting ticks longer than every bound.
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS_MS.len() + 1],
+    total_nanos: AtomicU64,
+}
+
+pub struct TickHistogram {
+    /// Upper bounds in seconds with the cumulative count of ticks at most that long.
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl TickTimes {
+    pub fn record(&self, start: Instant, duration: Duration) {
+        let mut starts = self.starts.lock().unwrap();
+        if starts.len() == SAMPLES {
+            starts.pop_front();
+        }
+        starts.push_back(start);
+        drop(starts);
+
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+
+        let bucket = HISTOGRAM_BUCKETS_MS.iter().position(|&bound| duration <= Duration::from_millis(bound))
+            .unwrap_or(HISTOGRAM_BUCKETS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.total_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn histogram(&self) -> TickHistogram {
+        let mut count = 0;
+        let mut buckets = Vec::with_capacity(HISTOGRAM_BUCKETS_MS.len());
+        for (bound, bucket) in HISTOGRAM_BUCKETS_MS.iter().zip(&self.buckets) {
+            count += bucket.load(Ordering::Relaxed);
+            buckets.push((*bound as f64 / 1000.0, count));
+        }
+        count += self.buckets[HISTOGRAM_BUCKETS_MS.len()].load(Ordering::Relaxed);
+
+        TickHistogram {
+            buckets,
+            count,
+            sum: self.total_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+        }
+    }
+
+    /// Average milliseconds per tick.
+    pub fn mspt(&self) -> f64 {
+        let samples = self.samples.lock().unwrap();
+        if samples.is_empty() {
+            return 0.0
+        }
+        samples.iter().sum::<Duration>().as_secs_f64() * 1000.0 / samples.len() as f64
+    }
+
+    /// Ticks run per second over the latest ticks, up to now so a tick that takes long
+    /// already counts.
+    pub fn tps(&self) -> f64 {
+        let starts = self.starts.lock().unwrap();
+        let max = TICKS_PER_SECOND as f64;
+        match starts.front() {
+            Some(first) if starts.len() > 1 => (starts.len() as f64 / first.elapsed().as_secs_f64()).min(max),
+            _ => max,
+        }
+    }
+
+    /// Ticks run since the server started.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|bucket| bucket.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs the game at [`TICKS_PER_SECOND`] until the server stops. Ticks that come late are
+/// run back to back to catch up, unless the server is so far behind that it skips them.
+pub async fn run(server: Arc<Server>) {
+    let mut next_tick = Instant::now();
+    let mut last_warning: Option<Instant> = None;
+    while !server.is_stopping() {
+        tokio::time::sleep_until(next_tick.into()).await;
+        let start = Instant::now();
+        let behind = start - next_tick;
+        if behind > MAX_BEHIND {
+            let ticks = (behind.as_nanos() / TICK_DURATION.as_nanos()) as u64;
+            if last_warning.is_none_or(|warning| warning.elapsed() >= OVERLOAD_WARNING_INTERVAL) {
+                tracing::warn!("Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+                    behind.as_millis(), ticks);
+                last_warning = Some(start)
+            }
+            server.ticks.skipped.fetch_add(ticks, Ordering::Relaxed);
+            next_tick += TICK_DURATION * ticks as u32
+        }
+        next_tick += TICK_DURATION;
+
+        server.tick().await;
+        server.ticks.record(start, start.elapsed())
+    }
+}
+
+impl Server {
+    /// One game tick: handles what players did since the last one, moves the world on and
+    /// sends players what changed.
+    async fn tick(&self) {
+        let players = self.online_players().await;
+        for player in &players {
+            player.handle_queued_packets(self).await
+        }
+
+        let sync_time = self.ticks.count().is_multiple_of(TIME_SYNC_INTERVAL);
+        for world in &self.worlds {
+            let previous_weather = world.tick();
+            let weather = world.weather();
+            if weather.raining != previous_weather.raining || weather.thundering != previous_weather.thundering {
+                self.send_weather(world, previous_weather).await
+            }
+            if sync_time {
+                let time = world.time();
+                self.broadcast_in(world, &SetTimeClientbound::new(time.game_time, time.day_time, true)).await
+            }
+        }
+        self.send_light_updates().await
+    }
+}
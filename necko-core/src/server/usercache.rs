@@ -0,0 +1,72 @@
+use std::fs;
+use std::io::Error;
+use std::sync::RwLock;
+use chrono::{DateTime, FixedOffset, Months};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use necko_protocol::types::GameProfile;
+use crate::server::access::{date, now};
+
+pub const USER_CACHE_PATH: &str = "usercache.json";
+/// Vanilla keeps the most recently seen profiles only.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserCacheEntry {
+    pub name: String,
+    pub uuid: Uuid,
+    #[serde(with = "date")]
+    pub expires_on: DateTime<FixedOffset>,
+}
+
+/// Names and UUIDs of players that joined before, used to resolve offline players in commands.
+/// Entries are kept most recently used first.
+pub struct UserCache {
+    entries: RwLock<Vec<UserCacheEntry>>,
+}
+
+impl UserCache {
+    pub fn load() -> Self {
+        let entries = fs::read_to_string(USER_CACHE_PATH)
+            .and_then(|json| serde_json::from_str::<Vec<UserCacheEntry>>(&json).map_err(Error::other))
+            .unwrap_or_else(|e| {
+                tracing::debug!("Couldn't load {}: {}", USER_CACHE_PATH, e);
+                vec![]
+            });
+        UserCache { entries: RwLock::new(entries) }
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<UserCacheEntry> {
+        self.find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_by_uuid(&self, uuid: &Uuid) -> Option<UserCacheEntry> {
+        self.find(|entry| entry.uuid == *uuid)
+    }
+
+    fn find(&self, predicate: impl Fn(&UserCacheEntry) -> bool) -> Option<UserCacheEntry> {
+        let now = now();
+        self.entries.read().unwrap().iter()
+            .find(|entry| predicate(entry) && entry.expires_on > now)
+            .cloned()
+    }
+
+    /// Remembers the profile of a player that just joined for a month.
+    pub fn add(&self, profile: &GameProfile) {
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|entry| entry.uuid != profile.uuid && !entry.name.eq_ignore_ascii_case(&profile.name));
+        entries.insert(0, UserCacheEntry {
+            name: profile.name.clone(),
+            uuid: profile.uuid,
+            expires_on: now() + Months::new(1),
+        });
+        entries.truncate(MAX_ENTRIES);
+        let result = serde_json::to_string_pretty(&*entries)
+            .map_err(Error::other)
+            .and_then(|json| fs::write(USER_CACHE_PATH, json));
+        if let Err(e) = result {
+            tracing::error!("Failed to save {}: {}", USER_CACHE_PATH, e);
+        }
+    }
+}
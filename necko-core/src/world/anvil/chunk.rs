@@ -0,0 +1,286 @@
+use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use necko_protocol::nbt::{Compound, Tag};
+use crate::registry::{self, BIOME};
+use crate::server::status::MINECRAFT_VERSION;
+use crate::world::block::{self, Block, BlockState, AIR};
+use crate::world::chunk::heightmap::HeightmapKind;
+use crate::world::chunk::light::LightArray;
+use crate::world::chunk::palette::PalettedContainer;
+use crate::world::chunk::{Biome, Chunk, ChunkSection, SECTION_SIZE};
+
+/// Data version of chunks saved by Minecraft 1.21.4.
+pub const DATA_VERSION: i32 = 4189;
+/// The oldest chunks with sections stored like today (1.18), which can be read without
+/// upgrading them in vanilla first.
+pub const MIN_DATA_VERSION: i32 = 2860;
+
+const FULL_STATUS: &str = "minecraft:full";
+
+static OLD_VERSION_WARNED: AtomicBool = AtomicBool::new(false);
+static UNFINISHED_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Tags of a saved chunk that are read into the chunk model. Everything else is preserved.
+const MODELED_TAGS: &[&str] = &["DataVersion", "xPos", "yPos", "zPos", "Status", "sections", "Heightmaps", "isLightOn"];
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Reads a saved chunk into the chunk model. Chunks that were not fully generated are loaded
+/// read-only as they are, since generating them again would replace what vanilla started.
+pub fn chunk_from_nbt(nbt: &Compound, x: i32, z: i32, min_y: i32, height: u32) -> Result<Chunk, Error> {
+    let data_version = nbt.get("DataVersion").and_then(Tag::as_i64).unwrap_or(0) as i32;
+    if data_version > DATA_VERSION {
+        return Err(invalid(format!("Chunk ({x}, {z}) was saved by a newer version (data version {data_version})")))
+    }
+    if data_version < MIN_DATA_VERSION {
+        return Err(invalid(format!("Chunk ({x}, {z}) is too old (data version {data_version}), open the world in vanilla to upgrade it")))
+    }
+    let status = nbt.get("Status").and_then(Tag::as_str).unwrap_or_default();
+    let unfinished = status != FULL_STATUS && status != "full";
+
+    let plains = registry::network_id(BIOME, "minecraft:plains").unwrap_or_default() as Biome;
+    let min_section = min_y.div_euclid(SECTION_SIZE);
+    let count = height as usize / SECTION_SIZE as usize;
+    let mut sections = vec![ChunkSection::new(plains); count];
+    let mut sky_light = vec![None; count + 2];
+    let mut block_light = vec![None; count + 2];
+    let light_on = nbt.get("isLightOn").and_then(Tag::as_i64).unwrap_or(0) != 0;
+    let mut unknown_states = 0;
+    let mut unknown_biomes = 0;
+
+    for section in nbt.get("sections").and_then(Tag::as_list).unwrap_or_default() {
+        let Some(section) = section.as_compound() else { continue };
+        let y = section.get("Y").and_then(Tag::as_i64).unwrap_or_default() as i32;
+        if let Ok(light_index) = usize::try_from(y - min_section + 1) {
+            if light_on && light_index < count + 2 {
+                sky_light[light_index] = read_light(section.get("SkyLight"));
+                block_light[light_index] = read_light(section.get("BlockLight"));
+            }
+        }
+        let Some(index) = usize::try_from(y - min_section).ok().filter(|index| *index < count) else { continue };
+
+        let blocks = match section.get("block_states").and_then(Tag::as_compound) {
+            Some(states) => {
+                let palette = palette_of(states).iter()
+                    .map(|entry| entry.as_compound().and_then(state_of).unwrap_or_else(|| {
+                        unknown_states += 1;
+                        AIR
+                    }))
+                    .collect();
+                PalettedContainer::from_palette(palette, data_of(states))
+                    .ok_or_else(|| invalid(format!("Chunk ({x}, {z}) has invalid block states in section {y}")))?
+            },
+            None => PalettedContainer::single(AIR),
+        };
+        let biomes = match section.get("biomes").and_then(Tag::as_compound) {
+            Some(biomes) => {
+                let palette = palette_of(biomes).iter()
+                    .map(|entry| entry.as_str()
+                        .and_then(|name| registry::network_id(BIOME, name))
+                        .map_or_else(|| {
+                            unknown_biomes += 1;
+                            plains
+                        }, |id| id as Biome))
+                    .collect();
+                PalettedContainer::from_palette(palette, data_of(biomes))
+                    .ok_or_else(|| invalid(format!("Chunk ({x}, {z}) has invalid biomes in section {y}")))?
+            },
+            None => PalettedContainer::single(plains),
+        };
+        sections[index] = ChunkSection::from_containers(blocks, biomes);
+    }
+    if unknown_states > 0 || unknown_biomes > 0 {
+        tracing::warn!("Replaced {} unknown block states with air and {} unknown biomes with plains in chunk ({}, {}), \
+            it won't be saved", unknown_states, unknown_biomes, x, z);
+    }
+
+    // without upgrading the data like vanilla does, older chunks could only be saved wrong
+    if data_version != DATA_VERSION && !OLD_VERSION_WARNED.swap(true, Ordering::Relaxed) {
+        tracing::warn!("Chunks saved by older versions are loaded read-only, open the world in vanilla {} to upgrade them",
+            MINECRAFT_VERSION);
+    }
+    if unfinished && !UNFINISHED_WARNED.swap(true, Ordering::Relaxed) {
+        tracing::warn!("Chunks that were not fully generated are loaded read-only, open the world in vanilla to finish them");
+    }
+
+    let mut chunk = Chunk::from_sections(x, z, min_y, sections);
+    chunk.read_only = unfinished || data_version != DATA_VERSION || unknown_states > 0 || unknown_biomes > 0;
+    chunk.sky_light = sky_light.into_boxed_slice();
+    chunk.block_light = block_light.into_boxed_slice();
+    chunk.preserved = nbt.iter()
+        .filter(|(name, _)| !MODELED_TAGS.contains(&name.as_str()))
+        .map(|(name, tag)| (name.clone(), tag.clone()))
+        .collect();
+    Ok(chunk)
+}
+
+fn palette_of(container: &Compound) -> &[Tag] {
+    container.get("palette").and_then(Tag::as_list).unwrap_or_default()
+}
+
+fn data_of(container: &Compound) -> Option<Box<[i64]>> {
+    container.get("data").and_then(Tag::as_long_array).map(Into::into)
+}
+
+/// A block state from a palette entry with its name and properties.
+fn state_of(entry: &Compound) -> Option<BlockState> {
+    let block = Block::from_name(entry.get("Name").and_then(Tag::as_str)?)?;
+    let properties = entry.get("Properties").and_then(Tag::as_compound).into_iter()
+        .flat_map(|properties| properties.iter())
+        .filter_map(|(name, value)| Some((name.as_str(), value.as_str()?)));
+    Some(block::state_with_properties(block, properties))
+}
+
+fn state_tag(state: BlockState) -> Compound {
+    let mut tag = Compound::from([("Name".into(), Tag::from(block::name_of(state).unwrap_or("minecraft:air")))]);
+    let properties = block::properties_of(state);
+    if !properties.is_empty() {
+        let properties = properties.into_iter()
+            .map(|(property, value)| (property.name().into(), Tag::from(value)))
+            .collect();
+        tag.insert("Properties".into(), Tag::Compound(properties));
+    }
+    tag
+}
+
+fn read_light(tag: Option<&Tag>) -> Option<LightArray> {
+    match tag {
+        Some(Tag::ByteArray(bytes)) => LightArray::from_bytes(&bytes.iter().map(|b| *b as u8).collect::<Vec<_>>()),
+        _ => None,
+    }
+}
+
+/// Saves a chunk the way vanilla 1.21.4 does, with the tags it did not read kept as they were.
+pub fn chunk_to_nbt(chunk: &Chunk) -> Compound {
+    let min_section = chunk.min_y().div_euclid(SECTION_SIZE);
+    let mut sections = Vec::new();
+    for light_index in 0..chunk.sections().len() + 2 {
+        let y = min_section - 1 + light_index as i32;
+        let mut section = Compound::new();
+        if let Some(blocks) = light_index.checked_sub(1).and_then(|index| chunk.sections().get(index)) {
+            let (palette, data) = blocks.blocks.to_palette();
+            let palette = palette.into_iter()
+                .map(|state| Tag::Compound(state_tag(state)))
+                .collect();
+            section.insert("block_states".into(), container_tag(palette, data));
+
+            let (palette, data) = blocks.biomes.to_palette();
+            let palette = palette.into_iter()
+                .map(|biome| Tag::String(registry::entry_name(BIOME, biome as i32).unwrap_or_else(|| "minecraft:plains".into())))
+                .collect();
+            section.insert("biomes".into(), container_tag(palette, data));
+        }
+        if let Some(light) = &chunk.sky_light[light_index] {
+            section.insert("SkyLight".into(), light_tag(light));
+        }
+        if let Some(light) = &chunk.block_light[light_index] {
+            section.insert("BlockLight".into(), light_tag(light));
+        }
+        if !section.is_empty() {
+            section.insert("Y".into(), Tag::Byte(y as i8));
+            sections.push(Tag::Compound(section));
+        }
+    }
+
+    let mut heightmaps = Compound::new();
+    for kind in HeightmapKind::SENT_TO_CLIENT {
+        heightmaps.insert(kind.name().into(), chunk.heightmap(kind).to_tag());
+    }
+    let light_on = chunk.sky_light.iter().chain(chunk.block_light.iter()).any(Option::is_some);
+
+    let mut nbt = chunk.preserved.clone();
+    nbt.insert("DataVersion".into(), Tag::Int(DATA_VERSION));
+    nbt.insert("xPos".into(), Tag::Int(chunk.x));
+    nbt.insert("yPos".into(), Tag::Int(min_section));
+    nbt.insert("zPos".into(), Tag::Int(chunk.z));
+    nbt.insert("Status".into(), Tag::from(FULL_STATUS));
+    nbt.insert("isLightOn".into(), Tag::Byte(light_on as i8));
+    nbt.insert("Heightmaps".into(), Tag::Compound(heightmaps));
+    nbt.insert("sections".into(), Tag::List(sections));
+    nbt
+}
+
+fn container_tag(palette: Vec<Tag>, data: Option<Box<[i64]>>) -> Tag {
+    let mut container = Compound::from([("palette".into(), Tag::List(palette))]);
+    if let Some(data) = data {
+        container.insert("data".into(), Tag::LongArray(data.into_vec()));
+    }
+    Tag::Compound(container)
+}
+
+fn light_tag(light: &LightArray) -> Tag {
+    Tag::ByteArray(light.bytes().iter().map(|b| *b as i8).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_Y: i32 = -64;
+    const HEIGHT: u32 = 384;
+
+    fn saved_chunk() -> Compound {
+        let plains = registry::network_id(BIOME, "minecraft:plains").unwrap() as Biome;
+        let mut chunk = Chunk::new(1, 2, MIN_Y, HEIGHT, plains);
+        chunk.set_block(16, 0, 32, Block::Stone.default_state());
+        chunk_to_nbt(&chunk)
+    }
+
+    fn section_zero(nbt: &mut Compound) -> &mut Compound {
+        let Some(Tag::List(sections)) = nbt.get_mut("sections") else { panic!("no sections") };
+        sections.iter_mut()
+            .filter_map(|section| match section {
+                Tag::Compound(section) => Some(section),
+                _ => None,
+            })
+            .find(|section| section.get("Y") == Some(&Tag::Byte(0)))
+            .expect("section 0 is saved")
+    }
+
+    #[test]
+    fn current_chunks_round_trip() {
+        let chunk = chunk_from_nbt(&saved_chunk(), 1, 2, MIN_Y, HEIGHT).unwrap();
+        assert!(!chunk.read_only);
+        assert_eq!(chunk.get_block(16, 0, 32), Block::Stone.default_state());
+        assert_eq!(chunk_to_nbt(&chunk), saved_chunk());
+    }
+
+    #[test]
+    fn older_chunks_are_read_only() {
+        let mut nbt = saved_chunk();
+        nbt.insert("DataVersion".into(), Tag::Int(MIN_DATA_VERSION));
+        assert!(chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).unwrap().read_only);
+
+        nbt.insert("DataVersion".into(), Tag::Int(MIN_DATA_VERSION - 1));
+        assert!(chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).is_err());
+        nbt.insert("DataVersion".into(), Tag::Int(DATA_VERSION + 1));
+        assert!(chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).is_err());
+    }
+
+    #[test]
+    fn unknown_blocks_and_biomes_make_chunks_read_only() {
+        let mut nbt = saved_chunk();
+        let Some(Tag::Compound(states)) = section_zero(&mut nbt).get_mut("block_states") else { panic!() };
+        let Some(Tag::List(palette)) = states.get_mut("palette") else { panic!() };
+        palette[1] = Tag::Compound(Compound::from([("Name".into(), Tag::from("minecraft:grass"))]));
+        let chunk = chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).unwrap();
+        assert!(chunk.read_only);
+        assert_eq!(chunk.get_block(16, 0, 32), AIR);
+
+        let mut nbt = saved_chunk();
+        let biomes = Compound::from([("palette".into(), Tag::List(vec![Tag::from("mod:swamp")]))]);
+        section_zero(&mut nbt).insert("biomes".into(), Tag::Compound(biomes));
+        assert!(chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).unwrap().read_only);
+    }
+
+    #[test]
+    fn unfinished_chunks_are_read_only() {
+        let mut nbt = saved_chunk();
+        nbt.insert("Status".into(), Tag::from("minecraft:features"));
+        let chunk = chunk_from_nbt(&nbt, 1, 2, MIN_Y, HEIGHT).unwrap();
+        assert!(chunk.read_only);
+        assert_eq!(chunk.get_block(16, 0, 32), Block::Stone.default_state());
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::{Error, ErrorKind, Read, Write};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh32::xxh32;
+
+/// How chunk payloads are compressed, like vanilla's `region-file-compression`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zlib,
+    None,
+    Lz4,
+}
+
+impl Compression {
+    pub fn id(self) -> u8 {
+        match self {
+            Compression::Gzip => 1,
+            Compression::Zlib => 2,
+            Compression::None => 3,
+            Compression::Lz4 => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Compression::Gzip),
+            2 => Some(Compression::Zlib),
+            3 => Some(Compression::None),
+            4 => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            },
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            },
+            Compression::None => Ok(data.to_vec()),
+            Compression::Lz4 => Ok(lz4_block_compress(data)),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        match self {
+            Compression::Gzip => _ = GzDecoder::new(data).read_to_end(&mut output)?,
+            Compression::Zlib => _ = ZlibDecoder::new(data).read_to_end(&mut output)?,
+            Compression::None => output.extend_from_slice(data),
+            Compression::Lz4 => output = lz4_block_decompress(data)?,
+        }
+        Ok(output)
+    }
+}
+
+// Vanilla writes LZ4 with lz4-java's block stream rather than the standard frame format:
+// blocks of up to 64 KiB, each with a header, ending with an empty block.
+const LZ4_MAGIC: &[u8; 8] = b"LZ4Block";
+const LZ4_HEADER_SIZE: usize = LZ4_MAGIC.len() + 13;
+const LZ4_BLOCK_SIZE: usize = 1 << 16;
+const LZ4_METHOD_RAW: u8 = 0x10;
+const LZ4_METHOD_LZ4: u8 = 0x20;
+/// `log2(LZ4_BLOCK_SIZE) - 10`, stored next to the method.
+const LZ4_LEVEL: u8 = 6;
+const LZ4_CHECKSUM_SEED: u32 = 0x9747B28C;
+
+fn lz4_checksum(data: &[u8]) -> u32 {
+    xxh32(data, LZ4_CHECKSUM_SEED) & 0xFFFFFFF
+}
+
+fn write_lz4_header(output: &mut Vec<u8>, method: u8, compressed: usize, original: usize, checksum: u32) {
+    output.extend_from_slice(LZ4_MAGIC);
+    output.push(method | LZ4_LEVEL);
+    output.extend_from_slice(&(compressed as i32).to_le_bytes());
+    output.extend_from_slice(&(original as i32).to_le_bytes());
+    output.extend_from_slice(&checksum.to_le_bytes());
+}
+
+fn lz4_block_compress(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    for block in data.chunks(LZ4_BLOCK_SIZE) {
+        let compressed = lz4_flex::block::compress(block);
+        let (method, payload) = match compressed.len() < block.len() {
+            true => (LZ4_METHOD_LZ4, compressed.as_slice()),
+            false => (LZ4_METHOD_RAW, block),
+        };
+        write_lz4_header(&mut output, method, payload.len(), block.len(), lz4_checksum(block));
+        output.extend_from_slice(payload);
+    }
+    write_lz4_header(&mut output, LZ4_METHOD_RAW, 0, 0, 0);
+    output
+}
+
+fn lz4_block_decompress(mut data: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("Invalid LZ4 block: {message}"));
+    let mut output = Vec::new();
+    loop {
+        if data.len() < LZ4_HEADER_SIZE || &data[..8] != LZ4_MAGIC {
+            return Err(invalid("bad header"))
+        }
+        let method = data[8] & 0xF0;
+        let int_at = |i: usize| i32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        let (compressed, original) = (int_at(9), int_at(13));
+        let checksum = int_at(17) as u32;
+        let (Ok(compressed), Ok(original)) = (usize::try_from(compressed), usize::try_from(original)) else {
+            return Err(invalid("negative length"))
+        };
+        if original == 0 {
+            return Ok(output)
+        }
+        let payload = data.get(LZ4_HEADER_SIZE..LZ4_HEADER_SIZE + compressed).ok_or_else(|| invalid("truncated"))?;
+        let block = match method {
+            LZ4_METHOD_RAW if compressed == original => payload.to_vec(),
+            LZ4_METHOD_LZ4 => lz4_flex::block::decompress(payload, original).map_err(|e| invalid(&e.to_string()))?,
+            _ => return Err(invalid("unknown method")),
+        };
+        if lz4_checksum(&block) != checksum {
+            return Err(invalid("checksum mismatch"))
+        }
+        output.extend_from_slice(&block);
+        data = &data[LZ4_HEADER_SIZE + compressed..];
+    }
+}
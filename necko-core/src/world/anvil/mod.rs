@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use bytes::BytesMut;
+use necko_protocol::nbt::Tag;
+use crate::world::anvil::chunk::{chunk_from_nbt, chunk_to_nbt};
+use crate::world::anvil::compression::Compression;
+use crate::world::anvil::region::{RegionFile, REGION_SIZE};
+use crate::world::chunk::Chunk;
+
+pub mod chunk;
+pub mod compression;
+pub mod region;
+
+type Regions = Arc<Mutex<HashMap<(i32, i32), Arc<Mutex<RegionFile>>>>>;
+
+/// Chunks of a world in vanilla region files. Files are read and written on the blocking
+/// thread pool, never on the tasks handling connections.
+pub struct AnvilStorage {
+    directory: PathBuf,
+    compression: Compression,
+    regions: Regions,
+}
+
+impl AnvilStorage {
+    /// Storage in a directory of region files like `world/region`.
+    pub fn new(directory: impl Into<PathBuf>, compression: Compression) -> Self {
+        AnvilStorage { directory: directory.into(), compression, regions: Arc::default() }
+    }
+
+    /// Opens the region file with a chunk, creating it only when `create` is set.
+    fn region(directory: &Path, regions: &Regions, x: i32, z: i32, create: bool) -> Result<Option<Arc<Mutex<RegionFile>>>, Error> {
+        let key = (x.div_euclid(REGION_SIZE), z.div_euclid(REGION_SIZE));
+        let mut regions = regions.lock().unwrap();
+        if let Some(region) = regions.get(&key) {
+            return Ok(Some(region.clone()))
+        }
+        let path = directory.join(format!("r.{}.{}.mca", key.0, key.1));
+        if !create && !path.exists() {
+            return Ok(None)
+        }
+        std::fs::create_dir_all(directory)?;
+        let region = Arc::new(Mutex::new(RegionFile::open(&path)?));
+        regions.insert(key, region.clone());
+        Ok(Some(region))
+    }
+
+    /// Loads a chunk, or `None` if it was never saved.
+    pub async fn load_chunk(&self, x: i32, z: i32, min_y: i32, height: u32) -> Result<Option<Chunk>, Error> {
+        let (directory, regions) = (self.directory.clone(), self.regions.clone());
+        tokio::task::spawn_blocking(move || {
+            let Some(region) = Self::region(&directory, &regions, x, z, false)? else { return Ok(None) };
+            let Some(data) = region.lock().unwrap().read(x, z)? else { return Ok(None) };
+            let (_, nbt) = Tag::read_named(&mut data.as_slice())?;
+            let Some(nbt) = nbt.as_compound() else {
+                return Err(Error::new(std::io::ErrorKind::InvalidData, format!("Chunk ({x}, {z}) is not a compound")))
+            };
+            chunk_from_nbt(nbt, x, z, min_y, height).map(Some)
+        }).await?
+    }
+
+    pub async fn save_chunk(&self, chunk: &Chunk) -> Result<(), Error> {
+        let (x, z) = (chunk.x, chunk.z);
+        if chunk.read_only {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, format!("Chunk ({x}, {z}) is read-only")))
+        }
+        let nbt = Tag::Compound(chunk_to_nbt(chunk));
+        let (directory, regions, compression) = (self.directory.clone(), self.regions.clone(), self.compression);
+        tokio::task::spawn_blocking(move || {
+            let mut data = BytesMut::new();
+            nbt.write_named("", &mut data)?;
+            let region = Self::region(&directory, &regions, x, z, true)?.expect("region is created");
+            let mut region = region.lock().unwrap();
+            region.write(x, z, &data, compression)
+        }).await?
+    }
+
+    /// Closes region files, which are opened again when needed.
+    pub fn close_regions(&self) {
+        self.regions.lock().unwrap().clear()
+    }
+}
@@ -0,0 +1,305 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::world::anvil::compression::Compression;
+
+pub const SECTOR_SIZE: usize = 4096;
+/// Chunks per region along each axis.
+pub const REGION_SIZE: i32 = 32;
+const CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+/// Set on the compression byte when the payload is in a separate `.mcc` file.
+const EXTERNAL_FLAG: u8 = 0x80;
+/// Sectors a chunk can take in the region, above which it is stored externally.
+const MAX_SECTORS: usize = 255;
+
+/// An `r.<x>.<z>.mca` file: a header of chunk locations and timestamps followed by chunk
+/// payloads in 4 KiB sectors.
+pub struct RegionFile {
+    file: File,
+    directory: PathBuf,
+    /// Sector offset in the upper 24 bits and sector count in the lower 8, 0 if not saved.
+    locations: [u32; CHUNKS],
+    timestamps: [u32; CHUNKS],
+    used_sectors: Vec<bool>,
+}
+
+impl RegionFile {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let mut header = vec![0; SECTOR_SIZE * 2];
+        let length = file.metadata()?.len() as usize;
+        if length < header.len() {
+            // new or cut off before the header was complete
+            file.set_len(header.len() as u64)?;
+        }
+        file.read_exact(&mut header)?;
+
+        let mut region = RegionFile {
+            file,
+            directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            locations: [0; CHUNKS],
+            timestamps: [0; CHUNKS],
+            used_sectors: vec![true, true],
+        };
+        let sectors = length.max(header.len()).div_ceil(SECTOR_SIZE);
+        region.used_sectors.resize(sectors, false);
+        for i in 0..CHUNKS {
+            let int_at = |offset: usize| u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap());
+            let location = int_at(i * 4);
+            region.timestamps[i] = int_at(SECTOR_SIZE + i * 4);
+            let (start, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+            if location == 0 {
+                continue
+            }
+            if start < 2 || count == 0 || start + count > sectors {
+                tracing::warn!("Ignoring chunk {} with invalid location in {}", i, path.display());
+                continue
+            }
+            region.locations[i] = location;
+            region.used_sectors[start..start + count].fill(true);
+        }
+        Ok(region)
+    }
+
+    fn index(x: i32, z: i32) -> usize {
+        (x.rem_euclid(REGION_SIZE) + z.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+    }
+
+    fn external_path(&self, x: i32, z: i32) -> PathBuf {
+        self.directory.join(format!("c.{x}.{z}.mcc"))
+    }
+
+    pub fn has_chunk(&self, x: i32, z: i32) -> bool {
+        self.locations[Self::index(x, z)] != 0
+    }
+
+    /// Seconds since the epoch when the chunk was last saved.
+    pub fn timestamp(&self, x: i32, z: i32) -> u32 {
+        self.timestamps[Self::index(x, z)]
+    }
+
+    /// The uncompressed NBT of a chunk, from world chunk coordinates.
+    pub fn read(&mut self, x: i32, z: i32) -> Result<Option<Vec<u8>>, Error> {
+        let location = self.locations[Self::index(x, z)];
+        if location == 0 {
+            return Ok(None)
+        }
+        let (start, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+        let mut data = vec![0; count * SECTOR_SIZE];
+        self.file.seek(SeekFrom::Start((start * SECTOR_SIZE) as u64))?;
+        self.file.read_exact(&mut data)?;
+
+        let length = i32::from_be_bytes(data[..4].try_into().unwrap());
+        let Some(length) = usize::try_from(length).ok().filter(|length| (1..=data.len() - 4).contains(length)) else {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Chunk ({x}, {z}) has an invalid length {length}")))
+        };
+        let compression_id = data[4];
+        let compression = Compression::from_id(compression_id & !EXTERNAL_FLAG).ok_or_else(|| Error::new(
+            ErrorKind::InvalidData, format!("Chunk ({x}, {z}) uses unknown compression {compression_id}")))?;
+
+        if compression_id & EXTERNAL_FLAG != 0 {
+            let external = std::fs::read(self.external_path(x, z))?;
+            return compression.decompress(&external).map(Some)
+        }
+        compression.decompress(&data[5..4 + length]).map(Some)
+    }
+
+    /// Saves a chunk's uncompressed NBT. The new data is written before the old sectors are
+    /// freed, so a crash leaves either version.
+    pub fn write(&mut self, x: i32, z: i32, nbt: &[u8], compression: Compression) -> Result<(), Error> {
+        let compressed = compression.compress(nbt)?;
+        let external = compressed.len() + 5 > MAX_SECTORS * SECTOR_SIZE;
+        let mut payload = Vec::with_capacity(compressed.len().min(MAX_SECTORS * SECTOR_SIZE) + 5);
+        if external {
+            std::fs::write(self.external_path(x, z), &compressed)?;
+            payload.extend_from_slice(&1i32.to_be_bytes());
+            payload.push(compression.id() | EXTERNAL_FLAG);
+        } else {
+            payload.extend_from_slice(&(compressed.len() as i32 + 1).to_be_bytes());
+            payload.push(compression.id());
+            payload.extend_from_slice(&compressed);
+        }
+        let count = payload.len().div_ceil(SECTOR_SIZE);
+        payload.resize(count * SECTOR_SIZE, 0);
+
+        let start = self.allocate(count);
+        self.file.seek(SeekFrom::Start((start * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&payload)?;
+
+        let index = Self::index(x, z);
+        let previous = self.locations[index];
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0);
+        self.set_location(index, (start as u32) << 8 | count as u32, timestamp)?;
+        self.free(previous);
+
+        if !external {
+            match std::fs::remove_file(self.external_path(x, z)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn set_location(&mut self, index: usize, location: u32, timestamp: u32) -> Result<(), Error> {
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+        self.file.seek(SeekFrom::Start((index * 4) as u64))?;
+        self.file.write_all(&location.to_be_bytes())?;
+        self.file.seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())
+    }
+
+    /// Finds the first run of free sectors long enough, growing the file if there is none.
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut run = 0;
+        for (sector, used) in self.used_sectors.iter().enumerate() {
+            run = if *used { 0 } else { run + 1 };
+            if run == count {
+                let start = sector + 1 - count;
+                self.used_sectors[start..=sector].fill(true);
+                return start
+            }
+        }
+        let start = self.used_sectors.len() - run;
+        self.used_sectors.resize(start + count, true);
+        self.used_sectors[start..].fill(true);
+        start
+    }
+
+    fn free(&mut self, location: u32) {
+        let (start, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+        if let Some(sectors) = self.used_sectors.get_mut(start..start + count) {
+            sectors.fill(false)
+        }
+    }
+
+    /// Forgets a chunk so it is generated again.
+    pub fn remove(&mut self, x: i32, z: i32) -> Result<(), Error> {
+        let index = Self::index(x, z);
+        let previous = self.locations[index];
+        self.set_location(index, 0, 0)?;
+        self.free(previous);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory per test, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("necko-region-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn region(&self) -> PathBuf {
+            self.0.join("r.0.0.mca")
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sectors(region: &RegionFile, x: i32, z: i32) -> (usize, usize) {
+        let location = region.locations[RegionFile::index(x, z)];
+        ((location >> 8) as usize, (location & 0xFF) as usize)
+    }
+
+    #[test]
+    fn round_trip_and_reopen() {
+        let dir = TempDir::new("round-trip");
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        assert_eq!(region.read(0, 0).unwrap(), None);
+        region.write(0, 0, b"first", Compression::Zlib).unwrap();
+        region.write(-1, 31, b"second", Compression::Lz4).unwrap();
+        assert!(region.timestamp(0, 0) > 0);
+        drop(region);
+
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        assert_eq!(region.read(0, 0).unwrap().as_deref(), Some(&b"first"[..]));
+        assert_eq!(region.read(31, -1).unwrap().as_deref(), Some(&b"second"[..]));
+        assert_eq!(sectors(&region, 0, 0), (2, 1));
+        assert_eq!(sectors(&region, 31, 31), (3, 1));
+
+        region.remove(0, 0).unwrap();
+        assert!(!region.has_chunk(0, 0));
+        assert_eq!(RegionFile::open(&dir.region()).unwrap().read(0, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn freed_sectors_are_reused() {
+        let dir = TempDir::new("reuse");
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        region.write(0, 0, b"a", Compression::None).unwrap();
+        region.write(1, 0, b"b", Compression::None).unwrap();
+
+        // growing a chunk moves it past the end and frees its old sector
+        region.write(0, 0, &vec![1; SECTOR_SIZE], Compression::None).unwrap();
+        assert_eq!(sectors(&region, 0, 0), (4, 2));
+        region.write(2, 0, b"c", Compression::None).unwrap();
+        assert_eq!(sectors(&region, 2, 0), (2, 1));
+
+        // a run too short for the chunk is skipped
+        region.remove(1, 0).unwrap();
+        region.write(3, 0, &vec![2; SECTOR_SIZE], Compression::None).unwrap();
+        assert_eq!(sectors(&region, 3, 0), (6, 2));
+        assert_eq!(region.read(0, 0).unwrap().unwrap(), vec![1; SECTOR_SIZE]);
+    }
+
+    #[test]
+    fn big_chunks_are_stored_externally() {
+        let dir = TempDir::new("external");
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        let big = vec![7; MAX_SECTORS * SECTOR_SIZE];
+        region.write(5, 6, &big, Compression::None).unwrap();
+        let external = dir.0.join("c.5.6.mcc");
+        assert!(external.exists());
+        assert_eq!(sectors(&region, 5, 6).1, 1);
+        assert_eq!(region.read(5, 6).unwrap().unwrap(), big);
+
+        region.write(5, 6, b"small", Compression::None).unwrap();
+        assert!(!external.exists());
+        assert_eq!(region.read(5, 6).unwrap().as_deref(), Some(&b"small"[..]));
+    }
+
+    #[test]
+    fn invalid_locations_are_ignored() {
+        let dir = TempDir::new("invalid");
+        let mut header = vec![0; SECTOR_SIZE * 2];
+        header[..4].copy_from_slice(&(9u32 << 8 | 1).to_be_bytes());
+        header[4..8].copy_from_slice(&(1u32 << 8 | 1).to_be_bytes());
+        std::fs::write(dir.region(), header).unwrap();
+
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        assert!(!region.has_chunk(0, 0));
+        assert!(!region.has_chunk(1, 0));
+        region.write(0, 0, b"fixed", Compression::Zlib).unwrap();
+        assert_eq!(sectors(&region, 0, 0), (2, 1));
+    }
+
+    #[test]
+    fn corrupt_lengths_fail() {
+        let dir = TempDir::new("corrupt");
+        let mut region = RegionFile::open(&dir.region()).unwrap();
+        region.write(0, 0, b"data", Compression::None).unwrap();
+        drop(region);
+
+        let mut file = OpenOptions::new().write(true).open(dir.region()).unwrap();
+        file.seek(SeekFrom::Start(2 * SECTOR_SIZE as u64)).unwrap();
+        file.write_all(&(SECTOR_SIZE as i32).to_be_bytes()).unwrap();
+        drop(file);
+        let error = RegionFile::open(&dir.region()).unwrap().read(0, 0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::OnceLock;
+use crate::server::status::PROTOCOL_VERSION;
+
+/// Global ID of a block state, as sent to clients.
+pub type BlockState = u32;
+
+include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+
+const _: () = assert!(REPORTS_PROTOCOL_VERSION == PROTOCOL_VERSION, "block reports are for another version");
+
+pub const AIR: BlockState = Block::Air.default_state();
+pub const VOID_AIR: BlockState = Block::VoidAir.default_state();
+pub const CAVE_AIR: BlockState = Block::CaveAir.default_state();
+
+struct BlockInfo {
+    block: Block,
+    name: &'static str,
+    first_state: BlockState,
+    default_state: BlockState,
+    /// Properties and their values, in the order vanilla numbers states by.
+    properties: &'static [(Property, &'static [&'static str])],
+}
+
+impl Block {
+    fn info(self) -> &'static BlockInfo {
+        &BLOCKS[self as usize]
+    }
+
+    /// The block name like `minecraft:stone`.
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// Looks up a block like `minecraft:stone`, the namespace can be left out.
+    pub fn from_name(name: &str) -> Option<Block> {
+        match name.strip_prefix("minecraft:") {
+            Some(_) => block_by_name(name),
+            None => block_by_name(&format!("minecraft:{name}")),
+        }
+    }
+
+    pub fn from_state(state: BlockState) -> Option<Block> {
+        STATE_BLOCKS.get(state as usize).map(|&index| BLOCKS[index as usize].block)
+    }
+
+    /// Every state of the block, one for each combination of its properties.
+    pub fn states(self) -> std::ops::Range<BlockState> {
+        let info = self.info();
+        let count: usize = info.properties.iter().map(|(_, values)| values.len()).product();
+        info.first_state..info.first_state + count as BlockState
+    }
+
+    pub fn properties(self) -> impl Iterator<Item = (Property, &'static [&'static str])> {
+        self.info().properties.iter().copied()
+    }
+}
+
+/// The default state of a block like `minecraft:stone`.
+pub fn state_by_name(name: &str) -> Option<BlockState> {
+    Block::from_name(name).map(|block| block.info().default_state)
+}
+
+/// The name of the block of a state.
+pub fn name_of(state: BlockState) -> Option<&'static str> {
+    Block::from_state(state).map(Block::name)
+}
+
+/// Where a property is in the ID of a state: how many states apart its values are and
+/// the index of its value in the state.
+fn property_index(state: BlockState, property: Property) -> Option<(&'static BlockInfo, usize, u32, u32)> {
+    let info = Block::from_state(state)?.info();
+    let offset = state - info.first_state;
+    let mut stride = 1;
+    for (i, (name, values)) in info.properties.iter().enumerate().rev() {
+        let count = values.len() as u32;
+        if *name == property {
+            return Some((info, i, stride, offset / stride % count))
+        }
+        stride *= count
+    }
+    None
+}
+
+/// The value of a property of a state, like `north` for `facing`.
+pub fn property(state: BlockState, property: Property) -> Option<&'static str> {
+    let (info, i, _, index) = property_index(state, property)?;
+    Some(info.properties[i].1[index as usize])
+}
+
+/// The same state with another value for a property, `None` if the block has no such
+/// property or value.
+pub fn with_property(state: BlockState, property: Property, value: &str) -> Option<BlockState> {
+    let (info, i, stride, index) = property_index(state, property)?;
+    let new_index = info.properties[i].1.iter().position(|v| *v == value)? as u32;
+    Some(state - index * stride + new_index * stride)
+}
+
+/// Every property of a state with its value.
+pub fn properties_of(state: BlockState) -> Vec<(Property, &'static str)> {
+    let Some(block) = Block::from_state(state) else { return Vec::new() };
+    block.properties()
+        .filter_map(|(name, _)| Some((name, property(state, name)?)))
+        .collect()
+}
+
+/// A state of a block with the given properties, the default ones for properties that
+/// are missing or unknown.
+pub fn state_with_properties<'a>(block: Block, properties: impl IntoIterator<Item = (&'a str, &'a str)>) -> BlockState {
+    properties.into_iter()
+        .fold(block.info().default_state, |state, (name, value)| Property::from_name(name)
+            .and_then(|property| with_property(state, property, value))
+            .unwrap_or(state))
+}
+
+/// Whether a state counts as empty for section block counts and the world surface.
+pub fn is_air(state: BlockState) -> bool {
+    matches!(state, AIR | VOID_AIR | CAVE_AIR)
+}
+
+/// Whether a state stops movement or holds a fluid, which is what `MOTION_BLOCKING` tracks.
+/// Without block shapes every non-air state does.
+pub fn blocks_motion(state: BlockState) -> bool {
+    !is_air(state)
+}
+
+/// Light level a state gives off.
+pub fn light_emission(state: BlockState) -> u8 {
+    light_properties().get(state as usize).map_or(0, |(emission, _)| *emission)
+}
+
+/// How much light a state takes away when passing through it, on top of the level lost
+/// every block. Like vanilla, full blocks stop light, fluids and leaves dim it and blocks
+/// that don't fill their space let sky light straight down.
+pub fn light_opacity(state: BlockState) -> u8 {
+    light_properties().get(state as usize).map_or(0, |(_, opacity)| *opacity)
+}
+
+/// Emission and opacity of every state. The reports don't have them, so they come from
+/// the blocks vanilla lights up and the families of blocks light passes through.
+fn light_properties() -> &'static [(u8, u8)] {
+    static LIGHT: OnceLock<Box<[(u8, u8)]>> = OnceLock::new();
+    LIGHT.get_or_init(|| (0..STATE_COUNT)
+        .map(|state| {
+            let block = Block::from_state(state).expect("every state has a block");
+            let lit = property(state, Property::Lit) != Some("false");
+            (emission_of(block, lit), opacity_of(block))
+        })
+        .collect())
+}
+
+fn emission_of(block: Block, lit: bool) -> u8 {
+    use Block::*;
+    match block {
+        Lava | Fire | Glowstone | JackOLantern | SeaLantern | Beacon | Conduit | Shroomlight | Lantern
+        | EndPortal | EndGateway | OchreFroglight | VerdantFroglight | PearlescentFroglight => 15,
+        RedstoneLamp | Campfire if lit => 15,
+        Torch | WallTorch | EndRod => 14,
+        Furnace | Smoker | BlastFurnace if lit => 13,
+        NetherPortal => 11,
+        SoulFire | SoulTorch | SoulWallTorch | SoulLantern | CryingObsidian => 10,
+        SoulCampfire if lit => 10,
+        RedstoneTorch | RedstoneWallTorch if lit => 7,
+        EnchantingTable | EnderChest | GlowLichen | RedstoneOre | DeepslateRedstoneOre if lit => 7,
+        AmethystCluster => 5,
+        MagmaBlock => 3,
+        BrewingStand | BrownMushroom | DragonEgg | EndPortalFrame => 1,
+        _ => 0,
+    }
+}
+
+fn opacity_of(block: Block) -> u8 {
+    use Block::*;
+    let name = block.name().strip_prefix("minecraft:").unwrap_or(block.name());
+    const CLEAR_SUFFIXES: &[&str] = &[
+        "_glass", "_pane", "_sapling", "_carpet", "_button", "_pressure_plate", "_sign", "_banner",
+        "torch", "rail", "_tulip", "_mushroom", "_coral", "_coral_fan", "_candle", "_bud",
+    ];
+    match block {
+        Water | Lava | BubbleColumn | Ice | FrostedIce | SlimeBlock | HoneyBlock | Cobweb | Seagrass
+        | TallSeagrass | Kelp | KelpPlant => 1,
+        _ if name.ends_with("_leaves") => 1,
+        TintedGlass => 15,
+        Air | CaveAir | VoidAir | Glass | IronBars | Chain | Barrier | Light | StructureVoid | ShortGrass
+        | Fern | TallGrass | LargeFern | DeadBush | Dandelion | Poppy | BlueOrchid | Allium | AzureBluet
+        | OxeyeDaisy | Cornflower | LilyOfTheValley | WitherRose | Torchflower | Sunflower | Lilac
+        | RoseBush | Peony | RedstoneWire | Ladder | Vine | Lever | Tripwire | TripwireHook | LilyPad
+        | SugarCane | Wheat | Carrots | Potatoes | Beetroots | Snow | Candle | Scaffolding => 0,
+        _ if CLEAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) => 0,
+        _ => 15,
+    }
+}
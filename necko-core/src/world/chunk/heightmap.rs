@@ -0,0 +1,61 @@
+use necko_protocol::nbt::Tag;
+use crate::world::block::{self, BlockState};
+use crate::world::chunk::palette::BitStorage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapKind {
+    /// The highest block that is not air.
+    WorldSurface,
+    /// The highest block that stops movement or holds a fluid, used for rain and snow.
+    MotionBlocking,
+}
+
+impl HeightmapKind {
+    /// Heightmaps clients need, in the order chunks keep them.
+    pub const SENT_TO_CLIENT: [HeightmapKind; 2] = [HeightmapKind::WorldSurface, HeightmapKind::MotionBlocking];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HeightmapKind::WorldSurface => "WORLD_SURFACE",
+            HeightmapKind::MotionBlocking => "MOTION_BLOCKING",
+        }
+    }
+
+    pub fn is_opaque(self, state: BlockState) -> bool {
+        match self {
+            HeightmapKind::WorldSurface => !block::is_air(state),
+            HeightmapKind::MotionBlocking => block::blocks_motion(state),
+        }
+    }
+}
+
+/// For every column of a chunk, one above the highest matching block counted from the
+/// bottom of the world, or 0 if there is none.
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    pub kind: HeightmapKind,
+    storage: BitStorage,
+}
+
+impl Heightmap {
+    pub fn new(kind: HeightmapKind, height: u32) -> Self {
+        let bits = (u32::BITS - height.leading_zeros()) as u8;
+        Heightmap { kind, storage: BitStorage::new(bits, 256) }
+    }
+
+    pub fn get(&self, x: usize, z: usize) -> u32 {
+        self.storage.get(z << 4 | x)
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, height: u32) {
+        self.storage.set(z << 4 | x, height)
+    }
+
+    pub fn data(&self) -> &[i64] {
+        self.storage.data()
+    }
+
+    pub fn to_tag(&self) -> Tag {
+        Tag::LongArray(self.data().to_vec())
+    }
+}
@@ -0,0 +1,38 @@
+/// Bytes of light in a section: a nibble for each of its 4096 blocks.
+pub const LIGHT_ARRAY_SIZE: usize = 2048;
+
+pub const MAX_LIGHT: u8 = 15;
+
+/// Light levels of a section, indexed like its blocks.
+#[derive(Debug, Clone)]
+pub struct LightArray(Box<[u8; LIGHT_ARRAY_SIZE]>);
+
+impl LightArray {
+    pub fn filled(level: u8) -> Self {
+        LightArray(Box::new([level << 4 | level; LIGHT_ARRAY_SIZE]))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(|bytes: [u8; LIGHT_ARRAY_SIZE]| LightArray(Box::new(bytes)))
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        let index = y << 8 | z << 4 | x;
+        (self.0[index >> 1] >> ((index & 1) * 4)) & 0xF
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let index = y << 8 | z << 4 | x;
+        let shift = (index & 1) * 4;
+        let byte = &mut self.0[index >> 1];
+        *byte = (*byte & !(0xF << shift)) | ((level & 0xF) << shift)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
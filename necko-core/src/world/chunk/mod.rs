@@ -0,0 +1,269 @@
+use necko_protocol::buffer::PacketByteBuffer;
+use necko_protocol::nbt::{Compound, Tag};
+use necko_protocol::packets::clientbound::play::level_chunk_with_light::LevelChunkWithLightClientbound;
+use necko_protocol::packets::clientbound::play::light_update::{LightData, LightUpdateClientbound};
+use crate::world::block::{self, BlockState, AIR};
+use crate::world::chunk::heightmap::{Heightmap, HeightmapKind};
+use crate::world::chunk::light::LightArray;
+use crate::world::chunk::palette::{Biomes, BlockStates, PalettedContainer};
+
+pub mod palette;
+pub mod heightmap;
+pub mod light;
+
+/// Network ID of a biome in `minecraft:worldgen/biome`.
+pub type Biome = u32;
+
+pub const SECTION_SIZE: i32 = 16;
+
+/// 16×16×16 blocks of a chunk and their biomes on a 4×4×4 grid.
+#[derive(Debug, Clone)]
+pub struct ChunkSection {
+    /// Blocks that are not air, so empty sections can be skipped.
+    block_count: u16,
+    pub blocks: PalettedContainer<BlockStates>,
+    pub biomes: PalettedContainer<Biomes>,
+}
+
+impl ChunkSection {
+    pub fn new(biome: Biome) -> Self {
+        ChunkSection {
+            block_count: 0,
+            blocks: PalettedContainer::single(AIR),
+            biomes: PalettedContainer::single(biome),
+        }
+    }
+
+    pub fn from_containers(blocks: PalettedContainer<BlockStates>, biomes: PalettedContainer<Biomes>) -> Self {
+        let mut block_count = 0;
+        blocks.count(|state, count| if !block::is_air(state) { block_count += count });
+        ChunkSection { block_count: block_count as u16, blocks, biomes }
+    }
+
+    pub fn block_count(&self) -> u16 {
+        self.block_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.block_count == 0
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockState {
+        self.blocks.get(y << 8 | z << 4 | x)
+    }
+
+    /// Sets a block from section coordinates and returns the previous one.
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+        let previous = self.blocks.set(y << 8 | z << 4 | x, state);
+        match (block::is_air(previous), block::is_air(state)) {
+            (true, false) => self.block_count += 1,
+            (false, true) => self.block_count -= 1,
+            _ => {}
+        }
+        previous
+    }
+
+    /// Biome from coordinates on the 4×4×4 grid.
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> Biome {
+        self.biomes.get(y << 4 | z << 2 | x)
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: Biome) {
+        self.biomes.set(y << 4 | z << 2 | x, biome);
+    }
+
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_i16(self.block_count as i16);
+        self.blocks.write(buffer);
+        self.biomes.write(buffer);
+    }
+}
+
+/// A 16-block wide column of sections from the bottom of its world to the top.
+/// Blocks are addressed with world coordinates, of which only the low 4 bits of `x` and `z`
+/// are used.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub x: i32,
+    pub z: i32,
+    min_y: i32,
+    sections: Box<[ChunkSection]>,
+    heightmaps: [Heightmap; 2],
+    /// Light of every section plus the ones right below and above the world,
+    /// `None` where it is unknown.
+    pub sky_light: Box<[Option<LightArray>]>,
+    pub block_light: Box<[Option<LightArray>]>,
+    /// Saved data the server does not use, like entities and ticks, kept when saving again.
+    pub preserved: Compound,
+    /// Changed since it was loaded or last saved.
+    pub dirty: bool,
+    /// Saving it would lose data, like blocks the server doesn't know, so it never is.
+    pub read_only: bool,
+}
+
+impl Chunk {
+    /// An empty chunk of `height` blocks starting at `min_y`, both multiples of 16.
+    pub fn new(x: i32, z: i32, min_y: i32, height: u32, biome: Biome) -> Self {
+        let sections = (0..height as i32 / SECTION_SIZE).map(|_| ChunkSection::new(biome)).collect();
+        Self::from_sections(x, z, min_y, sections)
+    }
+
+    pub fn from_sections(x: i32, z: i32, min_y: i32, sections: Vec<ChunkSection>) -> Self {
+        let height = sections.len() as u32 * SECTION_SIZE as u32;
+        let light_sections = sections.len() + 2;
+        let mut chunk = Chunk {
+            x,
+            z,
+            min_y,
+            sections: sections.into_boxed_slice(),
+            heightmaps: HeightmapKind::SENT_TO_CLIENT.map(|kind| Heightmap::new(kind, height)),
+            sky_light: vec![None; light_sections].into_boxed_slice(),
+            block_light: vec![None; light_sections].into_boxed_slice(),
+            preserved: Compound::new(),
+            dirty: false,
+            read_only: false,
+        };
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    pub fn height(&self) -> i32 {
+        self.sections.len() as i32 * SECTION_SIZE
+    }
+
+    pub fn sections(&self) -> &[ChunkSection] {
+        &self.sections
+    }
+
+    /// The section at a world Y, if it is inside the world.
+    pub fn section(&self, y: i32) -> Option<&ChunkSection> {
+        self.sections.get(usize::try_from((y - self.min_y).div_euclid(SECTION_SIZE)).ok()?)
+    }
+
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockState {
+        match self.section(y) {
+            Some(section) => section.get_block((x & 15) as usize, (y & 15) as usize, (z & 15) as usize),
+            None => AIR,
+        }
+    }
+
+    /// Sets a block and returns the previous one. Blocks outside the world stay air.
+    /// Heightmaps follow, but light has to be updated separately.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState) -> BlockState {
+        let Ok(index) = usize::try_from((y - self.min_y).div_euclid(SECTION_SIZE)) else { return AIR };
+        let Some(section) = self.sections.get_mut(index) else { return AIR };
+        let (x, z) = ((x & 15) as usize, (z & 15) as usize);
+        let previous = section.set_block(x, (y & 15) as usize, z, state);
+        self.dirty = true;
+
+        let height = (y - self.min_y) as u32 + 1;
+        for i in 0..self.heightmaps.len() {
+            let heightmap = &self.heightmaps[i];
+            let kind = heightmap.kind;
+            if kind.is_opaque(state) && height > heightmap.get(x, z) {
+                self.heightmaps[i].set(x, z, height)
+            } else if !kind.is_opaque(state) && height == heightmap.get(x, z) {
+                let top = self.find_top(kind, x, z, y - 1);
+                self.heightmaps[i].set(x, z, top)
+            }
+        }
+        previous
+    }
+
+    /// One above the highest block matching `kind` at or below `y` counted from the bottom,
+    /// or 0 if there is none.
+    fn find_top(&self, kind: HeightmapKind, x: usize, z: usize, y: i32) -> u32 {
+        (self.min_y..=y).rev()
+            .find(|y| kind.is_opaque(self.get_block(x as i32, *y, z as i32)))
+            .map(|y| (y - self.min_y) as u32 + 1)
+            .unwrap_or(0)
+    }
+
+    pub fn recalculate_heightmaps(&mut self) {
+        let top = self.min_y + self.height() - 1;
+        for i in 0..self.heightmaps.len() {
+            let kind = self.heightmaps[i].kind;
+            for z in 0..16 {
+                for x in 0..16 {
+                    let height = self.find_top(kind, x, z, top);
+                    self.heightmaps[i].set(x, z, height)
+                }
+            }
+        }
+    }
+
+    pub fn heightmap(&self, kind: HeightmapKind) -> &Heightmap {
+        self.heightmaps.iter().find(|heightmap| heightmap.kind == kind).expect("heightmap is kept")
+    }
+
+    /// World Y of the highest block matching `kind` in a column, below the world if none does.
+    pub fn top_y(&self, kind: HeightmapKind, x: i32, z: i32) -> i32 {
+        self.min_y + self.heightmap(kind).get((x & 15) as usize, (z & 15) as usize) as i32 - 1
+    }
+
+    pub fn get_biome(&self, x: i32, y: i32, z: i32) -> Biome {
+        let y = y.clamp(self.min_y, self.min_y + self.height() - 1);
+        let section = &self.sections[((y - self.min_y) / SECTION_SIZE) as usize];
+        section.get_biome(((x & 15) >> 2) as usize, ((y & 15) >> 2) as usize, ((z & 15) >> 2) as usize)
+    }
+
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome: Biome) {
+        let Ok(index) = usize::try_from((y - self.min_y).div_euclid(SECTION_SIZE)) else { return };
+        if let Some(section) = self.sections.get_mut(index) {
+            section.set_biome(((x & 15) >> 2) as usize, ((y & 15) >> 2) as usize, ((z & 15) >> 2) as usize, biome)
+        }
+    }
+
+    pub fn to_packet(&self) -> LevelChunkWithLightClientbound {
+        let mut heightmaps = Compound::new();
+        for heightmap in &self.heightmaps {
+            heightmaps.insert(heightmap.kind.name().into(), heightmap.to_tag());
+        }
+        let mut data = PacketByteBuffer::empty();
+        for section in self.sections.iter() {
+            section.write(&mut data);
+        }
+
+        LevelChunkWithLightClientbound {
+            x: self.x,
+            z: self.z,
+            heightmaps: Tag::Compound(heightmaps),
+            data: data.buffer().split().freeze(),
+            block_entities: Vec::new(),
+            light: self.light_data(),
+        }
+    }
+
+    pub fn light_packet(&self) -> LightUpdateClientbound {
+        LightUpdateClientbound::new(self.x, self.z, self.light_data())
+    }
+
+    /// Known light of every section, leaving out unknown sections and sending all-dark ones
+    /// only as a bit.
+    pub fn light_data(&self) -> LightData {
+        let mut light = LightData::default();
+        (light.sky_y_mask, light.empty_sky_y_mask, light.sky_updates) = Self::light_masks(&self.sky_light);
+        (light.block_y_mask, light.empty_block_y_mask, light.block_updates) = Self::light_masks(&self.block_light);
+        light
+    }
+
+    fn light_masks(sections: &[Option<LightArray>]) -> (Vec<i64>, Vec<i64>, Vec<Vec<u8>>) {
+        let longs = sections.len().div_ceil(64);
+        let (mut mask, mut empty_mask, mut updates) = (vec![0; longs], vec![0; longs], Vec::new());
+        for (i, light) in sections.iter().enumerate() {
+            match light {
+                Some(light) if light.is_empty() => empty_mask[i / 64] |= 1 << (i % 64),
+                Some(light) => {
+                    mask[i / 64] |= 1 << (i % 64);
+                    updates.push(light.bytes().to_vec())
+                },
+                None => {}
+            }
+        }
+        (mask, empty_mask, updates)
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use necko_protocol::buffer::PacketByteBuffer;
+use necko_protocol::types::{VarInt, VarIntType};
+
+/// Fixed-width values packed into longs, none of them spanning two longs.
+#[derive(Debug, Clone)]
+pub struct BitStorage {
+    bits: u8,
+    data: Box<[i64]>,
+}
+
+impl BitStorage {
+    pub fn new(bits: u8, size: usize) -> Self {
+        BitStorage { bits, data: vec![0; Self::longs(bits, size)].into_boxed_slice() }
+    }
+
+    /// Wraps packed data read from somewhere else, if it is long enough for `size` values.
+    pub fn from_data(bits: u8, size: usize, data: Box<[i64]>) -> Option<Self> {
+        (bits > 0 && bits <= 32 && data.len() == Self::longs(bits, size))
+            .then_some(BitStorage { bits, data })
+    }
+
+    fn longs(bits: u8, size: usize) -> usize {
+        size.div_ceil(64 / bits as usize)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn data(&self) -> &[i64] {
+        &self.data
+    }
+
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        ((self.data[index / per_long] as u64 >> shift) & self.mask()) as u32
+    }
+
+    pub fn set(&mut self, index: usize, value: u32) {
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        let mask = self.mask();
+        let long = &mut self.data[index / per_long];
+        *long = ((*long as u64 & !(mask << shift)) | ((value as u64 & mask) << shift)) as i64
+    }
+}
+
+/// How many values a container holds and how wide its palettes get.
+pub trait ContainerKind {
+    const SIZE: usize;
+    const MIN_BITS: u8;
+    const MAX_INDIRECT_BITS: u8;
+    /// Bits of a value in the global registry, used once the palette would be too big.
+    const DIRECT_BITS: u8;
+}
+
+/// The 16³ block states of a section.
+#[derive(Debug, Clone)]
+pub struct BlockStates;
+
+impl ContainerKind for BlockStates {
+    const SIZE: usize = 4096;
+    const MIN_BITS: u8 = 4;
+    const MAX_INDIRECT_BITS: u8 = 8;
+    const DIRECT_BITS: u8 = 15;
+}
+
+/// The 4³ biomes of a section.
+#[derive(Debug, Clone)]
+pub struct Biomes;
+
+impl ContainerKind for Biomes {
+    const SIZE: usize = 64;
+    const MIN_BITS: u8 = 1;
+    const MAX_INDIRECT_BITS: u8 = 3;
+    const DIRECT_BITS: u8 = 7;
+}
+
+#[derive(Debug, Clone)]
+enum Storage {
+    /// Every value is the same, so nothing is stored per entry.
+    Single(u32),
+    Indirect { palette: Vec<u32>, storage: BitStorage },
+    Direct(BitStorage),
+}
+
+/// Values of a section indexed by `y << 8 | z << 4 | x` (or the same on the 4³ biome grid),
+/// stored with as few bits as the number of different values allows.
+#[derive(Debug, Clone)]
+pub struct PalettedContainer<K> {
+    storage: Storage,
+    kind: PhantomData<K>,
+}
+
+impl<K: ContainerKind> PalettedContainer<K> {
+    pub fn single(value: u32) -> Self {
+        PalettedContainer { storage: Storage::Single(value), kind: PhantomData }
+    }
+
+    /// Builds a container from a palette and indices packed like on disk, failing if
+    /// the data does not fit the palette.
+    pub fn from_palette(palette: Vec<u32>, data: Option<Box<[i64]>>) -> Option<Self> {
+        let Some(data) = data.filter(|_| palette.len() > 1) else {
+            return palette.first().map(|value| Self::single(*value))
+        };
+        let bits = serialized_bits::<K>(palette.len());
+        let source = BitStorage::from_data(bits, K::SIZE, data)?;
+        if bits <= K::MAX_INDIRECT_BITS {
+            if (0..K::SIZE).any(|index| source.get(index) as usize >= palette.len()) {
+                return None
+            }
+            return Some(PalettedContainer { storage: Storage::Indirect { palette, storage: source }, kind: PhantomData })
+        }
+        let mut storage = BitStorage::new(K::DIRECT_BITS, K::SIZE);
+        for index in 0..K::SIZE {
+            storage.set(index, *palette.get(source.get(index) as usize)?);
+        }
+        Some(PalettedContainer { storage: Storage::Direct(storage), kind: PhantomData })
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        match &self.storage {
+            Storage::Single(value) => *value,
+            Storage::Indirect { palette, storage } => palette[storage.get(index) as usize],
+            Storage::Direct(storage) => storage.get(index),
+        }
+    }
+
+    /// Sets a value and returns the previous one, growing the palette when needed.
+    pub fn set(&mut self, index: usize, value: u32) -> u32 {
+        match &mut self.storage {
+            Storage::Single(current) if *current == value => value,
+            Storage::Single(current) => {
+                let previous = *current;
+                let mut storage = BitStorage::new(K::MIN_BITS, K::SIZE);
+                storage.set(index, 1);
+                self.storage = Storage::Indirect { palette: vec![previous, value], storage };
+                previous
+            },
+            Storage::Indirect { palette, storage } => {
+                let previous = palette[storage.get(index) as usize];
+                let id = match palette.iter().position(|v| *v == value) {
+                    Some(id) => id,
+                    None => {
+                        palette.push(value);
+                        palette.len() - 1
+                    },
+                };
+                if bits_for(palette.len()) <= storage.bits() {
+                    storage.set(index, id as u32);
+                } else {
+                    self.grow();
+                    self.set(index, value);
+                }
+                previous
+            },
+            Storage::Direct(storage) => {
+                let previous = storage.get(index);
+                storage.set(index, value);
+                previous
+            },
+        }
+    }
+
+    /// Repacks an indirect container whose palette outgrew its bits, going direct past the
+    /// biggest indirect palette.
+    fn grow(&mut self) {
+        let Storage::Indirect { palette, storage } = std::mem::replace(&mut self.storage, Storage::Single(0)) else {
+            return
+        };
+        let bits = bits_for(palette.len());
+        if bits > K::MAX_INDIRECT_BITS {
+            let mut direct = BitStorage::new(K::DIRECT_BITS, K::SIZE);
+            for index in 0..K::SIZE {
+                direct.set(index, palette[storage.get(index) as usize]);
+            }
+            self.storage = Storage::Direct(direct);
+        } else {
+            let mut grown = BitStorage::new(bits, K::SIZE);
+            for index in 0..K::SIZE {
+                grown.set(index, storage.get(index));
+            }
+            self.storage = Storage::Indirect { palette, storage: grown };
+        }
+    }
+
+    pub fn fill(&mut self, value: u32) {
+        self.storage = Storage::Single(value)
+    }
+
+    /// Calls `f` with every value and how many entries have it.
+    pub fn count(&self, mut f: impl FnMut(u32, usize)) {
+        match &self.storage {
+            Storage::Single(value) => f(*value, K::SIZE),
+            Storage::Indirect { palette, storage } => {
+                let mut counts = vec![0; palette.len()];
+                for index in 0..K::SIZE {
+                    counts[storage.get(index) as usize] += 1;
+                }
+                for (value, count) in palette.iter().zip(counts).filter(|(_, count)| *count > 0) {
+                    f(*value, count)
+                }
+            },
+            Storage::Direct(storage) => (0..K::SIZE).for_each(|index| f(storage.get(index), 1)),
+        }
+    }
+
+    /// The palette and indices packed like on disk. A single value has no data.
+    pub fn to_palette(&self) -> (Vec<u32>, Option<Box<[i64]>>) {
+        match &self.storage {
+            Storage::Single(value) => (vec![*value], None),
+            Storage::Indirect { palette, storage } => (palette.clone(), Some(storage.data().into())),
+            Storage::Direct(direct) => {
+                let mut palette = Vec::new();
+                let mut ids = HashMap::new();
+                for index in 0..K::SIZE {
+                    ids.entry(direct.get(index)).or_insert_with_key(|value| {
+                        palette.push(*value);
+                        palette.len() - 1
+                    });
+                }
+                let mut storage = BitStorage::new(serialized_bits::<K>(palette.len()), K::SIZE);
+                for index in 0..K::SIZE {
+                    storage.set(index, ids[&direct.get(index)] as u32);
+                }
+                (palette, Some(storage.data().into()))
+            },
+        }
+    }
+
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        match &self.storage {
+            Storage::Single(value) => {
+                buffer.write_u8(0);
+                buffer.write_var_int(&VarInt(*value as VarIntType));
+                buffer.write_long_array(&[]);
+            },
+            Storage::Indirect { palette, storage } => {
+                buffer.write_u8(storage.bits());
+                buffer.write_var_int(&VarInt(palette.len() as VarIntType));
+                for value in palette {
+                    buffer.write_var_int(&VarInt(*value as VarIntType));
+                }
+                buffer.write_long_array(storage.data());
+            },
+            Storage::Direct(storage) => {
+                buffer.write_u8(storage.bits());
+                buffer.write_long_array(storage.data());
+            },
+        }
+    }
+}
+
+/// Bits needed to index a palette of `len` values.
+fn bits_for(len: usize) -> u8 {
+    (usize::BITS - (len.max(2) - 1).leading_zeros()) as u8
+}
+
+/// Bits of the indices saved with a palette: the network width while it would be indirect,
+/// otherwise just enough for the palette.
+fn serialized_bits<K: ContainerKind>(len: usize) -> u8 {
+    match bits_for(len) {
+        bits if bits <= K::MAX_INDIRECT_BITS => bits.max(K::MIN_BITS),
+        bits => bits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_storage_keeps_values_in_one_long() {
+        let mut storage = BitStorage::new(5, 4096);
+        assert_eq!(storage.data().len(), 4096usize.div_ceil(12));
+        for index in 0..4096 {
+            storage.set(index, index as u32 % 32);
+        }
+        assert!((0..4096).all(|index| storage.get(index) == index as u32 % 32));
+        assert_eq!(storage.data()[0] as u64 >> 60, 0, "the 4 bits left over in a long stay unused");
+
+        storage.set(1, 0xFF);
+        assert_eq!(storage.get(1), 0x1F);
+        assert_eq!((storage.get(0), storage.get(2)), (0, 2));
+    }
+
+    #[test]
+    fn bit_storage_checks_data_length() {
+        assert!(BitStorage::from_data(4, 4096, vec![0; 256].into()).is_some());
+        assert!(BitStorage::from_data(4, 4096, vec![0; 255].into()).is_none());
+        assert!(BitStorage::from_data(0, 4096, vec![].into()).is_none());
+        assert!(BitStorage::from_data(33, 64, vec![0; 64].into()).is_none());
+    }
+
+    #[test]
+    fn container_grows_from_single_to_direct() {
+        let mut container = PalettedContainer::<BlockStates>::single(0);
+        assert_eq!(container.set(10, 0), 0);
+        assert!(matches!(container.storage, Storage::Single(0)));
+
+        assert_eq!(container.set(10, 7), 0);
+        assert!(matches!(&container.storage, Storage::Indirect { storage, .. } if storage.bits() == 4));
+
+        for value in 1..=16 {
+            container.set(value as usize, value);
+        }
+        assert!(matches!(&container.storage, Storage::Indirect { storage, .. } if storage.bits() == 5));
+
+        for value in 17..=300 {
+            container.set(value as usize, value);
+        }
+        assert!(matches!(&container.storage, Storage::Direct(storage) if storage.bits() == 15));
+        assert_eq!(container.get(10), 10);
+        assert_eq!(container.get(300), 300);
+        assert_eq!(container.get(4095), 0);
+    }
+
+    #[test]
+    fn container_counts() {
+        let mut container = PalettedContainer::<Biomes>::single(3);
+        container.set(0, 5);
+        container.set(1, 5);
+        let mut counts = vec![];
+        container.count(|value, count| counts.push((value, count)));
+        assert_eq!(counts, [(3, 62), (5, 2)]);
+    }
+
+    #[test]
+    fn palette_round_trip() {
+        let mut container = PalettedContainer::<BlockStates>::single(1);
+        assert_eq!(container.to_palette(), (vec![1], None));
+
+        for index in 0..4096 {
+            container.set(index, (index % 3) as u32 + 1);
+        }
+        let (palette, data) = container.to_palette();
+        assert_eq!(data.as_ref().unwrap().len(), 256, "indirect palettes are saved with at least 4 bits");
+        let loaded = PalettedContainer::<BlockStates>::from_palette(palette, data).unwrap();
+        assert!((0..4096).all(|index| loaded.get(index) == container.get(index)));
+
+        for index in 0..400 {
+            container.set(index, index as u32 + 100);
+        }
+        let (palette, data) = container.to_palette();
+        assert_eq!(palette.len(), 403);
+        assert_eq!(data.as_ref().unwrap().len(), 4096usize.div_ceil(7), "direct palettes are saved with 9 bits");
+        let loaded = PalettedContainer::<BlockStates>::from_palette(palette, data).unwrap();
+        assert!((0..4096).all(|index| loaded.get(index) == container.get(index)));
+    }
+
+    #[test]
+    fn from_palette_rejects_bad_indices() {
+        let mut storage = BitStorage::new(4, 4096);
+        storage.set(5, 2);
+        assert!(PalettedContainer::<BlockStates>::from_palette(vec![1, 2], Some(storage.data().into())).is_none());
+        assert!(PalettedContainer::<BlockStates>::from_palette(vec![1, 2], Some(vec![0; 3].into())).is_none());
+        assert!(PalettedContainer::<BlockStates>::from_palette(vec![], None).is_none());
+    }
+
+    #[test]
+    fn network_format() {
+        let mut buffer = PacketByteBuffer::empty();
+        PalettedContainer::<Biomes>::single(4).write(&mut buffer);
+        assert_eq!(&buffer.buffer()[..], &[0, 4, 0]);
+
+        let mut container = PalettedContainer::<Biomes>::single(4);
+        container.set(0, 9);
+        let mut buffer = PacketByteBuffer::empty();
+        container.write(&mut buffer);
+        assert_eq!(&buffer.buffer()[..4], &[1, 2, 4, 9]);
+        assert_eq!(buffer.len(), 4 + 1 + 8);
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::world::block::{self, BlockState};
+use crate::world::chunk::{Biome, Chunk};
+use crate::world::generator::{biome_id, ChunkGenerator};
+use crate::world::SpawnPoint;
+
+/// Layers from the bottom of the world up, written like vanilla's superflat presets:
+/// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct FlatLayers(pub Vec<(BlockState, u32)>);
+
+impl TryFrom<String> for FlatLayers {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut layers = Vec::new();
+        for layer in value.split(',').map(str::trim).filter(|layer| !layer.is_empty()) {
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => (count.trim().parse::<u32>()
+                    .map_err(|_| format!("Invalid layer height in {layer:?}"))?, name.trim()),
+                None => (1, layer),
+            };
+            let name = match name.contains(':') {
+                true => name.to_string(),
+                false => format!("minecraft:{name}"),
+            };
+            let state = block::state_by_name(&name).ok_or_else(|| format!("Unknown block {name} in flat layers"))?;
+            layers.push((state, count));
+        }
+        Ok(FlatLayers(layers))
+    }
+}
+
+impl Display for FlatLayers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, (state, count)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if *count > 1 {
+                write!(f, "{count}*")?;
+            }
+            f.write_str(block::name_of(*state).unwrap_or("minecraft:air"))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<FlatLayers> for String {
+    fn from(value: FlatLayers) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FlatConfig {
+    pub layers: FlatLayers,
+    pub biome: String,
+}
+
+impl Default for FlatConfig {
+    fn default() -> Self {
+        FlatConfig {
+            layers: "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block".to_string().try_into()
+                .expect("default layers are valid"),
+            biome: "minecraft:plains".into(),
+        }
+    }
+}
+
+/// The same layers of blocks everywhere, like vanilla's superflat worlds.
+pub struct FlatGenerator {
+    layers: Vec<(BlockState, u32)>,
+    biome: Biome,
+}
+
+impl FlatGenerator {
+    pub fn new(config: &FlatConfig) -> Self {
+        FlatGenerator { layers: config.layers.0.clone(), biome: biome_id(&config.biome) }
+    }
+
+    fn thickness(&self) -> i32 {
+        self.layers.iter().map(|(_, count)| *count as i32).sum()
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, x: i32, z: i32, min_y: i32, height: u32) -> Chunk {
+        let mut chunk = Chunk::new(x, z, min_y, height, self.biome);
+        let mut y = min_y;
+        for (state, count) in &self.layers {
+            for _ in 0..*count {
+                if y >= min_y + height as i32 {
+                    return chunk
+                }
+                if !block::is_air(*state) {
+                    for block_x in 0..16 {
+                        for block_z in 0..16 {
+                            chunk.set_block(block_x, y, block_z, *state);
+                        }
+                    }
+                }
+                y += 1;
+            }
+        }
+        chunk
+    }
+
+    fn spawn_point(&self, min_y: i32) -> SpawnPoint {
+        SpawnPoint { position: (0, min_y + self.thickness(), 0), angle: 0.0 }
+    }
+
+    fn is_flat(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use crate::registry::{self, BIOME};
+use crate::world::chunk::{Biome, Chunk};
+use crate::world::generator::flat::{FlatConfig, FlatGenerator};
+use crate::world::generator::noise::{NoiseConfig, NoiseGenerator};
+use crate::world::generator::void::{VoidConfig, VoidGenerator};
+use crate::world::SpawnPoint;
+
+pub mod flat;
+pub mod noise;
+pub mod pool;
+pub mod void;
+
+/// Creates the chunks of a world that were never saved.
+pub trait ChunkGenerator: Send + Sync {
+    /// The chunk at chunk coordinates `x` and `z`, from `min_y` up `height` blocks.
+    fn generate(&self, x: i32, z: i32, min_y: i32, height: u32) -> Chunk;
+
+    /// Where players first spawn in a world of this generator.
+    fn spawn_point(&self, _min_y: i32) -> SpawnPoint {
+        SpawnPoint::default()
+    }
+
+    /// Flat worlds show the horizon at the bottom of the world instead of at sea level.
+    fn is_flat(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GeneratorConfig {
+    Flat(FlatConfig),
+    Noise(NoiseConfig),
+    Void(VoidConfig),
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig::Flat(FlatConfig::default())
+    }
+}
+
+impl GeneratorConfig {
+    pub fn build(&self, seed: i64) -> Box<dyn ChunkGenerator> {
+        match self {
+            GeneratorConfig::Flat(config) => Box::new(FlatGenerator::new(config)),
+            GeneratorConfig::Noise(config) => Box::new(NoiseGenerator::new(config, seed)),
+            GeneratorConfig::Void(config) => Box::new(VoidGenerator::new(config)),
+        }
+    }
+}
+
+/// Network ID of a biome from the config, falling back to plains.
+pub fn biome_id(name: &str) -> Biome {
+    let name = match name.contains(':') {
+        true => name.to_string(),
+        false => format!("minecraft:{name}"),
+    };
+    registry::network_id(BIOME, &name)
+        .or_else(|| {
+            tracing::warn!("Unknown biome {}, using minecraft:plains", name);
+            registry::network_id(BIOME, "minecraft:plains")
+        })
+        .unwrap_or_default() as Biome
+}
@@ -0,0 +1,95 @@
+use serde::Deserialize;
+use crate::world::chunk::Biome;
+use crate::world::generator::biome_id;
+use crate::world::generator::noise::data;
+
+/// Temperature, humidity, continentalness, erosion, depth and weirdness at a position,
+/// the order vanilla compares them in.
+pub type Climate = [f64; 6];
+
+/// Range of every climate dimension an entry covers.
+type ClimateRanges = [(f64, f64); 6];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Parameter {
+    Point(f64),
+    Range([f64; 2]),
+}
+
+impl Parameter {
+    fn range(&self) -> (f64, f64) {
+        match self {
+            Parameter::Point(value) => (*value, *value),
+            Parameter::Range([min, max]) => (*min, *max),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Parameters {
+    temperature: Parameter,
+    humidity: Parameter,
+    continentalness: Parameter,
+    erosion: Parameter,
+    depth: Parameter,
+    weirdness: Parameter,
+    #[serde(default)]
+    offset: f64,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    biome: String,
+    parameters: Parameters,
+}
+
+#[derive(Deserialize)]
+struct ParameterList {
+    biomes: Vec<Entry>,
+}
+
+/// Picks biomes like vanilla's multi-noise biome source: the entry whose climate ranges are
+/// closest to the sampled climate wins, earlier entries winning ties.
+pub struct BiomeSource {
+    entries: Vec<(Biome, ClimateRanges, f64)>,
+}
+
+impl BiomeSource {
+    pub fn new(name: &str) -> Result<Self, String> {
+        let json = data::biome_parameter_list(name).ok_or_else(|| format!("Unknown biome parameter list {name}"))?;
+        let list: ParameterList = serde_json::from_str(json).map_err(|e| format!("Invalid biome parameter list {name}: {e}"))?;
+        if list.biomes.is_empty() {
+            return Err(format!("Biome parameter list {name} is empty"))
+        }
+        let entries = list.biomes.iter()
+            .map(|entry| {
+                let p = &entry.parameters;
+                let ranges = [&p.temperature, &p.humidity, &p.continentalness, &p.erosion, &p.depth, &p.weirdness]
+                    .map(Parameter::range);
+                (biome_id(&entry.biome), ranges, p.offset)
+            })
+            .collect();
+        Ok(BiomeSource { entries })
+    }
+
+    pub fn biome(&self, climate: &Climate) -> Biome {
+        let fitness = |ranges: &ClimateRanges, offset: f64| {
+            ranges.iter().zip(climate)
+                .map(|((min, max), value)| match *value {
+                    value if value < *min => (min - value).powi(2),
+                    value if value > *max => (value - max).powi(2),
+                    _ => 0.0,
+                })
+                .sum::<f64>() + offset * offset
+        };
+        let mut best = (self.entries[0].0, f64::INFINITY);
+        for (biome, ranges, offset) in &self.entries {
+            let distance = fitness(ranges, *offset);
+            if distance < best.1 {
+                best = (*biome, distance);
+            }
+        }
+        best.0
+    }
+}
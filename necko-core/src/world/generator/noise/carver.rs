@@ -0,0 +1,219 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use crate::world::block::{self, BlockState, AIR};
+use crate::world::chunk::Chunk;
+use crate::world::generator::noise::random::LegacyRandom;
+
+/// Chunks around a chunk whose caves can reach into it.
+const RANGE: i32 = 8;
+/// Chance of a chunk starting caves.
+const PROBABILITY: f32 = 0.15;
+/// Most steps a tunnel takes.
+const MAX_LENGTH: i32 = (4 * 2 - 1) * 16;
+const MAX_Y: i32 = 180;
+
+/// Vanilla's `minecraft:cave` carver: random tunnels and rooms started in nearby chunks,
+/// dug through the terrain after its surface is laid.
+pub struct CaveCarver {
+    seed: i64,
+    replaceable: Vec<BlockState>,
+    water: BlockState,
+    lava: BlockState,
+    grass: BlockState,
+    dirt: BlockState,
+}
+
+/// A cave ellipsoid in progress, with everything that stays the same while it is carved.
+struct Carving<'a> {
+    chunk: &'a mut Chunk,
+    horizontal_multiplier: f64,
+    vertical_multiplier: f64,
+    /// Relative height under which the cave keeps a floor.
+    floor_level: f64,
+}
+
+impl CaveCarver {
+    pub fn new(seed: i64) -> Self {
+        let state = |name: &str| block::state_by_name(name).expect("carver blocks are known");
+        let replaceable = ["stone", "deepslate", "dirt", "grass_block", "sand", "red_sand", "sandstone", "gravel",
+            "terracotta", "snow_block"];
+        CaveCarver {
+            seed,
+            replaceable: replaceable.iter().map(|name| state(&format!("minecraft:{name}"))).collect(),
+            water: state("minecraft:water"),
+            lava: state("minecraft:lava"),
+            grass: state("minecraft:grass_block"),
+            dirt: state("minecraft:dirt"),
+        }
+    }
+
+    pub fn carve(&self, chunk: &mut Chunk) {
+        let mut random = LegacyRandom::new(0);
+        let (chunk_x, chunk_z) = (chunk.x, chunk.z);
+        for x in chunk_x - RANGE..=chunk_x + RANGE {
+            for z in chunk_z - RANGE..=chunk_z + RANGE {
+                random.set_large_feature_seed(self.seed, x, z);
+                if random.next_float() <= PROBABILITY {
+                    self.carve_from(chunk, &mut random, x, z);
+                }
+            }
+        }
+    }
+
+    /// Carves the caves started in chunk `x`, `z` that reach into `chunk`.
+    fn carve_from(&self, chunk: &mut Chunk, random: &mut LegacyRandom, x: i32, z: i32) {
+        let min_y = chunk.min_y() + 8;
+        let bound = random.next_int(15) + 1;
+        let bound = random.next_int(bound) + 1;
+        let count = random.next_int(bound);
+        for _ in 0..count {
+            let start_x = (x * 16 + random.next_int(16)) as f64;
+            let start_y = (random.next_int(MAX_Y - min_y + 1) + min_y) as f64;
+            let start_z = (z * 16 + random.next_int(16)) as f64;
+            let mut carving = Carving {
+                chunk: &mut *chunk,
+                horizontal_multiplier: (random.next_float() * 0.7 + 0.7) as f64,
+                vertical_multiplier: (random.next_float() * 0.5 + 0.8) as f64,
+                floor_level: (random.next_float() * 0.6 - 1.0) as f64,
+            };
+
+            let mut tunnels = 1;
+            if random.next_int(4) == 0 {
+                let y_scale = (random.next_float() * 0.8 + 0.1) as f64;
+                let radius = 1.5 + (1.0 + random.next_float() * 6.0) as f64;
+                self.carve_ellipsoid(&mut carving, start_x + 1.0, start_y, start_z, radius, radius * y_scale);
+                tunnels += random.next_int(4);
+            }
+            for _ in 0..tunnels {
+                let yaw = random.next_float() * TAU;
+                let pitch = (random.next_float() - 0.5) / 4.0;
+                let thickness = Self::thickness(random);
+                let length = MAX_LENGTH - random.next_int(MAX_LENGTH / 4);
+                let tunnel = Tunnel { x: start_x, y: start_y, z: start_z, thickness, yaw, pitch, step: 0, length, y_scale: 1.0 };
+                self.carve_tunnel(&mut carving, random.next_long(), tunnel);
+            }
+        }
+    }
+
+    fn thickness(random: &mut LegacyRandom) -> f32 {
+        let mut thickness = random.next_float() * 2.0 + random.next_float();
+        if random.next_int(10) == 0 {
+            thickness *= random.next_float() * random.next_float() * 3.0 + 1.0;
+        }
+        thickness
+    }
+
+    fn carve_tunnel(&self, carving: &mut Carving, seed: i64, mut tunnel: Tunnel) {
+        let mut random = LegacyRandom::new(seed);
+        let branch_at = random.next_int(tunnel.length / 2) + tunnel.length / 4;
+        let steep = random.next_int(6) == 0;
+        let (mut yaw_change, mut pitch_change) = (0f32, 0f32);
+        for step in tunnel.step..tunnel.length {
+            let radius = 1.5 + ((PI * step as f32 / tunnel.length as f32).sin() * tunnel.thickness) as f64;
+            let height = radius * tunnel.y_scale;
+            let horizontal = tunnel.pitch.cos();
+            tunnel.x += (tunnel.yaw.cos() * horizontal) as f64;
+            tunnel.y += tunnel.pitch.sin() as f64;
+            tunnel.z += (tunnel.yaw.sin() * horizontal) as f64;
+            tunnel.pitch *= if steep { 0.92 } else { 0.7 };
+            tunnel.pitch += pitch_change * 0.1;
+            tunnel.yaw += yaw_change * 0.1;
+            pitch_change *= 0.9;
+            yaw_change *= 0.75;
+            pitch_change += (random.next_float() - random.next_float()) * random.next_float() * 2.0;
+            yaw_change += (random.next_float() - random.next_float()) * random.next_float() * 4.0;
+
+            if step == branch_at && tunnel.thickness > 1.0 {
+                for side in [-FRAC_PI_2, FRAC_PI_2] {
+                    let seed = random.next_long();
+                    let branch = Tunnel {
+                        thickness: random.next_float() * 0.5 + 0.5,
+                        yaw: tunnel.yaw + side,
+                        pitch: tunnel.pitch / 3.0,
+                        step,
+                        y_scale: 1.0,
+                        ..tunnel
+                    };
+                    self.carve_tunnel(carving, seed, branch);
+                }
+                return
+            }
+            if random.next_int(4) != 0 {
+                if !tunnel.can_reach(carving.chunk, step) {
+                    return
+                }
+                let (horizontal, vertical) = (radius * carving.horizontal_multiplier, height * carving.vertical_multiplier);
+                self.carve_ellipsoid(carving, tunnel.x, tunnel.y, tunnel.z, horizontal, vertical);
+            }
+        }
+    }
+
+    fn carve_ellipsoid(&self, carving: &mut Carving, x: f64, y: f64, z: f64, horizontal: f64, vertical: f64) {
+        let chunk = &mut *carving.chunk;
+        let (min_x, min_z) = (chunk.x * 16, chunk.z * 16);
+        let reach = 16.0 + horizontal * 2.0;
+        if (x - (min_x + 8) as f64).abs() > reach || (z - (min_z + 8) as f64).abs() > reach {
+            return
+        }
+        let from_x = ((x - horizontal).floor() as i32 - min_x - 1).max(0);
+        let to_x = ((x + horizontal).floor() as i32 - min_x).min(15);
+        let from_y = ((y - vertical).floor() as i32 - 1).max(chunk.min_y() + 1);
+        let to_y = ((y + vertical).floor() as i32 + 1).min(chunk.min_y() + chunk.height() - 1 - 7);
+        let from_z = ((z - horizontal).floor() as i32 - min_z - 1).max(0);
+        let to_z = ((z + horizontal).floor() as i32 - min_z).min(15);
+
+        for block_x in min_x + from_x..=min_x + to_x {
+            let dx = (block_x as f64 + 0.5 - x) / horizontal;
+            for block_z in min_z + from_z..=min_z + to_z {
+                let dz = (block_z as f64 + 0.5 - z) / horizontal;
+                if dx * dx + dz * dz >= 1.0 {
+                    continue
+                }
+                for block_y in (from_y + 1..=to_y).rev() {
+                    let dy = (block_y as f64 - 0.5 - y) / vertical;
+                    if dy <= carving.floor_level || dx * dx + dy * dy + dz * dz >= 1.0 {
+                        continue
+                    }
+                    self.carve_block(chunk, block_x, block_y, block_z);
+                }
+            }
+        }
+    }
+
+    fn carve_block(&self, chunk: &mut Chunk, x: i32, y: i32, z: i32) {
+        let current = chunk.get_block(x, y, z);
+        if !self.replaceable.contains(&current) || chunk.get_block(x, y + 1, z) == self.water {
+            return
+        }
+        let lava_level = chunk.min_y() + 8;
+        chunk.set_block(x, y, z, if y <= lava_level { self.lava } else { AIR });
+        // keep the surface green when a cave opens up under it
+        if current == self.grass && chunk.get_block(x, y - 1, z) == self.dirt {
+            chunk.set_block(x, y - 1, z, self.grass);
+        }
+    }
+}
+
+/// The head of a tunnel as it winds through the ground.
+#[derive(Clone, Copy)]
+struct Tunnel {
+    x: f64,
+    y: f64,
+    z: f64,
+    thickness: f32,
+    yaw: f32,
+    pitch: f32,
+    step: i32,
+    length: i32,
+    y_scale: f64,
+}
+
+impl Tunnel {
+    /// Whether the rest of the tunnel could still get to the chunk being carved.
+    fn can_reach(&self, chunk: &Chunk, step: i32) -> bool {
+        let dx = self.x - (chunk.x * 16 + 8) as f64;
+        let dz = self.z - (chunk.z * 16 + 8) as f64;
+        let remaining = (self.length - step) as f64;
+        let reach = self.thickness as f64 + 2.0 + 16.0;
+        dx * dx + dz * dz - remaining * remaining <= reach * reach
+    }
+}
@@ -0,0 +1,69 @@
+//! Worldgen data pack files built into the server. Noises and the density functions shared
+//! with vanilla are copied from it, the `necko` ones describe this generator's own terrain
+//! shape and biome layout in the same format.
+
+macro_rules! data_files {
+    ($kind:literal: $($namespace:literal $path:literal),* $(,)?) => {
+        &[$((
+            concat!($namespace, ":", $path),
+            include_str!(concat!("../../../../data/", $namespace, "/worldgen/", $kind, "/", $path, ".json")),
+        )),*]
+    };
+}
+
+const NOISES: &[(&str, &str)] = data_files!("noise":
+    "minecraft" "cave_cheese",
+    "minecraft" "continentalness",
+    "minecraft" "erosion",
+    "minecraft" "jagged",
+    "minecraft" "offset",
+    "minecraft" "ridge",
+    "minecraft" "surface",
+    "minecraft" "temperature",
+    "minecraft" "vegetation",
+    "necko" "terrain_detail",
+);
+
+const DENSITY_FUNCTIONS: &[(&str, &str)] = data_files!("density_function":
+    "minecraft" "shift_x",
+    "minecraft" "shift_z",
+    "minecraft" "overworld/continents",
+    "minecraft" "overworld/erosion",
+    "minecraft" "overworld/ridges",
+    "minecraft" "overworld/ridges_folded",
+    "necko" "overworld/base_3d_noise",
+    "necko" "overworld/caves/cheese",
+    "necko" "overworld/depth",
+    "necko" "overworld/factor",
+    "necko" "overworld/jaggedness",
+    "necko" "overworld/offset",
+    "necko" "overworld/sloped_cheese",
+);
+
+const NOISE_SETTINGS: &[(&str, &str)] = data_files!("noise_settings":
+    "necko" "overworld",
+);
+
+const BIOME_PARAMETER_LISTS: &[(&str, &str)] = data_files!("multi_noise_biome_source_parameter_list":
+    "necko" "overworld",
+);
+
+fn find(files: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+    files.iter().find(|(n, _)| *n == name).map(|(_, json)| *json)
+}
+
+pub fn noise(name: &str) -> Option<&'static str> {
+    find(NOISES, name)
+}
+
+pub fn density_function(name: &str) -> Option<&'static str> {
+    find(DENSITY_FUNCTIONS, name)
+}
+
+pub fn noise_settings(name: &str) -> Option<&'static str> {
+    find(NOISE_SETTINGS, name)
+}
+
+pub fn biome_parameter_list(name: &str) -> Option<&'static str> {
+    find(BIOME_PARAMETER_LISTS, name)
+}
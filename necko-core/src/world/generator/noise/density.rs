@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::{Map, Value};
+use crate::world::generator::noise::data;
+use crate::world::generator::noise::perlin::{NoiseParameters, NormalNoise};
+use crate::world::generator::noise::random::PositionalRandom;
+
+/// A cached value and the position it was computed at.
+type CacheEntry = ((i32, i32, i32), f64);
+
+/// A block position being sampled, with the values cached by the functions at it.
+pub struct DensityContext {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    cache: Vec<Option<CacheEntry>>,
+}
+
+impl DensityContext {
+    /// A context for functions from a parser that handed out `slots` cache slots.
+    pub fn new(slots: usize) -> Self {
+        DensityContext { x: 0, y: 0, z: 0, cache: vec![None; slots] }
+    }
+
+    pub fn at(&mut self, x: i32, y: i32, z: i32) -> &mut Self {
+        (self.x, self.y, self.z) = (x, y, z);
+        self
+    }
+}
+
+/// A `worldgen/density_function`: a value for every block position, built from noise, splines
+/// and arithmetic. Positive final density means solid terrain.
+#[derive(Debug)]
+pub enum DensityFunction {
+    Constant(f64),
+    Add(Box<DensityFunction>, Box<DensityFunction>),
+    Mul(Box<DensityFunction>, Box<DensityFunction>),
+    Min(Box<DensityFunction>, Box<DensityFunction>),
+    Max(Box<DensityFunction>, Box<DensityFunction>),
+    Abs(Box<DensityFunction>),
+    Square(Box<DensityFunction>),
+    Cube(Box<DensityFunction>),
+    HalfNegative(Box<DensityFunction>),
+    QuarterNegative(Box<DensityFunction>),
+    Squeeze(Box<DensityFunction>),
+    Clamp { input: Box<DensityFunction>, min: f64, max: f64 },
+    Noise { noise: Arc<NormalNoise>, xz_scale: f64, y_scale: f64 },
+    ShiftedNoise {
+        shift_x: Box<DensityFunction>,
+        shift_y: Box<DensityFunction>,
+        shift_z: Box<DensityFunction>,
+        xz_scale: f64,
+        y_scale: f64,
+        noise: Arc<NormalNoise>,
+    },
+    ShiftA(Arc<NormalNoise>),
+    ShiftB(Arc<NormalNoise>),
+    YClampedGradient { from_y: f64, to_y: f64, from_value: f64, to_value: f64 },
+    RangeChoice {
+        input: Box<DensityFunction>,
+        min_inclusive: f64,
+        max_exclusive: f64,
+        when_in_range: Box<DensityFunction>,
+        when_out_of_range: Box<DensityFunction>,
+    },
+    Spline(Box<Spline>),
+    /// `flat_cache` and `cache_2d`: computed once per column.
+    Cache2d { slot: usize, argument: Box<DensityFunction> },
+    /// `cache_once` and `cache_all_in_cell`: computed once per position.
+    CacheOnce { slot: usize, argument: Box<DensityFunction> },
+    /// A function registered by name, shared by everything referencing it.
+    Reference(Arc<DensityFunction>),
+}
+
+impl DensityFunction {
+    pub fn compute(&self, ctx: &mut DensityContext) -> f64 {
+        use DensityFunction::*;
+        match self {
+            Constant(value) => *value,
+            Add(a, b) => a.compute(ctx) + b.compute(ctx),
+            Mul(a, b) => match a.compute(ctx) {
+                0.0 => 0.0,
+                value => value * b.compute(ctx),
+            },
+            Min(a, b) => a.compute(ctx).min(b.compute(ctx)),
+            Max(a, b) => a.compute(ctx).max(b.compute(ctx)),
+            Abs(argument) => argument.compute(ctx).abs(),
+            Square(argument) => argument.compute(ctx).powi(2),
+            Cube(argument) => argument.compute(ctx).powi(3),
+            HalfNegative(argument) => match argument.compute(ctx) {
+                value if value > 0.0 => value,
+                value => value * 0.5,
+            },
+            QuarterNegative(argument) => match argument.compute(ctx) {
+                value if value > 0.0 => value,
+                value => value * 0.25,
+            },
+            Squeeze(argument) => {
+                let value = argument.compute(ctx).clamp(-1.0, 1.0);
+                value / 2.0 - value * value * value / 24.0
+            },
+            Clamp { input, min, max } => input.compute(ctx).clamp(*min, *max),
+            Noise { noise, xz_scale, y_scale } => noise.value(
+                ctx.x as f64 * xz_scale, ctx.y as f64 * y_scale, ctx.z as f64 * xz_scale),
+            ShiftedNoise { shift_x, shift_y, shift_z, xz_scale, y_scale, noise } => {
+                let x = ctx.x as f64 * xz_scale + shift_x.compute(ctx);
+                let y = ctx.y as f64 * y_scale + shift_y.compute(ctx);
+                let z = ctx.z as f64 * xz_scale + shift_z.compute(ctx);
+                noise.value(x, y, z)
+            },
+            ShiftA(noise) => noise.value(ctx.x as f64 * 0.25, 0.0, ctx.z as f64 * 0.25) * 4.0,
+            ShiftB(noise) => noise.value(ctx.z as f64 * 0.25, ctx.x as f64 * 0.25, 0.0) * 4.0,
+            YClampedGradient { from_y, to_y, from_value, to_value } => {
+                let t = ((ctx.y as f64 - from_y) / (to_y - from_y)).clamp(0.0, 1.0);
+                from_value + t * (to_value - from_value)
+            },
+            RangeChoice { input, min_inclusive, max_exclusive, when_in_range, when_out_of_range } => {
+                match input.compute(ctx) {
+                    value if value >= *min_inclusive && value < *max_exclusive => when_in_range.compute(ctx),
+                    _ => when_out_of_range.compute(ctx),
+                }
+            },
+            Spline(spline) => spline.compute(ctx) as f64,
+            Cache2d { slot, argument } => Self::cached(ctx, *slot, (ctx.x, 0, ctx.z), argument),
+            CacheOnce { slot, argument } => Self::cached(ctx, *slot, (ctx.x, ctx.y, ctx.z), argument),
+            Reference(function) => function.compute(ctx),
+        }
+    }
+
+    fn cached(ctx: &mut DensityContext, slot: usize, key: (i32, i32, i32), argument: &DensityFunction) -> f64 {
+        match ctx.cache[slot] {
+            Some((cached, value)) if cached == key => value,
+            _ => {
+                let value = argument.compute(ctx);
+                ctx.cache[slot] = Some((key, value));
+                value
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+enum SplineValue {
+    Constant(f32),
+    Spline(Spline),
+}
+
+/// A cubic Hermite spline over a coordinate function, whose points can be splines themselves.
+#[derive(Debug)]
+pub struct Spline {
+    coordinate: DensityFunction,
+    locations: Vec<f32>,
+    values: Vec<SplineValue>,
+    derivatives: Vec<f32>,
+}
+
+impl Spline {
+    pub fn compute(&self, ctx: &mut DensityContext) -> f32 {
+        let at = self.coordinate.compute(ctx) as f32;
+        let last = self.locations.len() - 1;
+        let extend = |i: usize, value: f32| match self.derivatives[i] {
+            0.0 => value,
+            derivative => value + derivative * (at - self.locations[i]),
+        };
+        let start = match self.locations.partition_point(|location| *location <= at) {
+            0 => return extend(0, self.value(0, ctx)),
+            i if i - 1 == last => return extend(last, self.value(last, ctx)),
+            i => i - 1,
+        };
+
+        let (from, to) = (self.locations[start], self.locations[start + 1]);
+        let t = (at - from) / (to - from);
+        let (a, b) = (self.value(start, ctx), self.value(start + 1, ctx));
+        let p = self.derivatives[start] * (to - from) - (b - a);
+        let q = -self.derivatives[start + 1] * (to - from) + (b - a);
+        a + t * (b - a) + t * (1.0 - t) * (p + t * (q - p))
+    }
+
+    fn value(&self, i: usize, ctx: &mut DensityContext) -> f32 {
+        match &self.values[i] {
+            SplineValue::Constant(value) => *value,
+            SplineValue::Spline(spline) => spline.compute(ctx),
+        }
+    }
+}
+
+/// Builds density functions from data pack JSON, creating the noises they sample from
+/// the world seed.
+pub struct DensityParser {
+    random: PositionalRandom,
+    noises: HashMap<String, Arc<NormalNoise>>,
+    functions: HashMap<String, Arc<DensityFunction>>,
+    slots: usize,
+}
+
+impl DensityParser {
+    pub fn new(random: PositionalRandom) -> Self {
+        DensityParser { random, noises: HashMap::new(), functions: HashMap::new(), slots: 0 }
+    }
+
+    /// Cache slots handed out so far, which a [`DensityContext`] needs room for.
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    pub fn noise(&mut self, name: &str) -> Result<Arc<NormalNoise>, String> {
+        if let Some(noise) = self.noises.get(name) {
+            return Ok(noise.clone())
+        }
+        let json = data::noise(name).ok_or_else(|| format!("Unknown noise {name}"))?;
+        let parameters: NoiseParameters = serde_json::from_str(json).map_err(|e| format!("Invalid noise {name}: {e}"))?;
+        let noise = Arc::new(NormalNoise::new(&mut self.random.from_hash_of(name), &parameters));
+        self.noises.insert(name.into(), noise.clone());
+        Ok(noise)
+    }
+
+    pub fn function(&mut self, name: &str) -> Result<Arc<DensityFunction>, String> {
+        if let Some(function) = self.functions.get(name) {
+            return Ok(function.clone())
+        }
+        let json = data::density_function(name).ok_or_else(|| format!("Unknown density function {name}"))?;
+        let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid density function {name}: {e}"))?;
+        let function = Arc::new(self.parse(&value)?);
+        self.functions.insert(name.into(), function.clone());
+        Ok(function)
+    }
+
+    pub fn parse(&mut self, value: &Value) -> Result<DensityFunction, String> {
+        use DensityFunction::*;
+        let object = match value {
+            Value::Number(number) => return Ok(Constant(number.as_f64().unwrap_or_default())),
+            Value::String(name) => return self.function(name).map(Reference),
+            Value::Object(object) => object,
+            _ => return Err(format!("Invalid density function {value}")),
+        };
+        let kind = string(object, "type")?;
+        let boxed = |parser: &mut Self, key: &str| -> Result<Box<DensityFunction>, String> {
+            parser.parse(field(object, key)?).map(Box::new)
+        };
+        let function = match kind.strip_prefix("minecraft:").unwrap_or(kind) {
+            "constant" => Constant(number(object, "argument")?),
+            "add" => Add(boxed(self, "argument1")?, boxed(self, "argument2")?),
+            "mul" => Mul(boxed(self, "argument1")?, boxed(self, "argument2")?),
+            "min" => Min(boxed(self, "argument1")?, boxed(self, "argument2")?),
+            "max" => Max(boxed(self, "argument1")?, boxed(self, "argument2")?),
+            "abs" => Abs(boxed(self, "argument")?),
+            "square" => Square(boxed(self, "argument")?),
+            "cube" => Cube(boxed(self, "argument")?),
+            "half_negative" => HalfNegative(boxed(self, "argument")?),
+            "quarter_negative" => QuarterNegative(boxed(self, "argument")?),
+            "squeeze" => Squeeze(boxed(self, "argument")?),
+            "clamp" => Clamp { input: boxed(self, "input")?, min: number(object, "min")?, max: number(object, "max")? },
+            "noise" => Noise {
+                noise: self.noise(string(object, "noise")?)?,
+                xz_scale: number(object, "xz_scale")?,
+                y_scale: number(object, "y_scale")?,
+            },
+            "shifted_noise" => ShiftedNoise {
+                shift_x: boxed(self, "shift_x")?,
+                shift_y: boxed(self, "shift_y")?,
+                shift_z: boxed(self, "shift_z")?,
+                xz_scale: number(object, "xz_scale")?,
+                y_scale: number(object, "y_scale")?,
+                noise: self.noise(string(object, "noise")?)?,
+            },
+            "shift_a" => ShiftA(self.noise(string(object, "argument")?)?),
+            "shift_b" => ShiftB(self.noise(string(object, "argument")?)?),
+            "y_clamped_gradient" => YClampedGradient {
+                from_y: number(object, "from_y")?,
+                to_y: number(object, "to_y")?,
+                from_value: number(object, "from_value")?,
+                to_value: number(object, "to_value")?,
+            },
+            "range_choice" => RangeChoice {
+                input: boxed(self, "input")?,
+                min_inclusive: number(object, "min_inclusive")?,
+                max_exclusive: number(object, "max_exclusive")?,
+                when_in_range: boxed(self, "when_in_range")?,
+                when_out_of_range: boxed(self, "when_out_of_range")?,
+            },
+            "spline" => Spline(Box::new(self.parse_spline(field(object, "spline")?)?)),
+            "flat_cache" | "cache_2d" => Cache2d { slot: self.next_slot(), argument: boxed(self, "argument")? },
+            "cache_once" | "cache_all_in_cell" => CacheOnce { slot: self.next_slot(), argument: boxed(self, "argument")? },
+            // whole cells are interpolated by the generator and there are no old chunks to blend with
+            "interpolated" | "blend_density" => return self.parse(field(object, "argument")?),
+            "blend_alpha" => Constant(1.0),
+            "blend_offset" => Constant(0.0),
+            kind => return Err(format!("Unsupported density function type {kind}")),
+        };
+        Ok(function)
+    }
+
+    fn parse_spline(&mut self, value: &Value) -> Result<Spline, String> {
+        let Value::Object(object) = value else { return Err(format!("Invalid spline {value}")) };
+        let coordinate = self.parse(field(object, "coordinate")?)?;
+        let points = field(object, "points")?.as_array().ok_or("Spline points must be a list")?;
+        if points.is_empty() {
+            return Err("Spline has no points".into())
+        }
+        let mut spline = Spline { coordinate, locations: Vec::new(), values: Vec::new(), derivatives: Vec::new() };
+        for point in points {
+            let Value::Object(point) = point else { return Err(format!("Invalid spline point {point}")) };
+            spline.locations.push(number(point, "location")? as f32);
+            spline.derivatives.push(number(point, "derivative")? as f32);
+            spline.values.push(match field(point, "value")? {
+                Value::Number(value) => SplineValue::Constant(value.as_f64().unwrap_or_default() as f32),
+                value => SplineValue::Spline(self.parse_spline(value)?),
+            });
+        }
+        Ok(spline)
+    }
+
+    fn next_slot(&mut self) -> usize {
+        self.slots += 1;
+        self.slots - 1
+    }
+}
+
+fn field<'a>(object: &'a Map<String, Value>, key: &str) -> Result<&'a Value, String> {
+    object.get(key).ok_or_else(|| format!("Missing {key} in {}", Value::Object(object.clone())))
+}
+
+fn number(object: &Map<String, Value>, key: &str) -> Result<f64, String> {
+    field(object, key)?.as_f64().ok_or_else(|| format!("{key} must be a number"))
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &str) -> Result<&'a str, String> {
+    field(object, key)?.as_str().ok_or_else(|| format!("{key} must be a string"))
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::world::block::{self, Block, BlockState};
+use crate::world::chunk::heightmap::HeightmapKind;
+use crate::world::chunk::{Chunk, SECTION_SIZE};
+use crate::world::generator::noise::biome::{BiomeSource, Climate};
+use crate::world::generator::noise::carver::CaveCarver;
+use crate::world::generator::noise::density::{DensityContext, DensityFunction, DensityParser};
+use crate::world::generator::noise::random::Xoroshiro;
+use crate::world::generator::noise::surface::SurfaceRules;
+use crate::world::generator::ChunkGenerator;
+use crate::world::SpawnPoint;
+
+pub mod biome;
+pub mod carver;
+pub mod data;
+pub mod density;
+pub mod perlin;
+pub mod random;
+pub mod surface;
+
+const SETTINGS: &str = "necko:overworld";
+/// Fluids below this height are lava, like in vanilla worlds without aquifers.
+const LAVA_LEVEL: i32 = -54;
+/// Chunks around the origin searched for dry land to spawn on.
+const SPAWN_SEARCH_RADIUS: i32 = 16;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct NoiseConfig {}
+
+#[derive(Deserialize)]
+struct NamedState {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Properties", default)]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct NoiseShape {
+    min_y: i32,
+    height: u32,
+    size_horizontal: i32,
+    size_vertical: i32,
+}
+
+/// A `worldgen/noise_settings` entry, with the parts this generator uses.
+#[derive(Deserialize)]
+struct NoiseSettings {
+    sea_level: i32,
+    default_block: NamedState,
+    default_fluid: NamedState,
+    noise: NoiseShape,
+    noise_router: Map<String, Value>,
+}
+
+/// Terrain shaped by density functions from built-in worldgen data, with multi-noise
+/// biomes, surface rules and carved caves. Built from the world seed, so the same seed
+/// always gives the same terrain.
+pub struct NoiseGenerator {
+    shape: NoiseShape,
+    sea_level: i32,
+    default_block: BlockState,
+    default_fluid: BlockState,
+    lava: BlockState,
+    final_density: DensityFunction,
+    climate: [DensityFunction; 6],
+    cache_slots: usize,
+    biomes: BiomeSource,
+    surface: SurfaceRules,
+    carver: CaveCarver,
+}
+
+impl NoiseGenerator {
+    pub fn new(_config: &NoiseConfig, seed: i64) -> Self {
+        Self::with_settings(SETTINGS, seed).expect("Could not load the built-in worldgen data")
+    }
+
+    fn with_settings(name: &str, seed: i64) -> Result<Self, String> {
+        let json = data::noise_settings(name).ok_or_else(|| format!("Unknown noise settings {name}"))?;
+        let settings: NoiseSettings = serde_json::from_str(json).map_err(|e| format!("Invalid noise settings {name}: {e}"))?;
+        let state = |named: &NamedState| Block::from_name(&named.name)
+            .map(|block| block::state_with_properties(block, named.properties.iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))))
+            .ok_or_else(|| format!("Unknown block {} in noise settings {name}", named.name));
+
+        let random = Xoroshiro::new(seed).fork_positional();
+        let mut parser = DensityParser::new(random);
+        let mut router = |key: &str| {
+            let function = settings.noise_router.get(key).ok_or_else(|| format!("Noise router of {name} has no {key}"))?;
+            parser.parse(function)
+        };
+        let climate = [
+            router("temperature")?,
+            router("vegetation")?,
+            router("continents")?,
+            router("erosion")?,
+            router("depth")?,
+            router("ridges")?,
+        ];
+        let final_density = router("final_density")?;
+
+        Ok(NoiseGenerator {
+            sea_level: settings.sea_level,
+            default_block: state(&settings.default_block)?,
+            default_fluid: state(&settings.default_fluid)?,
+            lava: block::state_by_name("minecraft:lava").expect("lava is known"),
+            final_density,
+            climate,
+            biomes: BiomeSource::new(name)?,
+            surface: SurfaceRules::new(&mut parser, random, settings.noise.min_y, settings.sea_level)?,
+            carver: CaveCarver::new(seed),
+            cache_slots: parser.slots(),
+            shape: settings.noise,
+        })
+    }
+
+    fn fill_biomes(&self, chunk: &mut Chunk, ctx: &mut DensityContext) {
+        let (min_x, min_z) = (chunk.x * 16, chunk.z * 16);
+        for quart_x in 0..4 {
+            for quart_z in 0..4 {
+                let (x, z) = (min_x + quart_x * 4, min_z + quart_z * 4);
+                for y in (chunk.min_y()..chunk.min_y() + chunk.height()).step_by(4) {
+                    ctx.at(x, y, z);
+                    let climate: Climate = self.climate.each_ref().map(|function| function.compute(ctx));
+                    chunk.set_biome(x, y, z, self.biomes.biome(&climate));
+                }
+            }
+        }
+    }
+
+    /// Samples the final density at the corners of every cell and fills blocks by
+    /// interpolating between them: stone where it is positive, fluid or air elsewhere.
+    fn fill_noise(&self, chunk: &mut Chunk, ctx: &mut DensityContext) {
+        let (cell_width, cell_height) = (self.shape.size_horizontal * 4, self.shape.size_vertical * 4);
+        let cells_xz = SECTION_SIZE / cell_width;
+        let cells_y = chunk.height() / cell_height;
+        let (min_x, min_y, min_z) = (chunk.x * 16, chunk.min_y(), chunk.z * 16);
+        let index = |x: i32, y: i32, z: i32| ((x * (cells_xz + 1) + z) * (cells_y + 1) + y) as usize;
+
+        let mut corners = vec![0.0; index(cells_xz + 1, 0, 0)];
+        for x in 0..=cells_xz {
+            for z in 0..=cells_xz {
+                for y in 0..=cells_y {
+                    ctx.at(min_x + x * cell_width, min_y + y * cell_height, min_z + z * cell_width);
+                    corners[index(x, y, z)] = self.final_density.compute(ctx);
+                }
+            }
+        }
+
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        for local_x in 0..SECTION_SIZE {
+            let (cell_x, tx) = (local_x / cell_width, (local_x % cell_width) as f64 / cell_width as f64);
+            for local_z in 0..SECTION_SIZE {
+                let (cell_z, tz) = (local_z / cell_width, (local_z % cell_width) as f64 / cell_width as f64);
+                for local_y in 0..chunk.height() {
+                    let (cell_y, ty) = (local_y / cell_height, (local_y % cell_height) as f64 / cell_height as f64);
+                    let corner = |dx: i32, dy: i32, dz: i32| corners[index(cell_x + dx, cell_y + dy, cell_z + dz)];
+                    let density = lerp(tz,
+                        lerp(ty, lerp(tx, corner(0, 0, 0), corner(1, 0, 0)), lerp(tx, corner(0, 1, 0), corner(1, 1, 0))),
+                        lerp(ty, lerp(tx, corner(0, 0, 1), corner(1, 0, 1)), lerp(tx, corner(0, 1, 1), corner(1, 1, 1))));
+
+                    let y = min_y + local_y;
+                    let state = match density > 0.0 {
+                        true => self.default_block,
+                        false if y < LAVA_LEVEL.min(self.sea_level) => self.lava,
+                        false if y < self.sea_level => self.default_fluid,
+                        false => continue,
+                    };
+                    chunk.set_block(min_x + local_x, y, min_z + local_z, state);
+                }
+            }
+        }
+    }
+
+    /// The middle of a chunk has land at sea level, judging from the density alone.
+    fn is_land(&self, ctx: &mut DensityContext, x: i32, z: i32) -> bool {
+        self.final_density.compute(ctx.at(x * 16 + 8, self.sea_level, z * 16 + 8)) > 0.0
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, x: i32, z: i32, min_y: i32, height: u32) -> Chunk {
+        let mut chunk = Chunk::new(x, z, min_y, height, 0);
+        let mut ctx = DensityContext::new(self.cache_slots);
+        self.fill_biomes(&mut chunk, &mut ctx);
+        self.fill_noise(&mut chunk, &mut ctx);
+        self.surface.apply(&mut chunk);
+        self.carver.carve(&mut chunk);
+        chunk
+    }
+
+    /// The dry ground closest to the origin, searching outwards chunk by chunk.
+    fn spawn_point(&self, min_y: i32) -> SpawnPoint {
+        let mut ctx = DensityContext::new(self.cache_slots);
+        for radius in 0..=SPAWN_SEARCH_RADIUS {
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    if x.abs().max(z.abs()) != radius || !self.is_land(&mut ctx, x, z) {
+                        continue
+                    }
+                    let chunk = self.generate(x, z, min_y, self.shape.height);
+                    let water = self.default_fluid;
+                    let dry = (0..256)
+                        .map(|i| (x * 16 + (i & 15), z * 16 + (i >> 4)))
+                        .map(|(x, z)| (x, chunk.top_y(HeightmapKind::MotionBlocking, x, z), z))
+                        .find(|(x, y, z)| *y >= self.sea_level && chunk.get_block(*x, *y, *z) != water);
+                    if let Some((x, y, z)) = dry {
+                        return SpawnPoint { position: (x, y + 1, z), angle: 0.0 }
+                    }
+                }
+            }
+        }
+        tracing::warn!("Found no land within {} chunks of the origin to spawn on", SPAWN_SEARCH_RADIUS);
+        SpawnPoint { position: (0, self.sea_level + 1, 0), angle: 0.0 }
+    }
+}
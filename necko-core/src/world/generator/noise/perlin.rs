@@ -0,0 +1,160 @@
+use serde::Deserialize;
+use crate::world::generator::noise::random::Xoroshiro;
+
+const GRADIENTS: [[f64; 3]; 16] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0], [0.0, -1.0, 1.0], [-1.0, 1.0, 0.0], [0.0, -1.0, -1.0],
+];
+
+fn smoothstep(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// One octave of Perlin noise with a shuffled permutation table.
+#[derive(Debug)]
+pub struct ImprovedNoise {
+    permutation: [u8; 256],
+    origin: (f64, f64, f64),
+}
+
+impl ImprovedNoise {
+    pub fn new(random: &mut Xoroshiro) -> Self {
+        let origin = (random.next_double() * 256.0, random.next_double() * 256.0, random.next_double() * 256.0);
+        let mut permutation = [0; 256];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        for i in 0..256 {
+            let j = random.next_int(256 - i as i32) as usize;
+            permutation.swap(i, i + j);
+        }
+        ImprovedNoise { permutation, origin }
+    }
+
+    fn p(&self, i: i32) -> i32 {
+        self.permutation[(i & 0xFF) as usize] as i32
+    }
+
+    fn grad_dot(&self, hash: i32, x: f64, y: f64, z: f64) -> f64 {
+        let [gx, gy, gz] = GRADIENTS[(hash & 15) as usize];
+        gx * x + gy * y + gz * z
+    }
+
+    pub fn noise(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.origin.0, y + self.origin.1, z + self.origin.2);
+        let (ix, iy, iz) = (x.floor(), y.floor(), z.floor());
+        let (dx, dy, dz) = (x - ix, y - iy, z - iz);
+        let (ix, iy, iz) = (ix as i32, iy as i32, iz as i32);
+
+        let a = self.p(ix);
+        let b = self.p(ix + 1);
+        let aa = self.p(a + iy);
+        let ab = self.p(a + iy + 1);
+        let ba = self.p(b + iy);
+        let bb = self.p(b + iy + 1);
+
+        let c000 = self.grad_dot(self.p(aa + iz), dx, dy, dz);
+        let c100 = self.grad_dot(self.p(ba + iz), dx - 1.0, dy, dz);
+        let c010 = self.grad_dot(self.p(ab + iz), dx, dy - 1.0, dz);
+        let c110 = self.grad_dot(self.p(bb + iz), dx - 1.0, dy - 1.0, dz);
+        let c001 = self.grad_dot(self.p(aa + iz + 1), dx, dy, dz - 1.0);
+        let c101 = self.grad_dot(self.p(ba + iz + 1), dx - 1.0, dy, dz - 1.0);
+        let c011 = self.grad_dot(self.p(ab + iz + 1), dx, dy - 1.0, dz - 1.0);
+        let c111 = self.grad_dot(self.p(bb + iz + 1), dx - 1.0, dy - 1.0, dz - 1.0);
+
+        let (tx, ty, tz) = (smoothstep(dx), smoothstep(dy), smoothstep(dz));
+        lerp(tz,
+            lerp(ty, lerp(tx, c000, c100), lerp(tx, c010, c110)),
+            lerp(ty, lerp(tx, c001, c101), lerp(tx, c011, c111)))
+    }
+}
+
+/// Keeps coordinates small enough for the noise to stay precise far from the origin.
+fn wrap(value: f64) -> f64 {
+    const ROUND_OFF: f64 = 33554432.0;
+    value - (value / ROUND_OFF + 0.5).floor() * ROUND_OFF
+}
+
+/// Octaves of Perlin noise, each twice the frequency and half the weight of the previous one.
+#[derive(Debug)]
+pub struct PerlinNoise {
+    octaves: Vec<Option<ImprovedNoise>>,
+    amplitudes: Vec<f64>,
+    lowest_input_factor: f64,
+    lowest_value_factor: f64,
+}
+
+impl PerlinNoise {
+    pub fn new(random: &mut Xoroshiro, first_octave: i32, amplitudes: &[f64]) -> Self {
+        let factory = random.fork_positional();
+        let octaves = amplitudes.iter().enumerate()
+            .map(|(i, amplitude)| (*amplitude != 0.0).then(|| {
+                ImprovedNoise::new(&mut factory.from_hash_of(&format!("octave_{}", first_octave + i as i32)))
+            }))
+            .collect();
+        let count = amplitudes.len() as i32;
+        PerlinNoise {
+            octaves,
+            amplitudes: amplitudes.to_vec(),
+            lowest_input_factor: 2f64.powi(first_octave),
+            lowest_value_factor: 2f64.powi(count - 1) / (2f64.powi(count) - 1.0),
+        }
+    }
+
+    pub fn value(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut value = 0.0;
+        let mut input_factor = self.lowest_input_factor;
+        let mut value_factor = self.lowest_value_factor;
+        for (octave, amplitude) in self.octaves.iter().zip(&self.amplitudes) {
+            if let Some(octave) = octave {
+                let noise = octave.noise(wrap(x * input_factor), wrap(y * input_factor), wrap(z * input_factor));
+                value += amplitude * noise * value_factor;
+            }
+            input_factor *= 2.0;
+            value_factor /= 2.0;
+        }
+        value
+    }
+}
+
+/// A `worldgen/noise` entry from a data pack.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseParameters {
+    pub first_octave: i32,
+    pub amplitudes: Vec<f64>,
+}
+
+/// Two Perlin noises sampled at slightly different scales and added, which hides the grid
+/// of a single one. Values are mostly within -1 to 1.
+#[derive(Debug)]
+pub struct NormalNoise {
+    first: PerlinNoise,
+    second: PerlinNoise,
+    value_factor: f64,
+}
+
+impl NormalNoise {
+    const INPUT_FACTOR: f64 = 1.0181268882175227;
+
+    pub fn new(random: &mut Xoroshiro, parameters: &NoiseParameters) -> Self {
+        let amplitudes = &parameters.amplitudes;
+        let first = PerlinNoise::new(random, parameters.first_octave, amplitudes);
+        let second = PerlinNoise::new(random, parameters.first_octave, amplitudes);
+        let used = amplitudes.iter().enumerate().filter(|(_, amplitude)| **amplitude != 0.0).map(|(i, _)| i as i32);
+        let span = used.clone().max().unwrap_or(0) - used.min().unwrap_or(0);
+        let expected_deviation = 0.1 * (1.0 + 1.0 / (span + 1) as f64);
+        NormalNoise { first, second, value_factor: 1.0 / 6.0 / expected_deviation }
+    }
+
+    pub fn value(&self, x: f64, y: f64, z: f64) -> f64 {
+        let second = self.second.value(x * Self::INPUT_FACTOR, y * Self::INPUT_FACTOR, z * Self::INPUT_FACTOR);
+        (self.first.value(x, y, z) + second) * self.value_factor
+    }
+}
@@ -0,0 +1,149 @@
+const GOLDEN_RATIO_64: i64 = 0x9E3779B97F4A7C15u64 as i64;
+const SILVER_RATIO_64: i64 = 0x6A09E667F3BCC909u64 as i64;
+
+fn mix_stafford13(mut value: i64) -> i64 {
+    value = (value ^ ((value as u64) >> 30) as i64).wrapping_mul(0xBF58476D1CE4E5B9u64 as i64);
+    value = (value ^ ((value as u64) >> 27) as i64).wrapping_mul(0x94D049BB133111EBu64 as i64);
+    value ^ ((value as u64) >> 31) as i64
+}
+
+/// Vanilla's random source for world generation since 1.18.
+#[derive(Debug, Clone)]
+pub struct Xoroshiro {
+    lo: i64,
+    hi: i64,
+}
+
+impl Xoroshiro {
+    pub fn new(seed: i64) -> Self {
+        let lo = seed ^ SILVER_RATIO_64;
+        let hi = lo.wrapping_add(GOLDEN_RATIO_64);
+        Self::from_parts(mix_stafford13(lo), mix_stafford13(hi))
+    }
+
+    pub fn from_parts(lo: i64, hi: i64) -> Self {
+        match (lo, hi) {
+            (0, 0) => Xoroshiro { lo: GOLDEN_RATIO_64, hi: SILVER_RATIO_64 },
+            _ => Xoroshiro { lo, hi },
+        }
+    }
+
+    pub fn next_long(&mut self) -> i64 {
+        let (lo, mut hi) = (self.lo, self.hi);
+        let result = lo.wrapping_add(hi).rotate_left(17).wrapping_add(lo);
+        hi ^= lo;
+        self.lo = lo.rotate_left(49) ^ hi ^ (hi << 21);
+        self.hi = hi.rotate_left(28);
+        result
+    }
+
+    fn next_bits(&mut self, bits: u32) -> u64 {
+        (self.next_long() as u64) >> (64 - bits)
+    }
+
+    pub fn next_int(&mut self, bound: i32) -> i32 {
+        let bound = bound as u64;
+        let mut product = (self.next_long() as u32 as u64) * bound;
+        let mut low = product & 0xFFFFFFFF;
+        if low < bound {
+            let threshold = (bound as u32).wrapping_neg() as u64 % bound;
+            while low < threshold {
+                product = (self.next_long() as u32 as u64) * bound;
+                low = product & 0xFFFFFFFF;
+            }
+        }
+        (product >> 32) as i32
+    }
+
+    pub fn next_double(&mut self) -> f64 {
+        self.next_bits(53) as f64 * 1.1102230246251565E-16
+    }
+
+    pub fn next_float(&mut self) -> f32 {
+        self.next_bits(24) as f32 * 5.9604645E-8
+    }
+
+    pub fn fork_positional(&mut self) -> PositionalRandom {
+        PositionalRandom { lo: self.next_long(), hi: self.next_long() }
+    }
+}
+
+/// Creates independent random sources for names and block positions from one seed.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionalRandom {
+    lo: i64,
+    hi: i64,
+}
+
+impl PositionalRandom {
+    pub fn from_hash_of(&self, name: &str) -> Xoroshiro {
+        let hash = md5::compute(name.as_bytes()).0;
+        let lo = i64::from_be_bytes(hash[..8].try_into().unwrap());
+        let hi = i64::from_be_bytes(hash[8..].try_into().unwrap());
+        Xoroshiro::from_parts(lo ^ self.lo, hi ^ self.hi)
+    }
+
+    pub fn at(&self, x: i32, y: i32, z: i32) -> Xoroshiro {
+        Xoroshiro::from_parts(position_seed(x, y, z) ^ self.lo, self.hi)
+    }
+}
+
+/// Vanilla's hash of a block position, used to seed per-block randomness.
+pub fn position_seed(x: i32, y: i32, z: i32) -> i64 {
+    let seed = (x.wrapping_mul(3129871) as i64) ^ (z as i64).wrapping_mul(116129781) ^ y as i64;
+    let seed = seed.wrapping_mul(seed).wrapping_mul(42317861).wrapping_add(seed.wrapping_mul(11));
+    seed >> 16
+}
+
+const LEGACY_MULTIPLIER: i64 = 0x5DEECE66D;
+const LEGACY_MASK: i64 = (1 << 48) - 1;
+
+/// `java.util.Random`, which vanilla still uses for carvers.
+#[derive(Debug, Clone)]
+pub struct LegacyRandom {
+    seed: i64,
+}
+
+impl LegacyRandom {
+    pub fn new(seed: i64) -> Self {
+        LegacyRandom { seed: (seed ^ LEGACY_MULTIPLIER) & LEGACY_MASK }
+    }
+
+    pub fn set_seed(&mut self, seed: i64) {
+        self.seed = (seed ^ LEGACY_MULTIPLIER) & LEGACY_MASK
+    }
+
+    /// Seeds the random for a carver or structure starting in a chunk.
+    pub fn set_large_feature_seed(&mut self, seed: i64, x: i32, z: i32) {
+        self.set_seed(seed);
+        let a = self.next_long();
+        let b = self.next_long();
+        self.set_seed((x as i64).wrapping_mul(a) ^ (z as i64).wrapping_mul(b) ^ seed)
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(LEGACY_MULTIPLIER).wrapping_add(0xB) & LEGACY_MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    pub fn next_int(&mut self, bound: i32) -> i32 {
+        if bound & -bound == bound {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32
+        }
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value
+            }
+        }
+    }
+
+    pub fn next_long(&mut self) -> i64 {
+        ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+    }
+
+    pub fn next_float(&mut self) -> f32 {
+        self.next(24) as f32 * 5.9604645E-8
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use crate::registry::{self, BIOME};
+use crate::world::block::{self, BlockState};
+use crate::world::chunk::Chunk;
+use crate::world::chunk::heightmap::HeightmapKind;
+use crate::world::generator::noise::density::DensityParser;
+use crate::world::generator::noise::perlin::NormalNoise;
+use crate::world::generator::noise::random::PositionalRandom;
+
+/// Blocks covering the stone of a biome: the top block, the ones below it and the floor
+/// under water.
+#[derive(Debug, Clone, Copy)]
+struct Materials {
+    top: BlockState,
+    under: BlockState,
+    underwater: BlockState,
+}
+
+fn state(name: &str) -> BlockState {
+    block::state_by_name(name).expect("surface blocks are known")
+}
+
+fn materials_of(biome: &str) -> Materials {
+    let (top, under, underwater) = match biome.strip_prefix("minecraft:").unwrap_or(biome) {
+        "desert" | "beach" | "snowy_beach" | "warm_ocean" | "lukewarm_ocean" | "deep_lukewarm_ocean" | "river" =>
+            ("sand", "sand", "sand"),
+        "badlands" | "eroded_badlands" | "wooded_badlands" => ("red_sand", "terracotta", "red_sand"),
+        "stony_peaks" | "stony_shore" | "windswept_hills" => ("stone", "stone", "gravel"),
+        "jagged_peaks" | "frozen_peaks" | "snowy_slopes" => ("snow_block", "snow_block", "stone"),
+        "ocean" | "deep_ocean" | "cold_ocean" | "deep_cold_ocean" | "frozen_ocean" | "deep_frozen_ocean" | "frozen_river" =>
+            ("grass_block", "dirt", "gravel"),
+        _ => ("grass_block", "dirt", "dirt"),
+    };
+    let state = |name| state(&format!("minecraft:{name}"));
+    Materials { top: state(top), under: state(under), underwater: state(underwater) }
+}
+
+/// A layer that is solid at and below `true_at`, never at or above `false_at`, and
+/// randomly in between with a chance fading out upwards.
+struct VerticalGradient {
+    random: PositionalRandom,
+    true_at: i32,
+    false_at: i32,
+}
+
+impl VerticalGradient {
+    fn new(random: PositionalRandom, name: &str, true_at: i32, false_at: i32) -> Self {
+        VerticalGradient { random: random.from_hash_of(name).fork_positional(), true_at, false_at }
+    }
+
+    fn test(&self, x: i32, y: i32, z: i32) -> bool {
+        if y <= self.true_at {
+            return true
+        }
+        if y >= self.false_at {
+            return false
+        }
+        let chance = (self.false_at - y) as f64 / (self.false_at - self.true_at) as f64;
+        (self.random.at(x, y, z).next_float() as f64) < chance
+    }
+}
+
+/// Replaces the stone of the noise terrain with the biome's surface, deepslate deep down and
+/// the bedrock floor.
+pub struct SurfaceRules {
+    noise: Arc<NormalNoise>,
+    bedrock: VerticalGradient,
+    deepslate: VerticalGradient,
+    sea_level: i32,
+    stone: BlockState,
+}
+
+impl SurfaceRules {
+    pub fn new(parser: &mut DensityParser, random: PositionalRandom, min_y: i32, sea_level: i32) -> Result<Self, String> {
+        Ok(SurfaceRules {
+            noise: parser.noise("minecraft:surface")?,
+            bedrock: VerticalGradient::new(random, "minecraft:bedrock_floor", min_y, min_y + 5),
+            deepslate: VerticalGradient::new(random, "minecraft:deepslate", 0, 8),
+            sea_level,
+            stone: state("minecraft:stone"),
+        })
+    }
+
+    pub fn apply(&self, chunk: &mut Chunk) {
+        let (water, bedrock, deepslate) = (state("minecraft:water"), state("minecraft:bedrock"), state("minecraft:deepslate"));
+        let min_y = chunk.min_y();
+        for z in chunk.z * 16..chunk.z * 16 + 16 {
+            for x in chunk.x * 16..chunk.x * 16 + 16 {
+                let top = chunk.top_y(HeightmapKind::WorldSurface, x, z);
+                let biome = chunk.get_biome(x, top, z);
+                let materials = materials_of(&registry::entry_name(BIOME, biome as i32).unwrap_or_default());
+                let depth = (self.noise.value(x as f64, 0.0, z as f64) * 2.75 + 3.0).max(1.0) as i32;
+
+                let mut remaining = None;
+                let mut underwater = false;
+                for y in (min_y..=top).rev() {
+                    let current = chunk.get_block(x, y, z);
+                    if current == water {
+                        underwater = true;
+                        continue
+                    }
+                    if current != self.stone {
+                        // the surface is only laid once, caves below keep their stone
+                        if remaining.is_some() {
+                            break
+                        }
+                        continue
+                    }
+                    let replacement = match remaining {
+                        None => {
+                            remaining = Some(depth);
+                            match (underwater, y >= self.sea_level - 1) {
+                                (false, true) => materials.top,
+                                (false, false) => materials.under,
+                                (true, _) => materials.underwater,
+                            }
+                        },
+                        Some(0) => break,
+                        Some(_) if underwater => materials.underwater,
+                        Some(_) => materials.under,
+                    };
+                    remaining = remaining.map(|remaining| remaining - 1);
+                    chunk.set_block(x, y, z, replacement);
+                }
+
+                for y in min_y..min_y + 5 {
+                    if self.bedrock.test(x, y, z) {
+                        chunk.set_block(x, y, z, bedrock);
+                    }
+                }
+                for y in min_y + 5..8 {
+                    if chunk.get_block(x, y, z) == self.stone && self.deepslate.test(x, y, z) {
+                        chunk.set_block(x, y, z, deepslate);
+                    }
+                }
+            }
+        }
+    }
+}
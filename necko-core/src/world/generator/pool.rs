@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use crossbeam::channel::{self, Sender};
+use tokio::sync::oneshot;
+use crate::world::chunk::Chunk;
+use crate::world::generator::ChunkGenerator;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads that only generate chunks, so generation never holds up packet handling on the
+/// async runtime. They stop once the pool is dropped.
+pub struct GenerationPool {
+    sender: Sender<Job>,
+}
+
+impl GenerationPool {
+    /// Starts `threads` workers, or one per CPU core if 0.
+    pub fn new(threads: usize) -> Self {
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |count| count.get()),
+            threads => threads,
+        };
+        let (sender, receiver) = channel::unbounded::<Job>();
+        for i in 0..threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("worldgen-{i}"))
+                .spawn(move || receiver.iter().for_each(|job| job()))
+                .expect("Could not start a world generation thread");
+        }
+        GenerationPool { sender }
+    }
+
+    pub async fn generate(&self, generator: Arc<dyn ChunkGenerator>, x: i32, z: i32, min_y: i32, height: u32) -> Chunk {
+        let (sender, receiver) = oneshot::channel();
+        let job = move || {
+            let _ = sender.send(generator.generate(x, z, min_y, height));
+        };
+        self.sender.send(Box::new(job)).expect("generation threads run while the pool exists");
+        receiver.await.expect("Chunk generation panicked")
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use crate::world::block;
+use crate::world::chunk::{Biome, Chunk};
+use crate::world::generator::{biome_id, ChunkGenerator};
+use crate::world::SpawnPoint;
+
+/// Blocks from the center of the spawn platform to its edges, like vanilla's void preset.
+const PLATFORM_RADIUS: i32 = 16;
+const PLATFORM_Y: i32 = 63;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VoidConfig {
+    /// Places a stone platform under the spawn so players do not fall.
+    pub platform: bool,
+    pub biome: String,
+}
+
+impl Default for VoidConfig {
+    fn default() -> Self {
+        VoidConfig {
+            platform: true,
+            biome: "minecraft:the_void".into(),
+        }
+    }
+}
+
+/// Nothing but air, with an optional platform to spawn on.
+pub struct VoidGenerator {
+    platform: bool,
+    biome: Biome,
+}
+
+impl VoidGenerator {
+    pub fn new(config: &VoidConfig) -> Self {
+        VoidGenerator { platform: config.platform, biome: biome_id(&config.biome) }
+    }
+}
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate(&self, x: i32, z: i32, min_y: i32, height: u32) -> Chunk {
+        let mut chunk = Chunk::new(x, z, min_y, height, self.biome);
+        if !self.platform {
+            return chunk
+        }
+        let stone = block::state_by_name("minecraft:stone").expect("stone is known");
+        let cobblestone = block::state_by_name("minecraft:cobblestone").expect("cobblestone is known");
+        for block_x in x * 16..x * 16 + 16 {
+            for block_z in z * 16..z * 16 + 16 {
+                if block_x.abs() > PLATFORM_RADIUS || block_z.abs() > PLATFORM_RADIUS {
+                    continue
+                }
+                let state = if block_x == 0 && block_z == 0 { cobblestone } else { stone };
+                chunk.set_block(block_x, PLATFORM_Y, block_z, state);
+            }
+        }
+        chunk
+    }
+
+    fn spawn_point(&self, _min_y: i32) -> SpawnPoint {
+        SpawnPoint { position: (0, PLATFORM_Y + 1, 0), angle: 0.0 }
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use necko_protocol::nbt::Tag;
+use crate::world::anvil::compression::Compression;
+use crate::world::{Weather, WorldTime};
+
+pub const LEVEL_PATH: &str = "level.json";
+/// Vanilla's level data, only read since the server can't write all vanilla expects in it.
+pub const VANILLA_LEVEL_PATH: &str = "level.dat";
+
+/// What the server keeps about a world besides its chunks, saved in its directory.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LevelData {
+    pub seed: i64,
+    /// Missing from levels saved before time and weather were.
+    #[serde(default)]
+    pub time: WorldTime,
+    #[serde(default)]
+    pub weather: Weather,
+}
+
+impl LevelData {
+    /// Reads `level.json`, or the seed, time and weather of a world made by vanilla. `None` for a new world.
+    pub fn load(directory: &Path) -> Result<Option<Self>, Error> {
+        let path = directory.join(LEVEL_PATH);
+        if path.exists() {
+            let json = fs::read_to_string(&path)?;
+            return serde_json::from_str(&json).map(Some)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Failed to load {}: {}", path.display(), e)))
+        }
+
+        let path = directory.join(VANILLA_LEVEL_PATH);
+        if path.exists() {
+            let data = Compression::Gzip.decompress(&fs::read(&path)?)?;
+            let (_, nbt) = Tag::read_named(&mut data.as_slice())?;
+            return Self::from_vanilla(&nbt)
+                .map(Some)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("No seed in {}", path.display())))
+        }
+        Ok(None)
+    }
+
+    /// The seed is in `WorldGenSettings` since 1.16 and was `RandomSeed` before.
+    fn from_vanilla(nbt: &Tag) -> Option<Self> {
+        let data = nbt.as_compound()?.get("Data").and_then(Tag::as_compound)?;
+        let seed = data.get("WorldGenSettings").and_then(Tag::as_compound)
+            .and_then(|settings| settings.get("seed"))
+            .or_else(|| data.get("RandomSeed"))
+            .and_then(Tag::as_i64)?;
+        let long = |key| data.get(key).and_then(Tag::as_i64).unwrap_or(0);
+        Some(LevelData {
+            seed,
+            time: WorldTime { game_time: long("Time"), day_time: long("DayTime") },
+            weather: Weather {
+                clear_time: long("clearWeatherTime") as i32,
+                rain_time: long("rainTime") as i32,
+                thunder_time: long("thunderTime") as i32,
+                raining: long("raining") != 0,
+                thundering: long("thundering") != 0,
+            },
+        })
+    }
+
+    /// Writes `level.json` through a temporary file, so a crash never leaves half of it.
+    pub fn save(&self, directory: &Path) -> Result<(), Error> {
+        fs::create_dir_all(directory)?;
+        let json = serde_json::to_string_pretty(self).map_err(Error::other)?;
+        let temporary = directory.join(format!("{LEVEL_PATH}.tmp"));
+        fs::write(&temporary, json)?;
+        fs::rename(temporary, directory.join(LEVEL_PATH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use necko_protocol::nbt::Compound;
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("necko-level-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    fn write_level_dat(directory: &Path, data: Compound) {
+        let mut nbt = BytesMut::new();
+        Tag::Compound(Compound::from([("Data".into(), Tag::Compound(data))])).write_named("", &mut nbt).unwrap();
+        fs::create_dir_all(directory).unwrap();
+        fs::write(directory.join(VANILLA_LEVEL_PATH), Compression::Gzip.compress(&nbt).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_worlds_have_no_level() {
+        assert_eq!(LevelData::load(&temp_dir("missing")).unwrap(), None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let directory = temp_dir("json");
+        let level = LevelData {
+            seed: -42,
+            time: WorldTime { game_time: 100, day_time: 30000 },
+            weather: Weather { rain_time: 50, raining: true, ..Weather::default() },
+        };
+        level.save(&directory).unwrap();
+        assert_eq!(LevelData::load(&directory).unwrap(), Some(level));
+
+        fs::write(directory.join(LEVEL_PATH), r#"{ "seed": 3 }"#).unwrap();
+        assert_eq!(LevelData::load(&directory).unwrap(), Some(LevelData { seed: 3, ..LevelData::default() }),
+            "levels saved without time and weather still load");
+
+        fs::write(directory.join(LEVEL_PATH), "{").unwrap();
+        assert!(LevelData::load(&directory).is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn vanilla_seeds() {
+        let directory = temp_dir("vanilla");
+        let settings = Compound::from([("seed".into(), Tag::Long(123))]);
+        write_level_dat(&directory, Compound::from([("WorldGenSettings".into(), Tag::Compound(settings))]));
+        assert_eq!(LevelData::load(&directory).unwrap(), Some(LevelData { seed: 123, ..LevelData::default() }));
+
+        write_level_dat(&directory, Compound::from([
+            ("RandomSeed".into(), Tag::Long(-7)),
+            ("Time".into(), Tag::Long(500)),
+            ("DayTime".into(), Tag::Long(7000)),
+            ("raining".into(), Tag::Byte(1)),
+            ("rainTime".into(), Tag::Int(1200)),
+            ("thunderTime".into(), Tag::Int(300)),
+        ]));
+        assert_eq!(LevelData::load(&directory).unwrap(), Some(LevelData {
+            seed: -7,
+            time: WorldTime { game_time: 500, day_time: 7000 },
+            weather: Weather { rain_time: 1200, thunder_time: 300, raining: true, ..Weather::default() },
+        }));
+
+        write_level_dat(&directory, Compound::new());
+        assert!(LevelData::load(&directory).is_err());
+
+        let level = LevelData { seed: 5, ..LevelData::default() };
+        level.save(&directory).unwrap();
+        assert_eq!(LevelData::load(&directory).unwrap(), Some(level), "level.json comes first");
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
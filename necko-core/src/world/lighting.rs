@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use crate::world::block::{self, BlockState};
+use crate::world::chunk::{Chunk, SECTION_SIZE};
+use crate::world::chunk::light::{LightArray, MAX_LIGHT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [(0, -1, 0), (0, 1, 0), (-1, 0, 0), (1, 0, 0), (0, 0, -1), (0, 0, 1)];
+
+/// The level light has after moving by `dy` into a block with `opacity`. Full sky light
+/// going down through transparent blocks is the only light that doesn't fade.
+fn propagated(kind: LightKind, level: u8, opacity: u8, dy: i32) -> u8 {
+    if kind == LightKind::Sky && level == MAX_LIGHT && opacity == 0 && dy == -1 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+fn light_arrays(chunk: &Chunk, kind: LightKind) -> &[Option<LightArray>] {
+    match kind {
+        LightKind::Sky => &chunk.sky_light,
+        LightKind::Block => &chunk.block_light,
+    }
+}
+
+fn light_arrays_mut(chunk: &mut Chunk, kind: LightKind) -> &mut [Option<LightArray>] {
+    match kind {
+        LightKind::Sky => &mut chunk.sky_light,
+        LightKind::Block => &mut chunk.block_light,
+    }
+}
+
+/// Whether a chunk has all its light, so it can be sent and doesn't need to be lit again.
+pub fn is_lit(chunk: &Chunk, has_skylight: bool) -> bool {
+    chunk.block_light.iter().all(Option::is_some)
+        && (!has_skylight || chunk.sky_light.iter().all(Option::is_some))
+}
+
+/// A chunk and the loaded ones around it, which is as far as light from the chunk can go.
+/// Light is kept in the sections of the world plus one below and one above it, and the
+/// section above is always in full sky light.
+pub struct LightArea<'a> {
+    center: (i32, i32),
+    chunks: [Option<&'a mut Chunk>; 9],
+    changed: [bool; 9],
+    has_skylight: bool,
+    min_y: i32,
+    /// First Y above the blocks of the world.
+    top_y: i32,
+}
+
+impl<'a> LightArea<'a> {
+    /// An area around the first chunk. Chunks that aren't next to it are left out.
+    pub fn new(center: &'a mut Chunk, neighbors: impl IntoIterator<Item = &'a mut Chunk>, has_skylight: bool) -> Self {
+        let (min_y, top_y) = (center.min_y(), center.min_y() + center.height());
+        let mut area = LightArea {
+            center: (center.x, center.z),
+            chunks: Default::default(),
+            changed: [false; 9],
+            has_skylight,
+            min_y,
+            top_y,
+        };
+        area.chunks[4] = Some(center);
+        for chunk in neighbors {
+            if let Some(index) = area.index(chunk.x, chunk.z) {
+                area.chunks[index] = Some(chunk)
+            }
+        }
+        area
+    }
+
+    /// Chunks whose light changed.
+    pub fn changed(&self) -> Vec<(i32, i32)> {
+        (0..9).filter(|&i| self.changed[i])
+            .map(|i| (self.center.0 + i as i32 % 3 - 1, self.center.1 + i as i32 / 3 - 1))
+            .collect()
+    }
+
+    fn index(&self, chunk_x: i32, chunk_z: i32) -> Option<usize> {
+        let (dx, dz) = (chunk_x - self.center.0 + 1, chunk_z - self.center.1 + 1);
+        ((0..3).contains(&dx) && (0..3).contains(&dz)).then_some((dz * 3 + dx) as usize)
+    }
+
+    fn kinds(&self) -> &'static [LightKind] {
+        if self.has_skylight { &[LightKind::Sky, LightKind::Block] } else { &[LightKind::Block] }
+    }
+
+    fn chunk(&self, x: i32, z: i32) -> Option<&Chunk> {
+        self.chunks[self.index(x >> 4, z >> 4)?].as_deref()
+    }
+
+    fn light_index(&self, y: i32) -> Option<usize> {
+        (self.min_y - SECTION_SIZE..self.top_y + SECTION_SIZE).contains(&y)
+            .then(|| ((y - self.min_y).div_euclid(SECTION_SIZE) + 1) as usize)
+    }
+
+    fn get_light(&self, kind: LightKind, x: i32, y: i32, z: i32) -> u8 {
+        let (Some(chunk), Some(index)) = (self.chunk(x, z), self.light_index(y)) else { return 0 };
+        match &light_arrays(chunk, kind)[index] {
+            Some(light) => light.get((x & 15) as usize, (y & 15) as usize, (z & 15) as usize),
+            None => 0,
+        }
+    }
+
+    fn set_light(&mut self, kind: LightKind, x: i32, y: i32, z: i32, level: u8) {
+        let (Some(chunk_index), Some(index)) = (self.index(x >> 4, z >> 4), self.light_index(y)) else { return };
+        let Some(chunk) = self.chunks[chunk_index].as_deref_mut() else { return };
+        let light = light_arrays_mut(chunk, kind)[index].get_or_insert_with(|| LightArray::filled(0));
+        let (x, y, z) = ((x & 15) as usize, (y & 15) as usize, (z & 15) as usize);
+        if light.get(x, y, z) != level {
+            light.set(x, y, z, level);
+            self.changed[chunk_index] = true
+        }
+    }
+
+    /// Opacity of a block, `None` where there is no light.
+    fn opacity(&self, x: i32, y: i32, z: i32) -> Option<u8> {
+        self.light_index(y)?;
+        Some(block::light_opacity(self.chunk(x, z)?.get_block(x, y, z)))
+    }
+
+    /// Spreads light from the queued positions to wherever it makes blocks brighter.
+    fn increase(&mut self, kind: LightKind, mut queue: VecDeque<(i32, i32, i32)>) {
+        while let Some((x, y, z)) = queue.pop_front() {
+            let level = self.get_light(kind, x, y, z);
+            if level <= 1 {
+                continue
+            }
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                let Some(opacity) = self.opacity(nx, ny, nz) else { continue };
+                let level = propagated(kind, level, opacity, dy);
+                if level > self.get_light(kind, nx, ny, nz) {
+                    self.set_light(kind, nx, ny, nz, level);
+                    queue.push_back((nx, ny, nz))
+                }
+            }
+        }
+    }
+
+    /// Darkens everything lit by the queued positions, which were as bright as their level,
+    /// and spreads light again from the brighter blocks around them.
+    fn decrease(&mut self, kind: LightKind, mut queue: VecDeque<(i32, i32, i32, u8)>) {
+        let mut relight = VecDeque::new();
+        while let Some((x, y, z, level)) = queue.pop_front() {
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if self.opacity(nx, ny, nz).is_none() {
+                    continue
+                }
+                let neighbor = self.get_light(kind, nx, ny, nz);
+                if neighbor == 0 {
+                    continue
+                }
+                let lit_by_us = neighbor < level
+                    || kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && neighbor == MAX_LIGHT;
+                if lit_by_us && !(kind == LightKind::Sky && ny >= self.top_y) {
+                    self.set_light(kind, nx, ny, nz, 0);
+                    queue.push_back((nx, ny, nz, neighbor));
+                    let emission = self.chunk(nx, nz)
+                        .map_or(0, |chunk| block::light_emission(chunk.get_block(nx, ny, nz)));
+                    if kind == LightKind::Block && emission > 0 {
+                        self.set_light(kind, nx, ny, nz, emission);
+                        relight.push_back((nx, ny, nz))
+                    }
+                } else {
+                    relight.push_back((nx, ny, nz))
+                }
+            }
+        }
+        self.increase(kind, relight)
+    }
+
+    /// Lights the center chunk from scratch, on its own.
+    pub fn light_center(&mut self) {
+        let Some(chunk) = self.chunks[4].as_deref_mut() else { return };
+        let sections = chunk.block_light.len();
+        chunk.block_light = vec![Some(LightArray::filled(0)); sections].into_boxed_slice();
+        chunk.sky_light = vec![self.has_skylight.then(|| LightArray::filled(0)); sections].into_boxed_slice();
+        self.changed[4] = true;
+        let chunk = self.chunks[4].as_deref().expect("center chunk is there");
+        let (base_x, base_z) = (chunk.x << 4, chunk.z << 4);
+
+        let mut emitters = VecDeque::new();
+        for (i, section) in chunk.sections().iter().enumerate() {
+            if section.is_empty() {
+                continue
+            }
+            for y in 0..16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let emission = block::light_emission(section.get_block(x, y, z));
+                        if emission > 0 {
+                            let y = self.min_y + i as i32 * SECTION_SIZE + y as i32;
+                            emitters.push_back((base_x + x as i32, y, base_z + z as i32, emission))
+                        }
+                    }
+                }
+            }
+        }
+        let emitters: VecDeque<_> = emitters.into_iter()
+            .map(|(x, y, z, emission)| {
+                self.set_light(LightKind::Block, x, y, z, emission);
+                (x, y, z)
+            })
+            .collect();
+        self.increase(LightKind::Block, emitters);
+
+        if self.has_skylight {
+            let sky = self.sky_columns(base_x, base_z);
+            self.increase(LightKind::Sky, sky)
+        }
+    }
+
+    /// Puts full sky light down every column of the center chunk until it hits a block that
+    /// dims it, and returns where it has to spread sideways and below from.
+    fn sky_columns(&mut self, base_x: i32, base_z: i32) -> VecDeque<(i32, i32, i32)> {
+        let chunk = self.chunks[4].as_deref().expect("center chunk is there");
+        // sections above the highest block are skipped, they are all full sky light
+        let first_empty = chunk.sections().iter().rposition(|section| !section.is_empty())
+            .map_or(0, |i| i + 1);
+        let start_y = self.min_y + first_empty as i32 * SECTION_SIZE;
+        let mut bottoms = [[0; 16]; 16];
+        for (z, row) in bottoms.iter_mut().enumerate() {
+            for (x, bottom) in row.iter_mut().enumerate() {
+                let (x, z) = (base_x + x as i32, base_z + z as i32);
+                let mut y = start_y;
+                while y > self.min_y - SECTION_SIZE && block::light_opacity(chunk.get_block(x, y - 1, z)) == 0 {
+                    y -= 1
+                }
+                *bottom = y
+            }
+        }
+
+        let chunk = self.chunks[4].as_deref_mut().expect("center chunk is there");
+        for index in first_empty + 1..chunk.sky_light.len() {
+            chunk.sky_light[index] = Some(LightArray::filled(MAX_LIGHT))
+        }
+        let mut queue = VecDeque::new();
+        for z in 0..16 {
+            for x in 0..16 {
+                let bottom = bottoms[z][x];
+                let mut highest = bottom;
+                for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, nz) = (x as i32 + dx, z as i32 + dz);
+                    if (0..16).contains(&nx) && (0..16).contains(&nz) {
+                        highest = highest.max(bottoms[nz as usize][nx as usize])
+                    }
+                }
+                let (x, z) = (base_x + x as i32, base_z + z as i32);
+                for y in bottom..start_y {
+                    self.set_light(LightKind::Sky, x, y, z, MAX_LIGHT);
+                }
+                // the lowest lit block spreads down, the ones beside darker columns sideways
+                for y in bottom..=highest {
+                    queue.push_back((x, y, z))
+                }
+            }
+        }
+        queue
+    }
+
+    /// Spreads light over the borders between the center chunk and its neighbors, after the
+    /// center was lit on its own.
+    pub fn join_neighbors(&mut self) {
+        let (center_x, center_z) = self.center;
+        let (base_x, base_z) = (center_x << 4, center_z << 4);
+        for &kind in self.kinds() {
+            let mut queue = VecDeque::new();
+            for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if self.index(center_x + dx, center_z + dz).and_then(|i| self.chunks[i].as_ref()).is_none() {
+                    continue
+                }
+                for along in 0..16 {
+                    // the edge column of the center chunk and the one across the border
+                    let edges = match (dx, dz) {
+                        (-1, _) => [(base_x, base_z + along), (base_x - 1, base_z + along)],
+                        (1, _) => [(base_x + 15, base_z + along), (base_x + 16, base_z + along)],
+                        (_, -1) => [(base_x + along, base_z), (base_x + along, base_z - 1)],
+                        _ => [(base_x + along, base_z + 15), (base_x + along, base_z + 16)],
+                    };
+                    for (x, z) in edges {
+                        for y in self.min_y - SECTION_SIZE..self.top_y + SECTION_SIZE {
+                            if self.get_light(kind, x, y, z) > 1 {
+                                queue.push_back((x, y, z))
+                            }
+                        }
+                    }
+                }
+            }
+            self.increase(kind, queue)
+        }
+    }
+
+    /// Sets a block in the center chunk and updates the light around it.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: BlockState) -> BlockState {
+        let Some(chunk) = self.chunks[4].as_deref_mut() else { return block::AIR };
+        let previous = chunk.set_block(x, y, z, state);
+        if block::light_emission(previous) == block::light_emission(state)
+            && block::light_opacity(previous) == block::light_opacity(state) {
+            return previous
+        }
+
+        for &kind in self.kinds() {
+            let level = self.get_light(kind, x, y, z);
+            self.set_light(kind, x, y, z, 0);
+            self.decrease(kind, VecDeque::from([(x, y, z, level)]));
+            let emission = block::light_emission(state);
+            if kind == LightKind::Block && emission > self.get_light(kind, x, y, z) {
+                self.set_light(kind, x, y, z, emission);
+                self.increase(kind, VecDeque::from([(x, y, z)]))
+            }
+        }
+        // light that only settles while loading isn't worth a save, light an edit changed is
+        for (chunk, changed) in self.chunks.iter_mut().zip(self.changed) {
+            if let Some(chunk) = chunk.as_deref_mut().filter(|_| changed) {
+                chunk.dirty = true
+            }
+        }
+        previous
+    }
+}
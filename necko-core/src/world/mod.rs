@@ -0,0 +1,522 @@
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use necko_protocol::packets::clientbound::play::login::PlayerSpawnInfo;
+use necko_protocol::types::GameMode;
+use sha2::{Digest, Sha256};
+use crate::registry;
+use crate::world::anvil::AnvilStorage;
+use crate::world::anvil::compression::Compression;
+use crate::world::block::BlockState;
+use crate::world::chunk::Chunk;
+use crate::world::generator::noise::random::Xoroshiro;
+use crate::world::generator::pool::GenerationPool;
+use crate::world::generator::{ChunkGenerator, GeneratorConfig};
+use crate::world::level::LevelData;
+use crate::world::lighting::LightArea;
+
+pub mod anvil;
+pub mod block;
+pub mod chunk;
+pub mod generator;
+pub mod level;
+pub mod lighting;
+
+/// Ticks in a Minecraft day.
+pub const DAY_LENGTH: i64 = 24000;
+pub const OVERWORLD: &str = "minecraft:overworld";
+pub const SEA_LEVEL: i32 = 63;
+/// Ticks between the weather changes, picked at random in these ranges like vanilla.
+const RAIN_DELAY: (i32, i32) = (12000, 180000);
+const RAIN_DURATION: (i32, i32) = (12000, 24000);
+const THUNDER_DELAY: (i32, i32) = (12000, 180000);
+const THUNDER_DURATION: (i32, i32) = (3600, 15600);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub name: String,
+    /// Name of the dimension on the client, like `minecraft:overworld`. Every world needs its
+    /// own, `/world` finds worlds by it.
+    pub dimension: String,
+    /// `minecraft:overworld`, `minecraft:overworld_caves`, `minecraft:the_nether` or
+    /// `minecraft:the_end`, which sets the height, sky and light of the world.
+    pub dimension_type: String,
+    /// Directory the world is saved in, the name of the world if empty.
+    pub path: String,
+    /// A number, any text (hashed like vanilla) or empty for a random seed. Only used when
+    /// the world is created, then the seed saved with it is.
+    pub seed: String,
+    /// How chunks are compressed when saved: `zlib`, `gzip`, `lz4` or `none`.
+    pub region_compression: Compression,
+    /// Creates chunks that were never saved: `flat`, `noise` or `void`.
+    pub generator: GeneratorConfig,
+    /// Threads generating chunks, 0 for one per CPU core.
+    pub generation_threads: usize,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig {
+            name: "world".into(),
+            dimension: OVERWORLD.into(),
+            dimension_type: OVERWORLD.into(),
+            path: String::new(),
+            seed: String::new(),
+            region_compression: Compression::Zlib,
+            generator: GeneratorConfig::default(),
+            generation_threads: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorldTime {
+    /// Ticks since the world was created, never changed by commands.
+    pub game_time: i64,
+    pub day_time: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Weather {
+    pub clear_time: i32,
+    pub rain_time: i32,
+    pub thunder_time: i32,
+    pub raining: bool,
+    pub thundering: bool,
+}
+
+/// A loaded chunk shared by everything that uses it.
+pub type ChunkHandle = Arc<RwLock<Chunk>>;
+
+/// A chunk kept in memory while anyone needs it. The cell is shared so a chunk requested
+/// again while it is still loading is only loaded once.
+struct LoadedChunk {
+    viewers: usize,
+    chunk: Arc<OnceCell<ChunkHandle>>,
+    /// Whether light was spread between it and the loaded chunks around it.
+    joined: bool,
+}
+
+/// Where players join and respawn without a bed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnPoint {
+    pub position: (i32, i32, i32),
+    pub angle: f32,
+}
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        SpawnPoint { position: (0, 64, 0), angle: 0.0 }
+    }
+}
+
+impl SpawnPoint {
+    /// Center of the spawn block, where players are placed.
+    pub fn center(&self) -> (f64, f64, f64) {
+        let (x, y, z) = self.position;
+        (x as f64 + 0.5, y as f64, z as f64 + 0.5)
+    }
+}
+
+pub struct World {
+    pub name: String,
+    /// Name of the dimension on the client, like `minecraft:overworld`.
+    pub dimension: String,
+    pub dimension_type: String,
+    pub min_y: i32,
+    pub height: u32,
+    pub has_skylight: bool,
+    pub seed: i64,
+    /// Where the world and its level data are saved.
+    directory: PathBuf,
+    pub spawn: Mutex<SpawnPoint>,
+    pub time: Mutex<WorldTime>,
+    pub weather: Mutex<Weather>,
+    /// Picks how long the weather lasts.
+    random: Mutex<Xoroshiro>,
+    pub storage: AnvilStorage,
+    pub generator: Arc<dyn ChunkGenerator>,
+    generation: GenerationPool,
+    chunks: Mutex<HashMap<(i32, i32), LoadedChunk>>,
+    /// Loaded chunks whose light changed since clients were last sent it.
+    light_updates: Mutex<HashSet<(i32, i32)>>,
+}
+
+impl WorldConfig {
+    pub fn path(&self) -> &str {
+        if self.path.is_empty() { &self.name } else { &self.path }
+    }
+}
+
+/// Checks that worlds can be told apart and have dimension types clients know.
+pub fn check_worlds(worlds: &[WorldConfig]) -> Result<(), String> {
+    if worlds.is_empty() {
+        return Err("At least one world is needed".into())
+    }
+    for (i, world) in worlds.iter().enumerate() {
+        if registry::network_id(registry::DIMENSION_TYPE, &world.dimension_type).is_none() {
+            return Err(format!("Unknown dimension type '{}' of world '{}'", world.dimension_type, world.name))
+        }
+        if let Some(other) = worlds[..i].iter().find(|other| other.name == world.name) {
+            return Err(format!("There are two worlds named '{}'", other.name))
+        }
+        if let Some(other) = worlds[..i].iter().find(|other| other.dimension == world.dimension) {
+            return Err(format!("Worlds '{}' and '{}' both use dimension '{}'", other.name, world.name, world.dimension))
+        }
+        if let Some(other) = worlds[..i].iter().find(|other| other.path() == world.path()) {
+            return Err(format!("Worlds '{}' and '{}' are both saved in '{}'", other.name, world.name, world.path()))
+        }
+    }
+    Ok(())
+}
+
+impl World {
+    /// Opens the world saved in its directory, or creates it.
+    pub fn new(config: &WorldConfig) -> Result<Self, Error> {
+        let (min_y, height) = registry::dimension_height(&config.dimension_type);
+        let directory = Path::new(config.path());
+        let level = match LevelData::load(directory)? {
+            Some(level) => {
+                if !config.seed.trim().is_empty() && parse_seed(&config.seed) != level.seed {
+                    tracing::warn!("World '{}' keeps its saved seed {}, the configured seed only applies to new worlds",
+                        config.name, level.seed);
+                }
+                level
+            }
+            None => LevelData { seed: parse_seed(&config.seed), ..LevelData::default() },
+        };
+        level.save(directory)?;
+
+        let seed = level.seed;
+        let generator: Arc<dyn ChunkGenerator> = config.generator.build(seed).into();
+        Ok(World {
+            name: config.name.clone(),
+            dimension: config.dimension.clone(),
+            dimension_type: config.dimension_type.clone(),
+            min_y,
+            height,
+            has_skylight: registry::has_skylight(&config.dimension_type),
+            seed,
+            directory: directory.to_path_buf(),
+            spawn: Mutex::new(generator.spawn_point(min_y)),
+            time: Mutex::new(level.time),
+            weather: Mutex::new(level.weather),
+            random: Mutex::new(Xoroshiro::new(RandomState::new().hash_one(seed) as i64)),
+            storage: AnvilStorage::new(directory.join("region"), config.region_compression),
+            generator,
+            generation: GenerationPool::new(config.generation_threads),
+            chunks: Mutex::new(HashMap::new()),
+            light_updates: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Loads a saved chunk, or `None` if it was never saved.
+    pub async fn load_chunk(&self, x: i32, z: i32) -> Result<Option<Chunk>, Error> {
+        self.storage.load_chunk(x, z, self.min_y, self.height).await
+    }
+
+    pub async fn save_chunk(&self, chunk: &Chunk) -> Result<(), Error> {
+        self.storage.save_chunk(chunk).await
+    }
+
+    pub async fn generate_chunk(&self, x: i32, z: i32) -> Chunk {
+        self.generation.generate(self.generator.clone(), x, z, self.min_y, self.height).await
+    }
+
+    /// The saved chunk, or a new one if it was never saved. One that could not be read is
+    /// generated in its place but never saved over it.
+    pub async fn load_or_generate_chunk(&self, x: i32, z: i32) -> Chunk {
+        let read_only = match self.load_chunk(x, z).await {
+            Ok(Some(chunk)) => return chunk,
+            Ok(None) => false,
+            Err(e) => {
+                tracing::error!("Could not load chunk ({}, {}), generating it without saving: {}", x, z, e);
+                true
+            }
+        };
+        let mut chunk = self.generate_chunk(x, z).await;
+        chunk.dirty = true;
+        chunk.read_only = read_only;
+        chunk
+    }
+
+    /// Loads or generates a chunk, or shares it if it is already loaded. Every call has to be
+    /// paired with [`World::release_chunk`].
+    pub async fn acquire_chunk(&self, x: i32, z: i32) -> ChunkHandle {
+        let cell = {
+            let mut chunks = self.chunks.lock().unwrap();
+            let loaded = chunks.entry((x, z)).or_insert_with(|| LoadedChunk {
+                viewers: 0,
+                chunk: Arc::new(OnceCell::new()),
+                joined: false,
+            });
+            loaded.viewers += 1;
+            loaded.chunk.clone()
+        };
+        let chunk = cell.get_or_init(|| async {
+            let chunk = self.load_or_generate_chunk(x, z).await;
+            let has_skylight = self.has_skylight;
+            let chunk = tokio::task::spawn_blocking(move || {
+                let mut chunk = chunk;
+                if !lighting::is_lit(&chunk, has_skylight) {
+                    LightArea::new(&mut chunk, [], has_skylight).light_center()
+                }
+                chunk
+            }).await.expect("lighting a chunk does not panic");
+            Arc::new(RwLock::new(chunk))
+        }).await.clone();
+
+        // light crosses over to neighbors once, with the ones that were joined before
+        let neighbors = {
+            let mut chunks = self.chunks.lock().unwrap();
+            match chunks.get_mut(&(x, z)) {
+                Some(loaded) if !loaded.joined => {
+                    loaded.joined = true;
+                    Some(Self::joined_neighbors(&chunks, x, z))
+                }
+                _ => None,
+            }
+        };
+        if let Some(neighbors) = neighbors {
+            let (center, has_skylight) = (chunk.clone(), self.has_skylight);
+            let changed = tokio::task::spawn_blocking(move || {
+                Self::with_area(&center, &neighbors, has_skylight, |area| area.join_neighbors())
+            }).await.expect("lighting a chunk does not panic");
+            self.light_updates.lock().unwrap().extend(changed);
+        }
+        chunk
+    }
+
+    /// The chunk if it is loaded.
+    pub fn loaded_chunk(&self, x: i32, z: i32) -> Option<ChunkHandle> {
+        self.chunks.lock().unwrap().get(&(x, z)).and_then(|loaded| loaded.chunk.get().cloned())
+    }
+
+    fn joined_neighbors(chunks: &HashMap<(i32, i32), LoadedChunk>, x: i32, z: i32) -> Vec<ChunkHandle> {
+        (-1..=1).flat_map(|dx| (-1..=1).map(move |dz| (x + dx, z + dz)))
+            .filter(|&position| position != (x, z))
+            .filter_map(|position| chunks.get(&position))
+            .filter(|loaded| loaded.joined)
+            .filter_map(|loaded| loaded.chunk.get().cloned())
+            .collect()
+    }
+
+    /// Locks a chunk and its neighbors for lighting, always in the same order so two areas
+    /// that overlap can't wait for each other. Returns the chunks whose light changed.
+    fn with_area(center: &ChunkHandle, neighbors: &[ChunkHandle], has_skylight: bool,
+                 update: impl FnOnce(&mut LightArea)) -> Vec<(i32, i32)> {
+        let position = |chunk: &ChunkHandle| {
+            let chunk = chunk.read().unwrap();
+            (chunk.x, chunk.z)
+        };
+        let mut order: Vec<_> = std::iter::once(center).chain(neighbors).collect();
+        order.sort_by_key(|chunk| position(chunk));
+        let center_position = position(center);
+        let mut guards: Vec<_> = order.iter().map(|chunk| chunk.write().unwrap()).collect();
+        let center_index = guards.iter().position(|chunk| (chunk.x, chunk.z) == center_position)
+            .expect("center is locked");
+        let (before, rest) = guards.split_at_mut(center_index);
+        let (center, after) = rest.split_first_mut().expect("center is locked");
+        let neighbors = before.iter_mut().chain(after).map(|guard| &mut **guard);
+        let mut area = LightArea::new(center, neighbors, has_skylight);
+        update(&mut area);
+        area.changed()
+    }
+
+    /// Sets a block in a loaded chunk and updates the light around it. Returns the previous
+    /// block, or `None` if the chunk isn't loaded.
+    pub fn set_block(&self, x: i32, y: i32, z: i32, state: BlockState) -> Option<BlockState> {
+        let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+        let (chunk, neighbors) = {
+            let chunks = self.chunks.lock().unwrap();
+            let chunk = chunks.get(&(chunk_x, chunk_z))?.chunk.get()?.clone();
+            (chunk, Self::joined_neighbors(&chunks, chunk_x, chunk_z))
+        };
+        let mut previous = None;
+        let changed = Self::with_area(&chunk, &neighbors, self.has_skylight, |area| {
+            previous = Some(area.set_block(x, y, z, state))
+        });
+        self.light_updates.lock().unwrap().extend(changed);
+        previous
+    }
+
+    /// Chunks whose light changed since the last call, to be sent to the players that have them.
+    pub fn take_light_updates(&self) -> Vec<(i32, i32)> {
+        self.light_updates.lock().unwrap().drain().collect()
+    }
+
+    /// Gives up a chunk from [`World::acquire_chunk`], saving and unloading it once nobody uses it.
+    pub async fn release_chunk(&self, x: i32, z: i32) {
+        let chunk = {
+            let mut chunks = self.chunks.lock().unwrap();
+            let Some(loaded) = chunks.get_mut(&(x, z)) else { return };
+            loaded.viewers = loaded.viewers.saturating_sub(1);
+            if loaded.viewers > 0 {
+                return
+            }
+            loaded.chunk.get().cloned()
+        };
+        if let Some(chunk) = chunk {
+            self.save_if_dirty(&chunk).await
+        }
+
+        // it stays loaded if it was acquired again while saving
+        let mut chunks = self.chunks.lock().unwrap();
+        if chunks.get(&(x, z)).is_some_and(|loaded| loaded.viewers == 0) {
+            chunks.remove(&(x, z));
+        }
+    }
+
+    /// Chunks kept in memory.
+    pub fn loaded_chunks(&self) -> usize {
+        self.chunks.lock().unwrap().len()
+    }
+
+    /// Saves every loaded chunk that changed.
+    pub async fn save_chunks(&self) {
+        let chunks: Vec<_> = self.chunks.lock().unwrap().values()
+            .filter_map(|loaded| loaded.chunk.get().cloned())
+            .collect();
+        for chunk in &chunks {
+            self.save_if_dirty(chunk).await
+        }
+    }
+
+    /// Saves the seed, time and weather of the world.
+    pub fn save_level(&self) -> Result<(), Error> {
+        LevelData { seed: self.seed, time: self.time(), weather: self.weather() }.save(&self.directory)
+    }
+
+    async fn save_if_dirty(&self, chunk: &ChunkHandle) {
+        let snapshot = {
+            let mut chunk = chunk.write().unwrap();
+            if !chunk.dirty || chunk.read_only {
+                return
+            }
+            chunk.dirty = false;
+            chunk.clone()
+        };
+        if let Err(e) = self.save_chunk(&snapshot).await {
+            tracing::error!("Could not save chunk ({}, {}): {}", snapshot.x, snapshot.z, e)
+        }
+    }
+
+    /// Where the world is saved.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn spawn(&self) -> SpawnPoint {
+        *self.spawn.lock().unwrap()
+    }
+
+    /// The seed as sent to clients, which only need it for biome noise.
+    pub fn hashed_seed(&self) -> i64 {
+        let hash = Sha256::digest(self.seed.to_le_bytes());
+        i64::from_le_bytes(hash[..8].try_into().unwrap())
+    }
+
+    /// What the client needs to know about the world when the player gets into it.
+    pub fn spawn_info(&self, game_mode: GameMode) -> PlayerSpawnInfo {
+        PlayerSpawnInfo {
+            dimension_type: registry::network_id(registry::DIMENSION_TYPE, &self.dimension_type).unwrap_or_default(),
+            dimension: self.dimension.clone(),
+            hashed_seed: self.hashed_seed(),
+            game_mode,
+            previous_game_mode: None,
+            is_debug: false,
+            is_flat: self.generator.is_flat(),
+            last_death_location: None,
+            portal_cooldown: 0,
+            sea_level: SEA_LEVEL,
+        }
+    }
+
+    pub fn time(&self) -> WorldTime {
+        *self.time.lock().unwrap()
+    }
+
+    pub fn set_day_time(&self, day_time: i64) -> WorldTime {
+        let mut time = self.time.lock().unwrap();
+        time.day_time = day_time;
+        *time
+    }
+
+    pub fn add_day_time(&self, ticks: i64) -> WorldTime {
+        let mut time = self.time.lock().unwrap();
+        time.day_time += ticks;
+        *time
+    }
+
+    pub fn weather(&self) -> Weather {
+        *self.weather.lock().unwrap()
+    }
+
+    pub fn set_weather(&self, weather: Weather) {
+        *self.weather.lock().unwrap() = weather
+    }
+
+    /// Moves time forward by a tick and counts the weather down, starting and stopping rain
+    /// and thunder like vanilla. Returns the weather from before the tick.
+    pub fn tick(&self) -> Weather {
+        {
+            let mut time = self.time.lock().unwrap();
+            time.game_time += 1;
+            time.day_time += 1;
+        }
+
+        let mut weather = self.weather.lock().unwrap();
+        let previous = *weather;
+        // there is no weather where there is no sky
+        if !self.has_skylight {
+            return previous
+        }
+        if weather.clear_time > 0 {
+            weather.clear_time -= 1;
+            weather.rain_time = if weather.raining { 0 } else { 1 };
+            weather.thunder_time = if weather.thundering { 0 } else { 1 };
+            weather.raining = false;
+            weather.thundering = false;
+            return previous
+        }
+
+        let mut random = self.random.lock().unwrap();
+        let mut between = |(min, max): (i32, i32)| min + random.next_int(max - min + 1);
+        if weather.thunder_time > 0 {
+            weather.thunder_time -= 1;
+            if weather.thunder_time == 0 {
+                weather.thundering = !weather.thundering
+            }
+        } else {
+            weather.thunder_time = between(if weather.thundering { THUNDER_DURATION } else { THUNDER_DELAY })
+        }
+        if weather.rain_time > 0 {
+            weather.rain_time -= 1;
+            if weather.rain_time == 0 {
+                weather.raining = !weather.raining
+            }
+        } else {
+            weather.rain_time = between(if weather.raining { RAIN_DURATION } else { RAIN_DELAY })
+        }
+        previous
+    }
+}
+
+/// Parses `level-seed` the way vanilla does: numbers as-is, other text by its Java hash code.
+pub fn parse_seed(seed: &str) -> i64 {
+    let seed = seed.trim();
+    if seed.is_empty() {
+        return RandomState::new().hash_one(0) as i64
+    }
+    seed.parse().unwrap_or_else(|_| java_hash_code(seed) as i64)
+}
+
+fn java_hash_code(value: &str) -> i32 {
+    value.encode_utf16().fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32))
+}
@@ -1,6 +1,7 @@
 use std::io::{Error, ErrorKind};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use crate::types::{VarInt, VarIntType};
+use crate::nbt::Tag;
+use crate::types::{SlotData, VarInt, VarIntType};
 use core::str;
 use uuid::Uuid;
 
@@ -63,7 +64,7 @@ impl PacketByteBuffer {
 
         match str::from_utf8(&bytes) {
             Ok(string) => Ok(string.to_string()),
-            Err(e) => Err(Error::new(ErrorKind::Other, e))
+            Err(e) => Err(Error::other(e))
         }
     }
 
@@ -83,42 +84,137 @@ impl PacketByteBuffer {
     pub fn read_u8(&mut self) -> Result<u8, Error> {
         if self.0.has_remaining() {
             Ok(self.0.get_u8())
-        } else { Err(Error::new(ErrorKind::Other, "No bytes left")) }
+        } else { Err(Error::other("No bytes left")) }
     }
 
     pub fn read_u16(&mut self) -> Result<u16, Error> {
         if self.0.has_remaining() {
             Ok(self.0.get_u16())
-        } else { Err(Error::new(ErrorKind::Other, "No bytes left")) }
+        } else { Err(Error::other("No bytes left")) }
     }
     
     pub fn read_i64(&mut self) -> Result<i64, Error> {
         if self.0.has_remaining() {
             Ok(self.0.get_i64())
-        } else { Err(Error::new(ErrorKind::Other, "No bytes left")) }
+        } else { Err(Error::other("No bytes left")) }
     }
 
     pub fn write_i64(&mut self, value: i64) {
         self.0.put_i64(value)
     }
 
+    pub fn write_i32(&mut self, value: i32) {
+        self.0.put_i32(value)
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, Error> {
+        if self.0.remaining() >= 4 {
+            Ok(self.0.get_f32())
+        } else { Err(Error::other("No bytes left")) }
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, Error> {
+        if self.0.remaining() >= 8 {
+            Ok(self.0.get_f64())
+        } else { Err(Error::other("No bytes left")) }
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.0.put_f32(value)
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.0.put_f64(value)
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.0.put_u8(value)
+    }
+
+    pub fn write_i16(&mut self, value: i16) {
+        self.0.put_i16(value)
+    }
+
+    /// Writes a length-prefixed array of longs, used for bit sets and packed storage.
+    pub fn write_long_array(&mut self, value: &[i64]) {
+        self.write_var_int(&VarInt(value.len() as VarIntType));
+        for long in value {
+            self.0.put_i64(*long)
+        }
+    }
+
+    /// Writes a block position packed into a long: 26 bits of X, 26 bits of Z and 12 bits of Y.
+    pub fn write_block_pos(&mut self, (x, y, z): (i32, i32, i32)) {
+        let packed = ((x as i64 & 0x3FFFFFF) << 38) | ((z as i64 & 0x3FFFFFF) << 12) | (y as i64 & 0xFFF);
+        self.0.put_i64(packed)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, Error> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.0.put_u8(value as u8)
+    }
+
     pub fn read_uuid(&mut self) -> Result<Uuid, Error> {
         let mut bytes: [u8; 16] = [0; 16];
         self.copy_to_slice(&mut bytes)?;
         Ok(Uuid::from_slice(&bytes).expect("Failed to parse UUID"))
     }
 
+    pub fn write_uuid(&mut self, value: &Uuid) {
+        self.0.put_slice(value.as_bytes())
+    }
+
+    /// Writes a slot: its item count, then the item and how many components it adds and removes.
+    pub fn write_slot(&mut self, value: Option<&SlotData>) {
+        match value {
+            Some(slot) if slot.count > 0 => {
+                self.write_var_int(&VarInt(slot.count));
+                self.write_var_int(&VarInt(slot.item_id));
+                self.write_var_int(&VarInt(0));
+                self.write_var_int(&VarInt(0));
+            }
+            _ => self.write_var_int(&VarInt(0)),
+        }
+    }
+
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.0.put_slice(value)
+    }
+
+    pub fn write_nbt(&mut self, value: &Tag) {
+        value.write_network(&mut self.0).expect("NBT is too long")
+    }
+
+    pub fn read_nbt(&mut self) -> Result<Tag, Error> {
+        Tag::read_network(&mut self.0)
+    }
+
+    pub fn read_remaining(&mut self) -> Bytes {
+        self.0.split().freeze()
+    }
+
     pub fn copy_to_bytes(&mut self, len: usize) -> Result<Bytes, Error> {
         if self.0.len() >= len {
             Ok(self.0.copy_to_bytes(len))
-        } else { Err(Error::new(ErrorKind::Other, "No bytes left")) }
+        } else { Err(Error::other("No bytes left")) }
     }
     
     pub fn copy_to_slice(&mut self, slice: &mut [u8]) -> Result<(), Error> {
         if self.0.remaining() >= slice.len() {
             self.0.copy_to_slice(slice);
             Ok(())
-        } else { Err(Error::new(ErrorKind::Other, "No bytes left")) }
+        } else { Err(Error::other("No bytes left")) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
     pub fn buffer(&mut self) -> &mut BytesMut {
@@ -1,6 +1,8 @@
 use std::io::{Error, ErrorKind};
+use std::sync::atomic::Ordering;
 use bytes::{Buf, BytesMut};
 use crate::buffer::PacketByteBuffer;
+use crate::metrics::METRICS;
 use crate::packets::{UnsignedPacket, MAX_PACKET_SIZE};
 use crate::types::VarInt;
 
@@ -9,6 +11,12 @@ pub struct Decoder {
     buffer: BytesMut
 }
 
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Decoder {
     pub fn new() -> Self {
         Decoder {
@@ -17,6 +25,14 @@ impl Decoder {
     }
 
     pub fn decode(&mut self) -> Result<Option<UnsignedPacket>, Error> {
+        let result = self.read_packet();
+        if result.is_err() {
+            METRICS.decode_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn read_packet(&mut self) -> Result<Option<UnsignedPacket>, Error> {
         let mut buffer = &self.buffer[..];
 
         let packet_len = match VarInt::read(&mut buffer) {
@@ -30,8 +46,8 @@ impl Decoder {
         };
         let packet_length = packet_len.0;
 
-        if packet_length < 0 || packet_length > MAX_PACKET_SIZE {
-            return Err(Error::new(ErrorKind::Other, "Invalid packet size"))
+        if !(0..=MAX_PACKET_SIZE).contains(&packet_length) {
+            return Err(Error::other("Invalid packet size"))
         }
 
         if buffer.len() < packet_length as usize {
@@ -41,6 +57,7 @@ impl Decoder {
         let packet_len_size = packet_len.size();
 
         self.buffer.advance(packet_len_size);
+        METRICS.bytes_in.fetch_add((packet_len_size + packet_length as usize) as u64, Ordering::Relaxed);
         let mut data = self.buffer.split_to(packet_length as usize);
         buffer = &data[..];
 
@@ -1,6 +1,8 @@
-use std::io::{Error, ErrorKind, Write};
+use std::io::{Error, Write};
+use std::sync::atomic::Ordering;
 use bytes::{BufMut, BytesMut};
 use crate::buffer::PacketByteBuffer;
+use crate::metrics::METRICS;
 use crate::packets::{ClientboundPacket, MAX_PACKET_SIZE};
 use crate::types::VarInt;
 
@@ -9,6 +11,12 @@ pub struct Encoder {
     buffer: BytesMut
 }
 
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Encoder {
     pub fn new() -> Self {
         Encoder {
@@ -25,12 +33,12 @@ impl Encoder {
             .write(&mut writer)?;
         packet.write(&mut buffer);
 
-        writer.write(buffer.buffer())?;
+        writer.write_all(buffer.buffer())?;
 
         let packet_len = self.buffer.len() - start_len;
 
         if packet_len > MAX_PACKET_SIZE as usize {
-            return Err(Error::new(ErrorKind::Other, "Invalid packet size"))
+            return Err(Error::other("Invalid packet size"))
         }
 
         let packet_len_size = VarInt(packet_len as i32).size();
@@ -44,6 +52,7 @@ impl Encoder {
         VarInt(packet_len as i32)
             .write(front)?;
 
+        METRICS.bytes_out.fetch_add((packet_len_size + packet_len) as u64, Ordering::Relaxed);
         Ok(())
     }
 
@@ -2,4 +2,7 @@ pub mod types;
 pub mod packets;
 pub mod buffer;
 pub mod decoder;
-pub mod encoder;
\ No newline at end of file
+pub mod encoder;
+pub mod metrics;
+pub mod nbt;
+pub mod text;
\ No newline at end of file
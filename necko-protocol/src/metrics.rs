@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::packets::serverbound::intention::NextState;
+use crate::types::VarIntType;
+
+/// Packet IDs above this are counted together with it.
+pub const MAX_COUNTED_ID: usize = 0xFF;
+const STATES: usize = 6;
+
+/// Traffic counters shared by all connections. Updating them is a relaxed atomic add, so
+/// they're always on and only read when metrics are scraped.
+pub static METRICS: ProtocolMetrics = ProtocolMetrics::new();
+
+pub struct ProtocolMetrics {
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub decode_errors: AtomicU64,
+    packets_in: PacketCounts,
+    packets_out: PacketCounts,
+}
+
+struct PacketCounts([[AtomicU64; MAX_COUNTED_ID + 1]; STATES]);
+
+impl PacketCounts {
+    const fn new() -> Self {
+        PacketCounts([const { [const { AtomicU64::new(0) }; MAX_COUNTED_ID + 1] }; STATES])
+    }
+
+    fn increment(&self, state: NextState, id: VarIntType) {
+        let id = (id.max(0) as usize).min(MAX_COUNTED_ID);
+        self.0[state_index(state)][id].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Non-zero counts by state and packet ID.
+    fn snapshot(&self) -> Vec<(NextState, VarIntType, u64)> {
+        let mut counts = vec![];
+        for state in ALL_STATES {
+            for (id, count) in self.0[state_index(state)].iter().enumerate() {
+                let count = count.load(Ordering::Relaxed);
+                if count > 0 {
+                    counts.push((state, id as VarIntType, count))
+                }
+            }
+        }
+        counts
+    }
+}
+
+pub const ALL_STATES: [NextState; STATES] = [
+    NextState::None, NextState::Status, NextState::Login,
+    NextState::Transfer, NextState::Configuration, NextState::Play,
+];
+
+fn state_index(state: NextState) -> usize {
+    match state {
+        NextState::None => 0,
+        NextState::Status => 1,
+        NextState::Login => 2,
+        NextState::Transfer => 3,
+        NextState::Configuration => 4,
+        NextState::Play => 5,
+    }
+}
+
+/// Name of a connection state as used in metric labels.
+pub fn state_name(state: NextState) -> &'static str {
+    match state {
+        NextState::None => "handshake",
+        NextState::Status => "status",
+        NextState::Login => "login",
+        NextState::Transfer => "transfer",
+        NextState::Configuration => "configuration",
+        NextState::Play => "play",
+    }
+}
+
+impl Default for ProtocolMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtocolMetrics {
+    pub const fn new() -> Self {
+        ProtocolMetrics {
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            decode_errors: AtomicU64::new(0),
+            packets_in: PacketCounts::new(),
+            packets_out: PacketCounts::new(),
+        }
+    }
+
+    pub fn packet_in(&self, state: NextState, id: VarIntType) {
+        self.packets_in.increment(state, id)
+    }
+
+    pub fn packet_out(&self, state: NextState, id: VarIntType) {
+        self.packets_out.increment(state, id)
+    }
+
+    pub fn packets_in(&self) -> Vec<(NextState, VarIntType, u64)> {
+        self.packets_in.snapshot()
+    }
+
+    pub fn packets_out(&self) -> Vec<(NextState, VarIntType, u64)> {
+        self.packets_out.snapshot()
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use bytes::{Buf, BufMut, BytesMut};
+
+pub const TAG_END: u8 = 0;
+pub const TAG_BYTE: u8 = 1;
+pub const TAG_SHORT: u8 = 2;
+pub const TAG_INT: u8 = 3;
+pub const TAG_LONG: u8 = 4;
+pub const TAG_FLOAT: u8 = 5;
+pub const TAG_DOUBLE: u8 = 6;
+pub const TAG_BYTE_ARRAY: u8 = 7;
+pub const TAG_STRING: u8 = 8;
+pub const TAG_LIST: u8 = 9;
+pub const TAG_COMPOUND: u8 = 10;
+pub const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_LONG_ARRAY: u8 = 12;
+
+/// Compounds nested deeper than this are rejected, like vanilla does.
+const MAX_DEPTH: usize = 512;
+
+pub type Compound = BTreeMap<String, Tag>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(v) => Some(v as f64),
+            Tag::Double(v) => Some(v),
+            _ => self.as_i64().map(|v| v as f64)
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// Writes the tag as a nameless root, the format used in packets since 1.20.2.
+    pub fn write_network(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        buffer.put_u8(self.id());
+        self.write_payload(buffer)
+    }
+
+    /// Writes the tag as a named root, the format used in files.
+    pub fn write_named(&self, name: &str, buffer: &mut BytesMut) -> Result<(), Error> {
+        buffer.put_u8(self.id());
+        write_string(name, buffer)?;
+        self.write_payload(buffer)
+    }
+
+    pub fn read_network(buffer: &mut impl Buf) -> Result<Self, Error> {
+        let id = read_u8(buffer)?;
+        read_payload(id, buffer, 0)
+    }
+
+    pub fn read_named(buffer: &mut impl Buf) -> Result<(String, Self), Error> {
+        let id = read_u8(buffer)?;
+        let name = read_string(buffer)?;
+        Ok((name, read_payload(id, buffer, 0)?))
+    }
+
+    fn write_payload(&self, buffer: &mut BytesMut) -> Result<(), Error> {
+        match self {
+            Tag::Byte(v) => buffer.put_i8(*v),
+            Tag::Short(v) => buffer.put_i16(*v),
+            Tag::Int(v) => buffer.put_i32(*v),
+            Tag::Long(v) => buffer.put_i64(*v),
+            Tag::Float(v) => buffer.put_f32(*v),
+            Tag::Double(v) => buffer.put_f64(*v),
+            Tag::ByteArray(v) => {
+                buffer.put_i32(v.len() as i32);
+                v.iter().for_each(|b| buffer.put_i8(*b));
+            }
+            Tag::String(v) => write_string(v, buffer)?,
+            Tag::List(v) => {
+                buffer.put_u8(v.first().map_or(TAG_END, Tag::id));
+                buffer.put_i32(v.len() as i32);
+                for tag in v {
+                    tag.write_payload(buffer)?;
+                }
+            }
+            Tag::Compound(v) => {
+                for (name, tag) in v {
+                    tag.write_named(name, buffer)?;
+                }
+                buffer.put_u8(TAG_END);
+            }
+            Tag::IntArray(v) => {
+                buffer.put_i32(v.len() as i32);
+                v.iter().for_each(|i| buffer.put_i32(*i));
+            }
+            Tag::LongArray(v) => {
+                buffer.put_i32(v.len() as i32);
+                v.iter().for_each(|l| buffer.put_i64(*l));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Compound> for Tag {
+    fn from(value: Compound) -> Self {
+        Tag::Compound(value)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Tag::String(value.into())
+    }
+}
+
+/// Strings are Java's modified UTF-8: `NUL` takes two bytes and characters outside the BMP
+/// are written as two three-byte surrogates.
+fn write_string(value: &str, buffer: &mut BytesMut) -> Result<(), Error> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000..=0x07FF => bytes.extend([0xC0 | (unit >> 6) as u8, 0x80 | (unit & 0x3F) as u8]),
+            _ => bytes.extend([
+                0xE0 | (unit >> 12) as u8,
+                0x80 | ((unit >> 6) & 0x3F) as u8,
+                0x80 | (unit & 0x3F) as u8,
+            ]),
+        }
+    }
+    if bytes.len() > u16::MAX as usize {
+        return Err(Error::new(ErrorKind::InvalidInput, "NBT string is longer than 65535 bytes"))
+    }
+    buffer.put_u16(bytes.len() as u16);
+    buffer.put_slice(&bytes);
+    Ok(())
+}
+
+fn ensure(buffer: &impl Buf, len: usize) -> Result<(), Error> {
+    if buffer.remaining() < len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "NBT data ended early"))
+    }
+    Ok(())
+}
+
+fn read_u8(buffer: &mut impl Buf) -> Result<u8, Error> {
+    ensure(buffer, 1)?;
+    Ok(buffer.get_u8())
+}
+
+fn read_length(buffer: &mut impl Buf, element_size: usize) -> Result<usize, Error> {
+    ensure(buffer, 4)?;
+    let len = buffer.get_i32();
+    if len < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Negative NBT length"))
+    }
+    ensure(buffer, len as usize * element_size)?;
+    Ok(len as usize)
+}
+
+fn read_string(buffer: &mut impl Buf) -> Result<String, Error> {
+    ensure(buffer, 2)?;
+    let len = buffer.get_u16() as usize;
+    ensure(buffer, len)?;
+    let bytes = buffer.copy_to_bytes(len);
+    let invalid = || Error::new(ErrorKind::InvalidData, "Invalid modified UTF-8 in NBT string");
+    let continuation = |byte: Option<&u8>| match byte {
+        Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u16),
+        _ => Err(invalid()),
+    };
+
+    let mut units = Vec::with_capacity(len);
+    let mut iter = bytes.iter();
+    while let Some(&byte) = iter.next() {
+        units.push(match byte {
+            0x00..=0x7F => byte as u16,
+            0xC0..=0xDF => ((byte & 0x1F) as u16) << 6 | continuation(iter.next())?,
+            0xE0..=0xEF => ((byte & 0x0F) as u16) << 12
+                | continuation(iter.next())? << 6
+                | continuation(iter.next())?,
+            _ => return Err(invalid()),
+        });
+    }
+    String::from_utf16(&units).map_err(|_| invalid())
+}
+
+fn read_payload(id: u8, buffer: &mut impl Buf, depth: usize) -> Result<Tag, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::new(ErrorKind::InvalidData, "NBT is nested too deep"))
+    }
+
+    Ok(match id {
+        TAG_BYTE => { ensure(buffer, 1)?; Tag::Byte(buffer.get_i8()) }
+        TAG_SHORT => { ensure(buffer, 2)?; Tag::Short(buffer.get_i16()) }
+        TAG_INT => { ensure(buffer, 4)?; Tag::Int(buffer.get_i32()) }
+        TAG_LONG => { ensure(buffer, 8)?; Tag::Long(buffer.get_i64()) }
+        TAG_FLOAT => { ensure(buffer, 4)?; Tag::Float(buffer.get_f32()) }
+        TAG_DOUBLE => { ensure(buffer, 8)?; Tag::Double(buffer.get_f64()) }
+        TAG_BYTE_ARRAY => {
+            let len = read_length(buffer, 1)?;
+            Tag::ByteArray((0..len).map(|_| buffer.get_i8()).collect())
+        }
+        TAG_STRING => Tag::String(read_string(buffer)?),
+        TAG_LIST => {
+            let element = read_u8(buffer)?;
+            let len = read_length(buffer, 0)?;
+            let mut list = Vec::with_capacity(len.min(1024));
+            for _ in 0..len {
+                list.push(read_payload(element, buffer, depth + 1)?);
+            }
+            Tag::List(list)
+        }
+        TAG_COMPOUND => {
+            let mut compound = Compound::new();
+            loop {
+                let id = read_u8(buffer)?;
+                if id == TAG_END { break }
+                let name = read_string(buffer)?;
+                compound.insert(name, read_payload(id, buffer, depth + 1)?);
+            }
+            Tag::Compound(compound)
+        }
+        TAG_INT_ARRAY => {
+            let len = read_length(buffer, 4)?;
+            Tag::IntArray((0..len).map(|_| buffer.get_i32()).collect())
+        }
+        TAG_LONG_ARRAY => {
+            let len = read_length(buffer, 8)?;
+            Tag::LongArray((0..len).map(|_| buffer.get_i64()).collect())
+        }
+        id => return Err(Error::new(ErrorKind::InvalidData, format!("Unknown NBT tag {id}")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Tag {
+        let mut nested = Compound::new();
+        nested.insert("name".into(), "minecraft:stone".into());
+        let mut root = Compound::new();
+        root.insert("byte".into(), Tag::Byte(-1));
+        root.insert("short".into(), Tag::Short(300));
+        root.insert("int".into(), Tag::Int(-70_000));
+        root.insert("long".into(), Tag::Long(i64::MIN));
+        root.insert("float".into(), Tag::Float(0.5));
+        root.insert("double".into(), Tag::Double(-2.25));
+        root.insert("bytes".into(), Tag::ByteArray(vec![1, -2, 3]));
+        root.insert("list".into(), Tag::List(vec![Tag::Int(1), Tag::Int(2)]));
+        root.insert("empty".into(), Tag::List(vec![]));
+        root.insert("nested".into(), Tag::Compound(nested));
+        root.insert("ints".into(), Tag::IntArray(vec![i32::MAX, 0]));
+        root.insert("longs".into(), Tag::LongArray(vec![1, -1]));
+        Tag::Compound(root)
+    }
+
+    #[test]
+    fn named_round_trip() {
+        let mut buffer = BytesMut::new();
+        sample().write_named("root", &mut buffer).unwrap();
+        let (name, tag) = Tag::read_named(&mut buffer.freeze()).unwrap();
+        assert_eq!(name, "root");
+        assert_eq!(tag, sample());
+    }
+
+    #[test]
+    fn network_round_trip() {
+        let mut buffer = BytesMut::new();
+        sample().write_network(&mut buffer).unwrap();
+        assert_eq!(buffer[0], TAG_COMPOUND);
+        assert_eq!(buffer[1], TAG_BYTE, "nameless root starts with the first entry");
+        assert_eq!(Tag::read_network(&mut buffer.freeze()).unwrap(), sample());
+    }
+
+    #[test]
+    fn modified_utf8() {
+        let mut buffer = BytesMut::new();
+        let value = format!("a{}\u{e9}\u{1F600}", '\0');
+        Tag::String(value.clone()).write_network(&mut buffer).unwrap();
+        assert_eq!(&buffer[..], &[
+            TAG_STRING, 0, 11,
+            b'a',
+            0xC0, 0x80,
+            0xC3, 0xA9,
+            0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80,
+        ]);
+        assert_eq!(Tag::read_network(&mut buffer.freeze()).unwrap(), Tag::String(value));
+    }
+
+    #[test]
+    fn rejects_long_strings() {
+        let mut buffer = BytesMut::new();
+        assert!(Tag::String("a".repeat(65535)).write_network(&mut buffer).is_ok());
+        let error = Tag::String("\u{e9}".repeat(32768)).write_network(&mut buffer).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        let truncated = [TAG_COMPOUND, TAG_INT, 0, 1, b'a', 0];
+        assert_eq!(Tag::read_network(&mut &truncated[..]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+        let negative = [TAG_INT_ARRAY, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(Tag::read_network(&mut &negative[..]).is_err());
+
+        let unknown = [13];
+        assert!(Tag::read_network(&mut &unknown[..]).is_err());
+
+        let mut deep = vec![TAG_LIST];
+        for _ in 0..=MAX_DEPTH {
+            deep.extend([TAG_LIST, 0, 0, 0, 1]);
+        }
+        assert!(Tag::read_network(&mut &deep[..]).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+/// Ends the configuration phase. The client switches to play once it acknowledges it.
+pub struct FinishConfigurationClientbound;
+
+impl Packet for FinishConfigurationClientbound { const PACKET_ID: VarIntType = 0x03; }
+
+impl ClientboundPacket for FinishConfigurationClientbound {
+    fn write(&self, _buffer: &mut PacketByteBuffer) {}
+}
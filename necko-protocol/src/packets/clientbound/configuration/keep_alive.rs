@@ -0,0 +1,21 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+pub struct KeepAliveClientbound {
+    pub id: i64
+}
+
+impl KeepAliveClientbound {
+    pub fn new(id: i64) -> Self {
+        Self { id }
+    }
+}
+
+impl Packet for KeepAliveClientbound { const PACKET_ID: VarIntType = 0x04; }
+
+impl ClientboundPacket for KeepAliveClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_i64(self.id);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod disconnect;
+pub mod keep_alive;
+pub mod select_known_packs;
+pub mod registry_data;
+pub mod finish_configuration;
\ No newline at end of file
@@ -0,0 +1,33 @@
+use crate::buffer::PacketByteBuffer;
+use crate::nbt::Tag;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// Entries of a synchronized registry, in the order that gives them their network IDs.
+/// Entries without data are looked up by the client in its known packs.
+pub struct RegistryDataClientbound {
+    pub registry: String,
+    pub entries: Vec<(String, Option<Tag>)>,
+}
+
+impl RegistryDataClientbound {
+    pub fn new(registry: &str, entries: Vec<(String, Option<Tag>)>) -> Self {
+        Self { registry: registry.into(), entries }
+    }
+}
+
+impl Packet for RegistryDataClientbound { const PACKET_ID: VarIntType = 0x07; }
+
+impl ClientboundPacket for RegistryDataClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_string(&self.registry);
+        buffer.write_var_int(&VarInt(self.entries.len() as VarIntType));
+        for (id, data) in &self.entries {
+            buffer.write_string(id);
+            buffer.write_bool(data.is_some());
+            if let Some(data) = data {
+                buffer.write_nbt(data)
+            }
+        }
+    }
+}
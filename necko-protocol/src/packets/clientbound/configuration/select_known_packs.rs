@@ -0,0 +1,56 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// A data pack both sides may already have, so registry entries from it can be sent without
+/// their data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownPack {
+    pub namespace: String,
+    pub id: String,
+    pub version: String,
+}
+
+impl KnownPack {
+    pub fn new(namespace: &str, id: &str, version: &str) -> Self {
+        KnownPack { namespace: namespace.into(), id: id.into(), version: version.into() }
+    }
+
+    pub fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(KnownPack {
+            namespace: buffer.read_string()?,
+            id: buffer.read_string()?,
+            version: buffer.read_string()?,
+        })
+    }
+
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_string(&self.namespace);
+        buffer.write_string(&self.id);
+        buffer.write_string(&self.version);
+    }
+}
+
+/// Asks the client which of the server's packs it has, answered with
+/// [`crate::packets::serverbound::configuration::select_known_packs::SelectKnownPacksServerbound`].
+pub struct SelectKnownPacksClientbound {
+    pub packs: Vec<KnownPack>,
+}
+
+impl SelectKnownPacksClientbound {
+    pub fn new(packs: Vec<KnownPack>) -> Self {
+        Self { packs }
+    }
+}
+
+impl Packet for SelectKnownPacksClientbound { const PACKET_ID: VarIntType = 0x0E; }
+
+impl ClientboundPacket for SelectKnownPacksClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&VarInt(self.packs.len() as VarIntType));
+        for pack in &self.packs {
+            pack.write(buffer)
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+pub struct CustomQueryClientbound<'a> {
+    pub message_id: VarInt,
+    pub channel: &'a str,
+    pub data: &'a [u8],
+}
+
+impl<'a> CustomQueryClientbound<'a> {
+    pub fn new(message_id: VarIntType, channel: &'a str, data: &'a [u8]) -> Self {
+        Self { message_id: VarInt(message_id), channel, data }
+    }
+}
+
+impl<'a> Packet for CustomQueryClientbound<'a> { const PACKET_ID: VarIntType = 0x04; }
+
+impl<'a> ClientboundPacket for CustomQueryClientbound<'a> {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.message_id);
+        buffer.write_string(self.channel);
+        buffer.write_bytes(self.data);
+    }
+}
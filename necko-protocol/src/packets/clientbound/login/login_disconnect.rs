@@ -0,0 +1,21 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+pub struct LoginDisconnectClientbound<'a> {
+    pub json_reason: &'a str
+}
+
+impl<'a> LoginDisconnectClientbound<'a> {
+    pub fn new(json_reason: &'a str) -> Self {
+        Self { json_reason }
+    }
+}
+
+impl<'a> Packet for LoginDisconnectClientbound<'a> { const PACKET_ID: VarIntType = 0x00; }
+
+impl<'a> ClientboundPacket for LoginDisconnectClientbound<'a> {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_string(self.json_reason);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{GameProfile, VarInt, VarIntType};
+
+pub struct LoginFinishedClientbound<'a> {
+    pub profile: &'a GameProfile
+}
+
+impl<'a> LoginFinishedClientbound<'a> {
+    pub fn new(profile: &'a GameProfile) -> Self {
+        Self { profile }
+    }
+}
+
+impl<'a> Packet for LoginFinishedClientbound<'a> { const PACKET_ID: VarIntType = 0x02; }
+
+impl<'a> ClientboundPacket for LoginFinishedClientbound<'a> {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_uuid(&self.profile.uuid);
+        buffer.write_string_limited(&self.profile.name, 16);
+        buffer.write_var_int(&VarInt(self.profile.properties.len() as VarIntType));
+        for property in &self.profile.properties {
+            property.write(buffer);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod login_disconnect;
+pub mod login_finished;
+pub mod custom_query;
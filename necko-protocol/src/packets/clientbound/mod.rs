@@ -1 +1,4 @@
-pub mod status;
\ No newline at end of file
+pub mod status;
+pub mod login;
+pub mod configuration;
+pub mod play;
\ No newline at end of file
@@ -0,0 +1,22 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// Ends a chunk batch. The client answers with Chunk Batch Received.
+pub struct ChunkBatchFinishedClientbound {
+    pub batch_size: VarInt,
+}
+
+impl ChunkBatchFinishedClientbound {
+    pub fn new(batch_size: i32) -> Self {
+        Self { batch_size: VarInt(batch_size) }
+    }
+}
+
+impl Packet for ChunkBatchFinishedClientbound { const PACKET_ID: VarIntType = 0x0C; }
+
+impl ClientboundPacket for ChunkBatchFinishedClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.batch_size);
+    }
+}
@@ -0,0 +1,12 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+/// Starts timing a batch of chunks on the client.
+pub struct ChunkBatchStartClientbound;
+
+impl Packet for ChunkBatchStartClientbound { const PACKET_ID: VarIntType = 0x0D; }
+
+impl ClientboundPacket for ChunkBatchStartClientbound {
+    fn write(&self, _buffer: &mut PacketByteBuffer) {}
+}
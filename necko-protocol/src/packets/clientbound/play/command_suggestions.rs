@@ -0,0 +1,40 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::text::TextComponent;
+use crate::types::{VarInt, VarIntType};
+
+pub struct CommandSuggestionsClientbound {
+    pub id: VarInt,
+    pub start: VarInt,
+    pub length: VarInt,
+    pub matches: Vec<(String, Option<TextComponent>)>,
+}
+
+impl CommandSuggestionsClientbound {
+    pub fn new(id: VarIntType, start: usize, length: usize, matches: Vec<(String, Option<TextComponent>)>) -> Self {
+        Self {
+            id: VarInt(id),
+            start: VarInt(start as VarIntType),
+            length: VarInt(length as VarIntType),
+            matches,
+        }
+    }
+}
+
+impl Packet for CommandSuggestionsClientbound { const PACKET_ID: VarIntType = 0x10; }
+
+impl ClientboundPacket for CommandSuggestionsClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.id);
+        buffer.write_var_int(&self.start);
+        buffer.write_var_int(&self.length);
+        buffer.write_var_int(&VarInt(self.matches.len() as VarIntType));
+        for (text, tooltip) in &self.matches {
+            buffer.write_string(text);
+            buffer.write_bool(tooltip.is_some());
+            if let Some(tooltip) = tooltip {
+                buffer.write_nbt(&tooltip.to_nbt());
+            }
+        }
+    }
+}
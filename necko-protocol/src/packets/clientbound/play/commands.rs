@@ -0,0 +1,69 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+pub const NODE_ROOT: u8 = 0x00;
+pub const NODE_LITERAL: u8 = 0x01;
+pub const NODE_ARGUMENT: u8 = 0x02;
+pub const FLAG_EXECUTABLE: u8 = 0x04;
+pub const FLAG_REDIRECT: u8 = 0x08;
+pub const FLAG_SUGGESTIONS: u8 = 0x10;
+
+#[derive(Debug)]
+pub struct ArgumentParser {
+    /// Index in the `command_argument_type` registry.
+    pub id: VarIntType,
+    pub properties: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct CommandNodeData {
+    pub flags: u8,
+    pub children: Vec<VarIntType>,
+    pub redirect: Option<VarIntType>,
+    pub name: Option<String>,
+    pub parser: Option<ArgumentParser>,
+    pub suggestions: Option<String>,
+}
+
+pub struct CommandsClientbound {
+    pub nodes: Vec<CommandNodeData>,
+    pub root: VarIntType,
+}
+
+impl CommandsClientbound {
+    pub fn new(nodes: Vec<CommandNodeData>, root: VarIntType) -> Self {
+        Self { nodes, root }
+    }
+}
+
+impl Packet for CommandsClientbound { const PACKET_ID: VarIntType = 0x11; }
+
+impl ClientboundPacket for CommandsClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&VarInt(self.nodes.len() as VarIntType));
+        for node in &self.nodes {
+            let mut flags = node.flags;
+            if node.redirect.is_some() { flags |= FLAG_REDIRECT }
+            if node.suggestions.is_some() { flags |= FLAG_SUGGESTIONS }
+            buffer.write_bytes(&[flags]);
+
+            buffer.write_var_int(&VarInt(node.children.len() as VarIntType));
+            node.children.iter().for_each(|child| buffer.write_var_int(&VarInt(*child)));
+            if let Some(redirect) = node.redirect {
+                buffer.write_var_int(&VarInt(redirect));
+            }
+            if let Some(name) = &node.name {
+                buffer.write_string(name);
+            }
+            if let Some(parser) = &node.parser {
+                buffer.write_var_int(&VarInt(parser.id));
+                buffer.write_bytes(&parser.properties);
+            }
+            if let Some(suggestions) = &node.suggestions {
+                buffer.write_string(suggestions);
+            }
+        }
+        buffer.write_var_int(&VarInt(self.root));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{SlotData, VarInt, VarIntType};
+
+/// Every slot of a container, sent when it opens or to resync it.
+pub struct ContainerSetContentClientbound {
+    pub container_id: VarInt,
+    pub state_id: VarInt,
+    pub slots: Vec<Option<SlotData>>,
+    /// The stack held by the cursor.
+    pub carried: Option<SlotData>,
+}
+
+impl ContainerSetContentClientbound {
+    pub fn new(container_id: VarIntType, state_id: VarIntType, slots: Vec<Option<SlotData>>) -> Self {
+        Self { container_id: VarInt(container_id), state_id: VarInt(state_id), slots, carried: None }
+    }
+}
+
+impl Packet for ContainerSetContentClientbound { const PACKET_ID: VarIntType = 0x12; }
+
+impl ClientboundPacket for ContainerSetContentClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.container_id);
+        buffer.write_var_int(&self.state_id);
+        buffer.write_var_int(&VarInt(self.slots.len() as VarIntType));
+        for slot in &self.slots {
+            buffer.write_slot(slot.as_ref());
+        }
+        buffer.write_slot(self.carried.as_ref());
+    }
+}
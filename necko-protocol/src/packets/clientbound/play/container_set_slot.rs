@@ -0,0 +1,48 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{SlotData, VarInt, VarIntType};
+
+/// Container ID of the player's own inventory.
+pub const PLAYER_INVENTORY: VarIntType = 0;
+
+pub struct ContainerSetSlotClientbound {
+    pub container_id: VarInt,
+    /// Echoed by the client when it clicks in the container.
+    pub state_id: VarInt,
+    pub slot: i16,
+    pub item: Option<SlotData>,
+}
+
+impl ContainerSetSlotClientbound {
+    pub fn new(container_id: VarIntType, state_id: VarIntType, slot: i16, item: Option<SlotData>) -> Self {
+        Self { container_id: VarInt(container_id), state_id: VarInt(state_id), slot, item }
+    }
+}
+
+impl Packet for ContainerSetSlotClientbound { const PACKET_ID: VarIntType = 0x14; }
+
+impl ClientboundPacket for ContainerSetSlotClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.container_id);
+        buffer.write_var_int(&self.state_id);
+        buffer.write_i16(self.slot);
+        buffer.write_slot(self.item.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write() {
+        let mut buffer = PacketByteBuffer::empty();
+        let item = SlotData { item_id: 300, count: 64 };
+        ContainerSetSlotClientbound::new(PLAYER_INVENTORY, 3, 36, Some(item)).write(&mut buffer);
+        assert_eq!(&buffer.buffer()[..], &[0, 3, 0, 36, 64, 0xAC, 0x02, 0, 0]);
+
+        let mut buffer = PacketByteBuffer::empty();
+        ContainerSetSlotClientbound::new(PLAYER_INVENTORY, 4, 9, None).write(&mut buffer);
+        assert_eq!(&buffer.buffer()[..], &[0, 4, 0, 9, 0]);
+    }
+}
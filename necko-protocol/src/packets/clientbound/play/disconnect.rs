@@ -0,0 +1,22 @@
+use crate::buffer::PacketByteBuffer;
+use crate::nbt::Tag;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+pub struct DisconnectClientbound<'a> {
+    pub reason: &'a Tag
+}
+
+impl<'a> DisconnectClientbound<'a> {
+    pub fn new(reason: &'a Tag) -> Self {
+        Self { reason }
+    }
+}
+
+impl<'a> Packet for DisconnectClientbound<'a> { const PACKET_ID: VarIntType = 0x1D; }
+
+impl<'a> ClientboundPacket for DisconnectClientbound<'a> {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_nbt(self.reason);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+/// Unloads a chunk on the client.
+pub struct ForgetLevelChunkClientbound {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ForgetLevelChunkClientbound {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+}
+
+impl Packet for ForgetLevelChunkClientbound { const PACKET_ID: VarIntType = 0x22; }
+
+impl ClientboundPacket for ForgetLevelChunkClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        // a chunk position packed into a long, x in the low half
+        buffer.write_i64((self.x as u32 as i64) | (self.z as i64) << 32);
+    }
+}
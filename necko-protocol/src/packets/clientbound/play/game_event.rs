@@ -0,0 +1,41 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    NoRespawnBlockAvailable = 0,
+    StartRaining = 1,
+    StopRaining = 2,
+    ChangeGameMode = 3,
+    WinGame = 4,
+    DemoEvent = 5,
+    ArrowHitPlayer = 6,
+    RainLevelChange = 7,
+    ThunderLevelChange = 8,
+    PufferFishSting = 9,
+    GuardianElderEffect = 10,
+    ImmediateRespawn = 11,
+    LimitedCrafting = 12,
+    WaitForLevelChunks = 13,
+}
+
+pub struct GameEventClientbound {
+    pub event: GameEvent,
+    pub value: f32,
+}
+
+impl GameEventClientbound {
+    pub fn new(event: GameEvent, value: f32) -> Self {
+        Self { event, value }
+    }
+}
+
+impl Packet for GameEventClientbound { const PACKET_ID: VarIntType = 0x23; }
+
+impl ClientboundPacket for GameEventClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_bytes(&[self.event as u8]);
+        buffer.write_f32(self.value);
+    }
+}
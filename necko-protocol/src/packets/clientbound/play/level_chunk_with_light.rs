@@ -0,0 +1,46 @@
+use bytes::Bytes;
+use crate::buffer::PacketByteBuffer;
+use crate::nbt::Tag;
+use crate::packets::clientbound::play::light_update::LightData;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+pub struct BlockEntityInfo {
+    /// Position in the chunk as `x << 4 | z`.
+    pub packed_xz: u8,
+    pub y: i16,
+    pub kind: VarInt,
+    pub data: Tag,
+}
+
+/// A whole chunk column: its sections, heightmaps, block entities and light.
+pub struct LevelChunkWithLightClientbound {
+    pub x: i32,
+    pub z: i32,
+    /// Compound of packed long arrays, like `MOTION_BLOCKING`.
+    pub heightmaps: Tag,
+    /// Every section from the bottom of the world, already serialized.
+    pub data: Bytes,
+    pub block_entities: Vec<BlockEntityInfo>,
+    pub light: LightData,
+}
+
+impl Packet for LevelChunkWithLightClientbound { const PACKET_ID: VarIntType = 0x28; }
+
+impl ClientboundPacket for LevelChunkWithLightClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_i32(self.x);
+        buffer.write_i32(self.z);
+        buffer.write_nbt(&self.heightmaps);
+        buffer.write_var_int(&VarInt(self.data.len() as VarIntType));
+        buffer.write_bytes(&self.data);
+        buffer.write_var_int(&VarInt(self.block_entities.len() as VarIntType));
+        for block_entity in &self.block_entities {
+            buffer.write_u8(block_entity.packed_xz);
+            buffer.write_i16(block_entity.y);
+            buffer.write_var_int(&block_entity.kind);
+            buffer.write_nbt(&block_entity.data);
+        }
+        self.light.write(buffer);
+    }
+}
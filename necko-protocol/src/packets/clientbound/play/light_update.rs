@@ -0,0 +1,55 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// Light of a chunk column, one 2048-byte nibble array per section, including the
+/// sections just below and above the world.
+#[derive(Debug, Clone, Default)]
+pub struct LightData {
+    /// Sections that have sky light in `sky_updates`, from the lowest one.
+    pub sky_y_mask: Vec<i64>,
+    pub block_y_mask: Vec<i64>,
+    /// Sections whose sky light is all zero and is not sent.
+    pub empty_sky_y_mask: Vec<i64>,
+    pub empty_block_y_mask: Vec<i64>,
+    pub sky_updates: Vec<Vec<u8>>,
+    pub block_updates: Vec<Vec<u8>>,
+}
+
+impl LightData {
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_long_array(&self.sky_y_mask);
+        buffer.write_long_array(&self.block_y_mask);
+        buffer.write_long_array(&self.empty_sky_y_mask);
+        buffer.write_long_array(&self.empty_block_y_mask);
+        for updates in [&self.sky_updates, &self.block_updates] {
+            buffer.write_var_int(&VarInt(updates.len() as VarIntType));
+            for light in updates {
+                buffer.write_var_int(&VarInt(light.len() as VarIntType));
+                buffer.write_bytes(light);
+            }
+        }
+    }
+}
+
+pub struct LightUpdateClientbound {
+    pub x: i32,
+    pub z: i32,
+    pub light: LightData,
+}
+
+impl LightUpdateClientbound {
+    pub fn new(x: i32, z: i32, light: LightData) -> Self {
+        Self { x, z, light }
+    }
+}
+
+impl Packet for LightUpdateClientbound { const PACKET_ID: VarIntType = 0x2B; }
+
+impl ClientboundPacket for LightUpdateClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&VarInt(self.x));
+        buffer.write_var_int(&VarInt(self.z));
+        self.light.write(buffer);
+    }
+}
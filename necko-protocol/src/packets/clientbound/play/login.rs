@@ -0,0 +1,75 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{GameMode, VarInt, VarIntType};
+
+/// The world the player spawns into, shared by Login and Respawn.
+#[derive(Debug, Clone)]
+pub struct PlayerSpawnInfo {
+    /// Network ID of the dimension type in the synchronized registry.
+    pub dimension_type: VarIntType,
+    pub dimension: String,
+    /// First 8 bytes of the SHA-256 of the world seed, used by the client for biome noise.
+    pub hashed_seed: i64,
+    pub game_mode: GameMode,
+    pub previous_game_mode: Option<GameMode>,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub last_death_location: Option<(String, (i32, i32, i32))>,
+    pub portal_cooldown: VarIntType,
+    pub sea_level: VarIntType,
+}
+
+impl PlayerSpawnInfo {
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&VarInt(self.dimension_type));
+        buffer.write_string(&self.dimension);
+        buffer.write_i64(self.hashed_seed);
+        buffer.write_u8(self.game_mode as u8);
+        buffer.write_u8(self.previous_game_mode.map_or(-1, |game_mode| game_mode as i8) as u8);
+        buffer.write_bool(self.is_debug);
+        buffer.write_bool(self.is_flat);
+        buffer.write_bool(self.last_death_location.is_some());
+        if let Some((dimension, position)) = &self.last_death_location {
+            buffer.write_string(dimension);
+            buffer.write_block_pos(*position);
+        }
+        buffer.write_var_int(&VarInt(self.portal_cooldown));
+        buffer.write_var_int(&VarInt(self.sea_level));
+    }
+}
+
+/// Puts the client into the world after configuration.
+pub struct LoginClientbound {
+    pub entity_id: i32,
+    pub hardcore: bool,
+    pub dimensions: Vec<String>,
+    pub max_players: VarInt,
+    pub view_distance: VarInt,
+    pub simulation_distance: VarInt,
+    pub reduced_debug_info: bool,
+    pub show_death_screen: bool,
+    pub limited_crafting: bool,
+    pub spawn: PlayerSpawnInfo,
+    pub enforces_secure_chat: bool,
+}
+
+impl Packet for LoginClientbound { const PACKET_ID: VarIntType = 0x2C; }
+
+impl ClientboundPacket for LoginClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_i32(self.entity_id);
+        buffer.write_bool(self.hardcore);
+        buffer.write_var_int(&VarInt(self.dimensions.len() as VarIntType));
+        for dimension in &self.dimensions {
+            buffer.write_string(dimension);
+        }
+        buffer.write_var_int(&self.max_players);
+        buffer.write_var_int(&self.view_distance);
+        buffer.write_var_int(&self.simulation_distance);
+        buffer.write_bool(self.reduced_debug_info);
+        buffer.write_bool(self.show_death_screen);
+        buffer.write_bool(self.limited_crafting);
+        self.spawn.write(buffer);
+        buffer.write_bool(self.enforces_secure_chat);
+    }
+}
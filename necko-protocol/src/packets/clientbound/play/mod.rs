@@ -0,0 +1,20 @@
+pub mod disconnect;
+pub mod keep_alive;
+pub mod system_chat;
+pub mod commands;
+pub mod command_suggestions;
+pub mod game_event;
+pub mod player_position;
+pub mod set_time;
+pub mod set_health;
+pub mod login;
+pub mod set_default_spawn_position;
+pub mod set_chunk_cache_center;
+pub mod light_update;
+pub mod level_chunk_with_light;
+pub mod chunk_batch_start;
+pub mod chunk_batch_finished;
+pub mod forget_level_chunk;
+pub mod respawn;
+pub mod container_set_slot;
+pub mod container_set_content;
\ No newline at end of file
@@ -0,0 +1,50 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// Teleports the player. The client answers with an Accept Teleportation carrying the same ID.
+pub struct PlayerPositionClientbound {
+    pub teleport_id: VarInt,
+    pub position: (f64, f64, f64),
+    pub velocity: (f64, f64, f64),
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Which of the fields are relative to the current values, see the `RELATIVE_*` constants.
+    pub flags: i32,
+}
+
+pub const RELATIVE_X: i32 = 0x01;
+pub const RELATIVE_Y: i32 = 0x02;
+pub const RELATIVE_Z: i32 = 0x04;
+pub const RELATIVE_YAW: i32 = 0x08;
+pub const RELATIVE_PITCH: i32 = 0x10;
+
+impl PlayerPositionClientbound {
+    pub fn new(teleport_id: VarIntType, position: (f64, f64, f64), yaw: f32, pitch: f32, flags: i32) -> Self {
+        Self {
+            teleport_id: VarInt(teleport_id),
+            position,
+            velocity: (0.0, 0.0, 0.0),
+            yaw,
+            pitch,
+            flags,
+        }
+    }
+}
+
+impl Packet for PlayerPositionClientbound { const PACKET_ID: VarIntType = 0x42; }
+
+impl ClientboundPacket for PlayerPositionClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.teleport_id);
+        buffer.write_f64(self.position.0);
+        buffer.write_f64(self.position.1);
+        buffer.write_f64(self.position.2);
+        buffer.write_f64(self.velocity.0);
+        buffer.write_f64(self.velocity.1);
+        buffer.write_f64(self.velocity.2);
+        buffer.write_f32(self.yaw);
+        buffer.write_f32(self.pitch);
+        buffer.write_i32(self.flags);
+    }
+}
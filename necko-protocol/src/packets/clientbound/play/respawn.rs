@@ -0,0 +1,25 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::clientbound::play::login::PlayerSpawnInfo;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+/// Keeps attribute modifiers like max health through the respawn.
+pub const KEEP_ATTRIBUTES: u8 = 0x01;
+/// Keeps entity data like health and effects through the respawn.
+pub const KEEP_ENTITY_DATA: u8 = 0x02;
+
+/// Moves the player into another world, or back into the same one after dying.
+pub struct RespawnClientbound {
+    pub spawn: PlayerSpawnInfo,
+    /// What the client keeps of the player, see [`KEEP_ATTRIBUTES`] and [`KEEP_ENTITY_DATA`].
+    pub data_to_keep: u8,
+}
+
+impl Packet for RespawnClientbound { const PACKET_ID: VarIntType = 0x4C; }
+
+impl ClientboundPacket for RespawnClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        self.spawn.write(buffer);
+        buffer.write_u8(self.data_to_keep);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+/// The chunk the player is in. The client drops chunks outside its view distance around it.
+pub struct SetChunkCacheCenterClientbound {
+    pub x: VarInt,
+    pub z: VarInt,
+}
+
+impl SetChunkCacheCenterClientbound {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x: VarInt(x), z: VarInt(z) }
+    }
+}
+
+impl Packet for SetChunkCacheCenterClientbound { const PACKET_ID: VarIntType = 0x58; }
+
+impl ClientboundPacket for SetChunkCacheCenterClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_var_int(&self.x);
+        buffer.write_var_int(&self.z);
+    }
+}
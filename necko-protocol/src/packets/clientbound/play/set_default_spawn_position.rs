@@ -0,0 +1,24 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+/// Where compasses point and players respawn without a bed.
+pub struct SetDefaultSpawnPositionClientbound {
+    pub position: (i32, i32, i32),
+    pub angle: f32,
+}
+
+impl SetDefaultSpawnPositionClientbound {
+    pub fn new(position: (i32, i32, i32), angle: f32) -> Self {
+        Self { position, angle }
+    }
+}
+
+impl Packet for SetDefaultSpawnPositionClientbound { const PACKET_ID: VarIntType = 0x5B; }
+
+impl ClientboundPacket for SetDefaultSpawnPositionClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_block_pos(self.position);
+        buffer.write_f32(self.angle);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::{VarInt, VarIntType};
+
+pub struct SetHealthClientbound {
+    pub health: f32,
+    pub food: VarInt,
+    pub saturation: f32,
+}
+
+impl SetHealthClientbound {
+    pub fn new(health: f32, food: VarIntType, saturation: f32) -> Self {
+        Self { health, food: VarInt(food), saturation }
+    }
+}
+
+impl Packet for SetHealthClientbound { const PACKET_ID: VarIntType = 0x62; }
+
+impl ClientboundPacket for SetHealthClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_f32(self.health);
+        buffer.write_var_int(&self.food);
+        buffer.write_f32(self.saturation);
+    }
+}
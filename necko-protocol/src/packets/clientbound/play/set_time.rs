@@ -0,0 +1,26 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::types::VarIntType;
+
+pub struct SetTimeClientbound {
+    pub game_time: i64,
+    pub day_time: i64,
+    /// Whether the client advances the day time on its own between updates.
+    pub tick_day_time: bool,
+}
+
+impl SetTimeClientbound {
+    pub fn new(game_time: i64, day_time: i64, tick_day_time: bool) -> Self {
+        Self { game_time, day_time, tick_day_time }
+    }
+}
+
+impl Packet for SetTimeClientbound { const PACKET_ID: VarIntType = 0x6B; }
+
+impl ClientboundPacket for SetTimeClientbound {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_i64(self.game_time);
+        buffer.write_i64(self.day_time);
+        buffer.write_bool(self.tick_day_time);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{ClientboundPacket, Packet};
+use crate::text::TextComponent;
+use crate::types::VarIntType;
+
+pub struct SystemChatClientbound<'a> {
+    pub content: &'a TextComponent,
+    /// Shows the message above the hotbar instead of in the chat.
+    pub overlay: bool,
+}
+
+impl<'a> SystemChatClientbound<'a> {
+    pub fn new(content: &'a TextComponent, overlay: bool) -> Self {
+        Self { content, overlay }
+    }
+}
+
+impl<'a> Packet for SystemChatClientbound<'a> { const PACKET_ID: VarIntType = 0x73; }
+
+impl<'a> ClientboundPacket for SystemChatClientbound<'a> {
+    fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_nbt(&self.content.to_nbt());
+        buffer.write_bool(self.overlay);
+    }
+}
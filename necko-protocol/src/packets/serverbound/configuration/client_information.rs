@@ -0,0 +1,14 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::{ClientInformation, VarIntType};
+
+pub struct ClientInformationServerbound(pub ClientInformation);
+
+impl Packet for ClientInformationServerbound { const PACKET_ID: VarIntType = 0x00; }
+
+impl ServerboundPacket for ClientInformationServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(Self(ClientInformation::read(buffer)?))
+    }
+}
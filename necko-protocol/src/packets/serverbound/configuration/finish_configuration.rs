@@ -0,0 +1,14 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub struct FinishConfigurationServerbound;
+
+impl Packet for FinishConfigurationServerbound { const PACKET_ID: VarIntType = 0x03; }
+
+impl ServerboundPacket for FinishConfigurationServerbound {
+    fn read(_buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(FinishConfigurationServerbound)
+    }
+}
@@ -0,0 +1,4 @@
+pub mod keep_alive;
+pub mod select_known_packs;
+pub mod finish_configuration;
+pub mod client_information;
\ No newline at end of file
@@ -0,0 +1,28 @@
+use std::io::{Error, ErrorKind};
+use crate::buffer::PacketByteBuffer;
+use crate::packets::clientbound::configuration::select_known_packs::KnownPack;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+/// Vanilla clients don't send more packs than this.
+const MAX_PACKS: i32 = 64;
+
+/// The packs the client has out of the ones the server offered.
+pub struct SelectKnownPacksServerbound {
+    pub packs: Vec<KnownPack>,
+}
+
+impl Packet for SelectKnownPacksServerbound { const PACKET_ID: VarIntType = 0x07; }
+
+impl ServerboundPacket for SelectKnownPacksServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        let count = buffer.read_var_int()?.0;
+        if !(0..=MAX_PACKS).contains(&count) {
+            return Err(Error::new(ErrorKind::InvalidData, "Too many known packs"))
+        }
+        let packs = (0..count)
+            .map(|_| KnownPack::read(buffer))
+            .collect::<Result<_, _>>()?;
+        Ok(SelectKnownPacksServerbound { packs })
+    }
+}
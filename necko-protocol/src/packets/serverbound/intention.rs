@@ -1,4 +1,4 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use crate::buffer::PacketByteBuffer;
 use crate::packets::{Packet, ServerboundPacket};
 use crate::types::{VarInt, VarIntType};
@@ -8,23 +8,31 @@ pub enum NextState {
     None,
     Status,
     Login,
-    Transfer
+    Transfer,
+    Configuration,
+    Play
 }
 
-impl From<VarInt> for NextState {
-    fn from(value: VarInt) -> Self {
-        match value.0 { 
-            1 => NextState::Status,
-            2 => NextState::Login,
-            3 => NextState::Transfer,
-            _ => unimplemented!("VarInt value out of range")
+impl TryFrom<VarInt> for NextState {
+    type Error = Error;
+
+    fn try_from(value: VarInt) -> Result<Self, Error> {
+        match value.0 {
+            1 => Ok(NextState::Status),
+            2 => Ok(NextState::Login),
+            3 => Ok(NextState::Transfer),
+            state => Err(Error::new(ErrorKind::InvalidData, format!("Unknown next state {state}")))
         }
     }
 }
 
+/// Vanilla caps the address at 255 characters, but BungeeCord forwarding packs the player's
+/// address, UUID and signed skin into it, so this is as lenient as Spigot.
+const MAX_ADDRESS_LENGTH: i32 = i16::MAX as i32;
+
 pub struct IntentionServerbound {
     pub protocol_version: VarInt,
-    pub server_address: String,   // limited to 255
+    pub server_address: String,
     pub server_port: u16,
     pub next_state: NextState
 }
@@ -35,9 +43,50 @@ impl ServerboundPacket for IntentionServerbound {
     fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
         Ok(IntentionServerbound {
             protocol_version: buffer.read_var_int()?,
-            server_address: buffer.read_string_limited(255)?,
+            server_address: buffer.read_string_limited(MAX_ADDRESS_LENGTH)?,
             server_port: buffer.read_u16()?,
-            next_state: buffer.read_var_int()?.into()
+            next_state: buffer.read_var_int()?.try_into()?
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BufMut;
+    use super::*;
+
+    #[test]
+    fn bungeecord_forwarded_address() {
+        let properties = format!(
+            r#"[{{"name":"textures","value":"{}","signature":"{}"}}]"#,
+            "ewogICJ0aW1lc3RhbXAiIDogMTcwMDAwMDAwMDAwMCwKICAicHJvZmlsZUlkIiA6ICI".repeat(8),
+            "SIGNATUREaGVsbG8gd29ybGQgdGhpcyBpcyBhIHNpZ25hdHVyZQ".repeat(14),
+        );
+        let address = format!("play.example.com\0203.0.113.5\0069a79f444e94726a5befca90e38aaf5\0{properties}");
+        assert!(address.len() > 1024);
+
+        let mut buffer = PacketByteBuffer::empty();
+        buffer.write_var_int(&VarInt(769));
+        buffer.write_string(&address);
+        buffer.buffer().put_u16(25565);
+        buffer.write_var_int(&VarInt(2));
+        let packet = IntentionServerbound::read(&mut buffer).unwrap();
+        assert_eq!(packet.server_address, address);
+        assert_eq!(packet.server_port, 25565);
+        assert_eq!(packet.next_state, NextState::Login);
+    }
+
+    #[test]
+    fn unknown_next_state() {
+        for state in [0, 4, -1] {
+            let mut buffer = PacketByteBuffer::empty();
+            buffer.write_var_int(&VarInt(769));
+            buffer.write_string("localhost");
+            buffer.buffer().put_u16(25565);
+            buffer.write_var_int(&VarInt(state));
+            let error = IntentionServerbound::read(&mut buffer).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{state}");
+        }
+        assert_eq!(NextState::try_from(VarInt(1)).unwrap(), NextState::Status);
+    }
+}
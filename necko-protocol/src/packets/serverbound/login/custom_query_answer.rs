@@ -0,0 +1,24 @@
+use std::io::Error;
+use bytes::Bytes;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::{VarInt, VarIntType};
+
+pub struct CustomQueryAnswerServerbound {
+    pub message_id: VarInt,
+    pub data: Option<Bytes>,
+}
+
+impl Packet for CustomQueryAnswerServerbound { const PACKET_ID: VarIntType = 0x02; }
+
+impl ServerboundPacket for CustomQueryAnswerServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(CustomQueryAnswerServerbound {
+            message_id: buffer.read_var_int()?,
+            data: match buffer.read_bool()? {
+                true => Some(buffer.read_remaining()),
+                false => None
+            },
+        })
+    }
+}
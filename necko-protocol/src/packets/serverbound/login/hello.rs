@@ -5,8 +5,8 @@ use crate::packets::{Packet, ServerboundPacket};
 use crate::types::VarIntType;
 
 pub struct HelloServerbound {
-    name: String,
-    uuid: Uuid,
+    pub name: String,
+    pub uuid: Uuid,
 }
 
 impl Packet for HelloServerbound { const PACKET_ID: VarIntType = 0x00; }
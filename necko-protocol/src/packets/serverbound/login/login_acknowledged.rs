@@ -0,0 +1,14 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub struct LoginAcknowledgedServerbound;
+
+impl Packet for LoginAcknowledgedServerbound { const PACKET_ID: VarIntType = 0x03; }
+
+impl ServerboundPacket for LoginAcknowledgedServerbound {
+    fn read(_: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(LoginAcknowledgedServerbound)
+    }
+}
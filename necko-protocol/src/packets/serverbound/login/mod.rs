@@ -1 +1,3 @@
-pub mod hello;
\ No newline at end of file
+pub mod hello;
+pub mod custom_query_answer;
+pub mod login_acknowledged;
\ No newline at end of file
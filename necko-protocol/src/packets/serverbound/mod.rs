@@ -1,3 +1,5 @@
 pub mod intention;
 pub mod status;
-pub mod login;
\ No newline at end of file
+pub mod login;
+pub mod configuration;
+pub mod play;
\ No newline at end of file
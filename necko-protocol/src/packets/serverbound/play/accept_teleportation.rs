@@ -0,0 +1,19 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+/// Confirms a Synchronize Player Position with the same ID.
+pub struct AcceptTeleportationServerbound {
+    pub teleport_id: VarIntType,
+}
+
+impl Packet for AcceptTeleportationServerbound { const PACKET_ID: VarIntType = 0x00; }
+
+impl ServerboundPacket for AcceptTeleportationServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(AcceptTeleportationServerbound {
+            teleport_id: buffer.read_var_int()?.0
+        })
+    }
+}
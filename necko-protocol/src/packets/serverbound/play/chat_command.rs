@@ -0,0 +1,19 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub struct ChatCommandServerbound {
+    /// The command typed by the player, without the leading `/`.
+    pub command: String
+}
+
+impl Packet for ChatCommandServerbound { const PACKET_ID: VarIntType = 0x05; }
+
+impl ServerboundPacket for ChatCommandServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(ChatCommandServerbound {
+            command: buffer.read_string_limited(256)?
+        })
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+/// Sent instead of [`super::chat_command::ChatCommandServerbound`] when the command has
+/// signable arguments. Signatures and acknowledgements are ignored.
+pub struct ChatCommandSignedServerbound {
+    pub command: String,
+    pub timestamp: i64,
+    pub salt: i64,
+}
+
+impl Packet for ChatCommandSignedServerbound { const PACKET_ID: VarIntType = 0x06; }
+
+impl ServerboundPacket for ChatCommandSignedServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        let packet = ChatCommandSignedServerbound {
+            command: buffer.read_string_limited(256)?,
+            timestamp: buffer.read_i64()?,
+            salt: buffer.read_i64()?,
+        };
+        buffer.read_remaining();
+        Ok(packet)
+    }
+}
@@ -0,0 +1,19 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+/// Acknowledges a chunk batch with how many chunks per tick the client can keep up with.
+pub struct ChunkBatchReceivedServerbound {
+    pub desired_chunks_per_tick: f32,
+}
+
+impl Packet for ChunkBatchReceivedServerbound { const PACKET_ID: VarIntType = 0x09; }
+
+impl ServerboundPacket for ChunkBatchReceivedServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(ChunkBatchReceivedServerbound {
+            desired_chunks_per_tick: buffer.read_f32()?
+        })
+    }
+}
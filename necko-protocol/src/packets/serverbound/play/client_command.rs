@@ -0,0 +1,23 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::{VarInt, VarIntType};
+
+/// Asks to respawn from the death screen.
+pub const PERFORM_RESPAWN: VarIntType = 0;
+/// Asks for the statistics screen.
+pub const REQUEST_STATS: VarIntType = 1;
+
+pub struct ClientCommandServerbound {
+    pub action: VarInt,
+}
+
+impl Packet for ClientCommandServerbound { const PACKET_ID: VarIntType = 0x0A; }
+
+impl ServerboundPacket for ClientCommandServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(ClientCommandServerbound {
+            action: buffer.read_var_int()?
+        })
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub struct CommandSuggestionServerbound {
+    pub id: VarIntType,
+    /// Everything left of the cursor, including the leading `/`.
+    pub command: String,
+}
+
+impl Packet for CommandSuggestionServerbound { const PACKET_ID: VarIntType = 0x0D; }
+
+impl ServerboundPacket for CommandSuggestionServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(CommandSuggestionServerbound {
+            id: buffer.read_var_int()?.0,
+            command: buffer.read_string_limited(32500)?,
+        })
+    }
+}
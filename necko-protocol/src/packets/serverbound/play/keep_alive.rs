@@ -0,0 +1,18 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub struct KeepAliveServerbound {
+    pub id: i64
+}
+
+impl Packet for KeepAliveServerbound { const PACKET_ID: VarIntType = 0x1A; }
+
+impl ServerboundPacket for KeepAliveServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(KeepAliveServerbound {
+            id: buffer.read_i64()?
+        })
+    }
+}
@@ -0,0 +1,10 @@
+pub mod keep_alive;
+pub mod chat_command;
+pub mod chat_command_signed;
+pub mod command_suggestion;
+pub mod accept_teleportation;
+pub mod move_player;
+pub mod player_loaded;
+pub mod client_information;
+pub mod chunk_batch_received;
+pub mod client_command;
\ No newline at end of file
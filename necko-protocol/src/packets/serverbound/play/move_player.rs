@@ -0,0 +1,66 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+pub const FLAG_ON_GROUND: u8 = 0x01;
+pub const FLAG_HORIZONTAL_COLLISION: u8 = 0x02;
+
+/// Any of the four movement packets. Fields the packet doesn't carry are `None`.
+pub struct MovePlayer {
+    pub position: Option<(f64, f64, f64)>,
+    pub rotation: Option<(f32, f32)>,
+    pub flags: u8,
+}
+
+impl MovePlayer {
+    pub fn on_ground(&self) -> bool {
+        self.flags & FLAG_ON_GROUND != 0
+    }
+
+    fn read(buffer: &mut PacketByteBuffer, position: bool, rotation: bool) -> Result<Self, Error> {
+        let position = match position {
+            true => Some((buffer.read_f64()?, buffer.read_f64()?, buffer.read_f64()?)),
+            false => None,
+        };
+        let rotation = match rotation {
+            true => Some((buffer.read_f32()?, buffer.read_f32()?)),
+            false => None,
+        };
+        Ok(MovePlayer { position, rotation, flags: buffer.read_u8()? })
+    }
+}
+
+pub struct MovePlayerPosServerbound(pub MovePlayer);
+pub struct MovePlayerPosRotServerbound(pub MovePlayer);
+pub struct MovePlayerRotServerbound(pub MovePlayer);
+pub struct MovePlayerStatusOnlyServerbound(pub MovePlayer);
+
+impl Packet for MovePlayerPosServerbound { const PACKET_ID: VarIntType = 0x1C; }
+impl Packet for MovePlayerPosRotServerbound { const PACKET_ID: VarIntType = 0x1D; }
+impl Packet for MovePlayerRotServerbound { const PACKET_ID: VarIntType = 0x1E; }
+impl Packet for MovePlayerStatusOnlyServerbound { const PACKET_ID: VarIntType = 0x1F; }
+
+impl ServerboundPacket for MovePlayerPosServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(Self(MovePlayer::read(buffer, true, false)?))
+    }
+}
+
+impl ServerboundPacket for MovePlayerPosRotServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(Self(MovePlayer::read(buffer, true, true)?))
+    }
+}
+
+impl ServerboundPacket for MovePlayerRotServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(Self(MovePlayer::read(buffer, false, true)?))
+    }
+}
+
+impl ServerboundPacket for MovePlayerStatusOnlyServerbound {
+    fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(Self(MovePlayer::read(buffer, false, false)?))
+    }
+}
@@ -0,0 +1,15 @@
+use std::io::Error;
+use crate::buffer::PacketByteBuffer;
+use crate::packets::{Packet, ServerboundPacket};
+use crate::types::VarIntType;
+
+/// Sent once the client left the loading screen after joining or respawning.
+pub struct PlayerLoadedServerbound;
+
+impl Packet for PlayerLoadedServerbound { const PACKET_ID: VarIntType = 0x2A; }
+
+impl ServerboundPacket for PlayerLoadedServerbound {
+    fn read(_buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(PlayerLoadedServerbound)
+    }
+}
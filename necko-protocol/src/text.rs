@@ -0,0 +1,86 @@
+use crate::nbt::{Compound, Tag};
+
+/// A chat component, sent as NBT in configuration and play packets.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextComponent {
+    pub text: String,
+    pub color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub extra: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    pub fn text(text: impl Into<String>) -> Self {
+        TextComponent { text: text.into(), ..Default::default() }
+    }
+
+    pub fn color(mut self, color: &str) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn append(mut self, component: TextComponent) -> Self {
+        self.extra.push(component);
+        self
+    }
+
+    /// The text without any formatting, as shown in logs.
+    pub fn plain(&self) -> String {
+        let mut plain = self.text.clone();
+        self.extra.iter().for_each(|extra| plain.push_str(&extra.plain()));
+        plain
+    }
+
+    pub fn to_nbt(&self) -> Tag {
+        if self.color.is_none() && self.bold.is_none() && self.italic.is_none() && self.extra.is_empty() {
+            return Tag::String(self.text.clone())
+        }
+
+        let mut compound = Compound::new();
+        compound.insert("text".into(), Tag::String(self.text.clone()));
+        if let Some(color) = &self.color {
+            compound.insert("color".into(), Tag::String(color.clone()));
+        }
+        if let Some(bold) = self.bold {
+            compound.insert("bold".into(), Tag::Byte(bold as i8));
+        }
+        if let Some(italic) = self.italic {
+            compound.insert("italic".into(), Tag::Byte(italic as i8));
+        }
+        if !self.extra.is_empty() {
+            let extra = self.extra.iter().map(|extra| Tag::Compound(extra.to_compound())).collect();
+            compound.insert("extra".into(), Tag::List(extra));
+        }
+        Tag::Compound(compound)
+    }
+
+    fn to_compound(&self) -> Compound {
+        match self.to_nbt() {
+            Tag::Compound(compound) => compound,
+            tag => Compound::from([("text".into(), tag)]),
+        }
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(value: &str) -> Self {
+        TextComponent::text(value)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(value: String) -> Self {
+        TextComponent::text(value)
+    }
+}
@@ -1,5 +1,7 @@
 use std::io::{Error, ErrorKind, Write};
 use bytes::{Buf};
+use uuid::Uuid;
+use crate::buffer::PacketByteBuffer;
 
 pub type VarIntType = i32;
 
@@ -21,7 +23,7 @@ impl VarInt {
         
         for i in 0..Self::MAX_SIZE {
             if !buffer.has_remaining() {
-                return Err(Error::new(ErrorKind::Other, "Buffer is corrupted"))
+                return Err(Error::other("Buffer is corrupted"))
             }
             let byte = buffer.get_u8();
             value |= (VarIntType::from(byte) & 0x7F) << (7 * i);
@@ -48,4 +50,108 @@ impl VarInt {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl ProfileProperty {
+    pub fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(ProfileProperty {
+            name: buffer.read_string()?,
+            value: buffer.read_string()?,
+            signature: match buffer.read_bool()? {
+                true => Some(buffer.read_string()?),
+                false => None
+            },
+        })
+    }
+
+    pub fn write(&self, buffer: &mut PacketByteBuffer) {
+        buffer.write_string(&self.name);
+        buffer.write_string(&self.value);
+        buffer.write_bool(self.signature.is_some());
+        if let Some(signature) = &self.signature {
+            buffer.write_string(signature);
+        }
+    }
+}
+
+/// Client settings sent during configuration and whenever they change in game.
+#[derive(Debug, Clone)]
+pub struct ClientInformation {
+    pub language: String,
+    /// Render distance in chunks.
+    pub view_distance: u8,
+    pub chat_mode: VarIntType,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    pub main_hand: VarIntType,
+    pub text_filtering: bool,
+    pub allow_server_listings: bool,
+    pub particle_status: VarIntType,
+}
+
+impl ClientInformation {
+    pub fn read(buffer: &mut PacketByteBuffer) -> Result<Self, Error> {
+        Ok(ClientInformation {
+            language: buffer.read_string_limited(16)?,
+            view_distance: buffer.read_u8()?,
+            chat_mode: buffer.read_var_int()?.0,
+            chat_colors: buffer.read_bool()?,
+            displayed_skin_parts: buffer.read_u8()?,
+            main_hand: buffer.read_var_int()?.0,
+            text_filtering: buffer.read_bool()?,
+            allow_server_listings: buffer.read_bool()?,
+            particle_status: buffer.read_var_int()?.0,
+        })
+    }
+}
+
+/// An item stack in a container slot, without data components.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotData {
+    pub item_id: VarIntType,
+    pub count: VarIntType,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameProfile {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<ProfileProperty>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [GameMode::Survival, GameMode::Creative, GameMode::Adventure, GameMode::Spectator];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Adventure => "adventure",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        GameMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        GameMode::ALL.into_iter().find(|mode| *mode as i32 == id)
+    }
+}